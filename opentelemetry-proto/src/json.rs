@@ -0,0 +1,289 @@
+//! Configurable OTLP/JSON encoding of trace requests.
+//!
+//! The serde implementations of the generated types follow the [OTLP/JSON]
+//! specification: trace and span ids are hex encoded strings and 64 bit
+//! integers are encoded as decimal strings. Some consumers expect a different
+//! flavor, such as base64 encoded ids (the default proto3 JSON mapping for
+//! bytes) or plain JSON numbers. [`OtlpJsonEncoder`] produces and reads those
+//! variants.
+//!
+//! [OTLP/JSON]: https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding
+use crate::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::de::Error as _;
+use serde_json::Value;
+
+/// Fields holding trace or span ids.
+const ID_FIELDS: [&str; 3] = ["traceId", "spanId", "parentSpanId"];
+
+/// Fields holding 64 bit integers, encoded as strings by default.
+const INT_FIELDS: [&str; 5] = [
+    "startTimeUnixNano",
+    "endTimeUnixNano",
+    "timeUnixNano",
+    "observedTimeUnixNano",
+    "intValue",
+];
+
+/// How trace and span ids are encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdEncoding {
+    /// Lowercase hex strings, as required by the OTLP/JSON specification.
+    #[default]
+    Hex,
+    /// Standard base64 strings, as in the canonical proto3 JSON mapping.
+    Base64,
+}
+
+/// Encoder and decoder for OTLP/JSON trace requests.
+///
+/// By default it produces output following the OTLP/JSON specification, with
+/// hex encoded ids and 64 bit integers encoded as strings. Use
+/// [`with_id_encoding`] and [`with_int_as_string`] to target collectors that
+/// expect a different variant. Decoding expects input in the configured
+/// variant, so an encoder can read back anything it wrote.
+///
+/// Link ids are encoded the same way as span ids.
+///
+/// # Examples
+///
+/// ```ignore
+/// use opentelemetry_proto::json::{IdEncoding, OtlpJsonEncoder};
+///
+/// let encoder = OtlpJsonEncoder::new()
+///     .with_id_encoding(IdEncoding::Base64)
+///     .with_int_as_string(false);
+/// let json = encoder.encode_traces(&request)?;
+/// ```
+///
+/// [`with_id_encoding`]: OtlpJsonEncoder::with_id_encoding
+/// [`with_int_as_string`]: OtlpJsonEncoder::with_int_as_string
+#[derive(Clone, Copy, Debug)]
+pub struct OtlpJsonEncoder {
+    id_encoding: IdEncoding,
+    int_as_string: bool,
+}
+
+impl Default for OtlpJsonEncoder {
+    fn default() -> Self {
+        OtlpJsonEncoder {
+            id_encoding: IdEncoding::Hex,
+            int_as_string: true,
+        }
+    }
+}
+
+impl OtlpJsonEncoder {
+    /// Create an encoder producing spec compliant OTLP/JSON.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how trace and span ids are encoded. Defaults to [`IdEncoding::Hex`].
+    pub fn with_id_encoding(mut self, id_encoding: IdEncoding) -> Self {
+        self.id_encoding = id_encoding;
+        self
+    }
+
+    /// Set whether 64 bit integers (timestamps and integer attribute values)
+    /// are encoded as strings or as JSON numbers. Defaults to `true`.
+    pub fn with_int_as_string(mut self, int_as_string: bool) -> Self {
+        self.int_as_string = int_as_string;
+        self
+    }
+
+    /// Encode a trace export request as JSON.
+    pub fn encode_traces(
+        &self,
+        request: &ExportTraceServiceRequest,
+    ) -> Result<String, serde_json::Error> {
+        let mut value = serde_json::to_value(request)?;
+        self.encode_value(&mut value)?;
+        serde_json::to_string(&value)
+    }
+
+    /// Decode a trace export request from JSON produced with the same options.
+    pub fn decode_traces(
+        &self,
+        json: &str,
+    ) -> Result<ExportTraceServiceRequest, serde_json::Error> {
+        let mut value = serde_json::from_str(json)?;
+        self.decode_value(&mut value, false)?;
+        serde_json::from_value(value)
+    }
+
+    fn encode_value(&self, value: &mut Value) -> Result<(), serde_json::Error> {
+        match value {
+            Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if ID_FIELDS.contains(&key.as_str()) {
+                        let bytes = id_bytes(field)?;
+                        *field = Value::String(match self.id_encoding {
+                            IdEncoding::Hex => hex::encode(bytes),
+                            IdEncoding::Base64 => BASE64.encode(bytes),
+                        });
+                    } else if INT_FIELDS.contains(&key.as_str()) {
+                        if !self.int_as_string {
+                            int_to_number(field)?;
+                        }
+                    } else {
+                        self.encode_value(field)?;
+                    }
+                }
+                Ok(())
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .try_for_each(|item| self.encode_value(item)),
+            _ => Ok(()),
+        }
+    }
+
+    // Converts `value` back to the representation expected by the serde
+    // implementations of the generated types.
+    fn decode_value(&self, value: &mut Value, in_links: bool) -> Result<(), serde_json::Error> {
+        match value {
+            Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if ID_FIELDS.contains(&key.as_str()) {
+                        let encoded = field.as_str().ok_or_else(|| {
+                            serde_json::Error::custom(format!("{key} must be a string"))
+                        })?;
+                        let bytes = match self.id_encoding {
+                            IdEncoding::Hex => {
+                                hex::decode(encoded).map_err(serde_json::Error::custom)?
+                            }
+                            IdEncoding::Base64 => {
+                                BASE64.decode(encoded).map_err(serde_json::Error::custom)?
+                            }
+                        };
+                        // link ids use the default serde representation of bytes
+                        *field = if in_links {
+                            Value::from(bytes)
+                        } else {
+                            Value::String(hex::encode(bytes))
+                        };
+                    } else if INT_FIELDS.contains(&key.as_str()) {
+                        if let Value::Number(number) = field {
+                            *field = Value::String(number.to_string());
+                        }
+                    } else {
+                        self.decode_value(field, in_links || key == "links")?;
+                    }
+                }
+                Ok(())
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .try_for_each(|item| self.decode_value(item, in_links)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn id_bytes(value: &Value) -> Result<Vec<u8>, serde_json::Error> {
+    match value {
+        Value::String(hex_string) => hex::decode(hex_string).map_err(serde_json::Error::custom),
+        Value::Array(_) => serde_json::from_value(value.clone()),
+        _ => Err(serde_json::Error::custom("unexpected id representation")),
+    }
+}
+
+fn int_to_number(value: &mut Value) -> Result<(), serde_json::Error> {
+    if let Value::String(s) = value {
+        *value = if let Ok(unsigned) = s.parse::<u64>() {
+            Value::from(unsigned)
+        } else {
+            Value::from(s.parse::<i64>().map_err(serde_json::Error::custom)?)
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdEncoding, OtlpJsonEncoder};
+    use crate::tonic::collector::trace::v1::ExportTraceServiceRequest;
+    use crate::tonic::common::v1::{any_value::Value, AnyValue, KeyValue};
+    use crate::tonic::trace::v1::{span::Link, ResourceSpans, ScopeSpans, Span};
+
+    fn request() -> ExportTraceServiceRequest {
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        trace_id: vec![0x5b; 16],
+                        span_id: vec![0xee; 8],
+                        parent_span_id: vec![0xe1; 8],
+                        name: "span".to_string(),
+                        start_time_unix_nano: 1_544_712_660_000_000_000,
+                        end_time_unix_nano: 1_544_712_661_000_000_000,
+                        attributes: vec![KeyValue {
+                            key: "count".to_string(),
+                            value: Some(AnyValue {
+                                value: Some(Value::IntValue(-42)),
+                            }),
+                        }],
+                        links: vec![Link {
+                            trace_id: vec![0x01; 16],
+                            span_id: vec![0x02; 8],
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trip_all_options() {
+        for id_encoding in [IdEncoding::Hex, IdEncoding::Base64] {
+            for int_as_string in [true, false] {
+                let encoder = OtlpJsonEncoder::new()
+                    .with_id_encoding(id_encoding)
+                    .with_int_as_string(int_as_string);
+                let json = encoder.encode_traces(&request()).unwrap();
+                assert_eq!(
+                    encoder.decode_traces(&json).unwrap(),
+                    request(),
+                    "{id_encoding:?}, int_as_string: {int_as_string}: {json}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn default_is_spec_compliant() {
+        let json = OtlpJsonEncoder::new().encode_traces(&request()).unwrap();
+        assert!(json.contains(r#""traceId":"5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b5b""#));
+        assert!(json.contains(r#""startTimeUnixNano":"1544712660000000000""#));
+        assert!(json.contains(r#""intValue":"-42""#));
+        // links are hex encoded too
+        assert!(json.contains(r#""spanId":"0202020202020202""#));
+
+        // without links, the output is readable by the serde implementation
+        let mut request = request();
+        request.resource_spans[0].scope_spans[0].spans[0]
+            .links
+            .clear();
+        let json = OtlpJsonEncoder::new().encode_traces(&request).unwrap();
+        let decoded: ExportTraceServiceRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn base64_ids_and_number_ints() {
+        let json = OtlpJsonEncoder::new()
+            .with_id_encoding(IdEncoding::Base64)
+            .with_int_as_string(false)
+            .encode_traces(&request())
+            .unwrap();
+        assert!(json.contains(r#""traceId":"W1tbW1tbW1tbW1tbW1tbWw==""#));
+        assert!(json.contains(r#""startTimeUnixNano":1544712660000000000"#));
+        assert!(json.contains(r#""intValue":-42"#));
+    }
+}
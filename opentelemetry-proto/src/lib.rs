@@ -36,3 +36,10 @@ mod proto;
 pub use proto::tonic;
 
 pub mod transform;
+
+#[cfg(all(
+    feature = "with-serde",
+    feature = "gen-tonic-messages",
+    feature = "trace"
+))]
+pub mod json;
@@ -7,6 +7,12 @@ use std::borrow::Cow;
 use std::fmt::Debug;
 use std::time::SystemTime;
 
+mod fallback;
+mod retrying;
+
+pub use fallback::FallbackExporter;
+pub use retrying::RetryingExporter;
+
 /// Describes the result of an export.
 pub type ExportResult = Result<(), TraceError>;
 
@@ -64,6 +70,18 @@ pub trait SpanExporter: Send + Sync + Debug {
         Box::pin(async { Ok(()) })
     }
 
+    /// Establishes the exporter's connections ahead of the first export, so
+    /// that connectivity problems surface at startup, e.g. when an exporter
+    /// would otherwise connect lazily on its first export.
+    ///
+    /// Called through [`TracerProvider::warmup`], before any span is exported
+    /// by the processor holding the exporter. The default does nothing.
+    ///
+    /// [`TracerProvider::warmup`]: crate::trace::TracerProvider::warmup
+    fn warmup(&mut self) -> BoxFuture<'static, ExportResult> {
+        Box::pin(async { Ok(()) })
+    }
+
     /// Set the resource for the exporter.
     fn set_resource(&mut self, _resource: &Resource) {}
 }
@@ -0,0 +1,199 @@
+use crate::export::trace::{ExportResult, SpanData, SpanExporter};
+use crate::Resource;
+use futures_util::future::{self, BoxFuture};
+use opentelemetry::trace::TraceError;
+use std::sync::{Arc, Mutex};
+
+/// A [`SpanExporter`] forwarding the batches its primary exporter fails to
+/// export to a secondary exporter.
+///
+/// This avoids losing spans while the primary destination is unavailable,
+/// e.g. export to a collector and fall back to writing the spans locally.
+/// The secondary exporter receives the exact batch the primary one failed
+/// to export, and only once the primary export has failed. If both fail, the
+/// returned error names both errors.
+///
+/// [`shutdown`], [`force_flush`] and [`set_resource`] are forwarded to both
+/// exporters.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "testing")]
+/// # {
+/// use opentelemetry_sdk::export::trace::FallbackExporter;
+/// use opentelemetry_sdk::testing::trace::{InMemorySpanExporter, NoopSpanExporter};
+///
+/// let exporter = FallbackExporter::new(NoopSpanExporter::new(), InMemorySpanExporter::default());
+/// # }
+/// ```
+///
+/// [`shutdown`]: SpanExporter::shutdown
+/// [`force_flush`]: SpanExporter::force_flush
+/// [`set_resource`]: SpanExporter::set_resource
+#[derive(Debug)]
+pub struct FallbackExporter<P, S> {
+    primary: P,
+    secondary: Arc<Mutex<S>>,
+}
+
+impl<P, S> FallbackExporter<P, S>
+where
+    P: SpanExporter,
+    S: SpanExporter + 'static,
+{
+    /// Create an exporter exporting to `primary` and falling back to
+    /// `secondary` for the batches `primary` fails to export.
+    pub fn new(primary: P, secondary: S) -> Self {
+        FallbackExporter {
+            primary,
+            secondary: Arc::new(Mutex::new(secondary)),
+        }
+    }
+}
+
+impl<P, S> SpanExporter for FallbackExporter<P, S>
+where
+    P: SpanExporter,
+    S: SpanExporter + 'static,
+{
+    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        let primary_export = self.primary.export(batch.clone());
+        let secondary = self.secondary.clone();
+
+        Box::pin(async move {
+            let primary_err = match primary_export.await {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let secondary_export = match secondary.lock() {
+                Ok(mut secondary) => secondary.export(batch),
+                Err(_) => {
+                    return Err(combined_error(
+                        primary_err,
+                        TraceError::Other("FallbackExporter mutex poison".into()),
+                    ))
+                }
+            };
+            secondary_export
+                .await
+                .map_err(|secondary_err| combined_error(primary_err, secondary_err))
+        })
+    }
+
+    fn shutdown(&mut self) {
+        self.primary.shutdown();
+        if let Ok(mut secondary) = self.secondary.lock() {
+            secondary.shutdown();
+        }
+    }
+
+    fn force_flush(&mut self) -> BoxFuture<'static, ExportResult> {
+        let primary_flush = self.primary.force_flush();
+        let secondary_flush = match self.secondary.lock() {
+            Ok(mut secondary) => secondary.force_flush(),
+            Err(_) => Box::pin(future::ready(Err(TraceError::Other(
+                "FallbackExporter mutex poison".into(),
+            )))),
+        };
+
+        Box::pin(async move {
+            match future::join(primary_flush, secondary_flush).await {
+                (Ok(()), Ok(())) => Ok(()),
+                (Err(err), Ok(())) | (Ok(()), Err(err)) => Err(err),
+                (Err(primary_err), Err(secondary_err)) => {
+                    Err(combined_error(primary_err, secondary_err))
+                }
+            }
+        })
+    }
+
+    fn warmup(&mut self) -> BoxFuture<'static, ExportResult> {
+        let primary_warmup = self.primary.warmup();
+        let secondary_warmup = match self.secondary.lock() {
+            Ok(mut secondary) => secondary.warmup(),
+            Err(_) => Box::pin(future::ready(Err(TraceError::Other(
+                "FallbackExporter mutex poison".into(),
+            )))),
+        };
+
+        Box::pin(async move {
+            match future::join(primary_warmup, secondary_warmup).await {
+                (Ok(()), Ok(())) => Ok(()),
+                (Err(err), Ok(())) | (Ok(()), Err(err)) => Err(err),
+                (Err(primary_err), Err(secondary_err)) => {
+                    Err(combined_error(primary_err, secondary_err))
+                }
+            }
+        })
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.primary.set_resource(resource);
+        if let Ok(mut secondary) = self.secondary.lock() {
+            secondary.set_resource(resource);
+        }
+    }
+}
+
+fn combined_error(primary: TraceError, secondary: TraceError) -> TraceError {
+    TraceError::Other(
+        format!("primary exporter failed: {primary}, secondary exporter failed: {secondary}")
+            .into(),
+    )
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::FallbackExporter;
+    use crate::export::trace::{ExportResult, SpanData, SpanExporter};
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use futures_util::future::BoxFuture;
+    use opentelemetry::trace::TraceError;
+
+    #[derive(Debug)]
+    struct FailingExporter(&'static str);
+
+    impl SpanExporter for FailingExporter {
+        fn export(&mut self, _batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+            let message = self.0;
+            Box::pin(async move { Err(TraceError::from(message)) })
+        }
+    }
+
+    #[test]
+    fn test_fallback_on_primary_error() {
+        let secondary = InMemorySpanExporterBuilder::new().build();
+        let mut exporter = FallbackExporter::new(FailingExporter("unreachable"), secondary.clone());
+
+        let batch = vec![new_test_export_span_data(), new_test_export_span_data()];
+        assert!(futures_executor::block_on(exporter.export(batch.clone())).is_ok());
+        assert_eq!(secondary.get_finished_spans().unwrap(), batch);
+    }
+
+    #[test]
+    fn test_no_fallback_on_primary_success() {
+        let primary = InMemorySpanExporterBuilder::new().build();
+        let secondary = InMemorySpanExporterBuilder::new().build();
+        let mut exporter = FallbackExporter::new(primary.clone(), secondary.clone());
+
+        let batch = vec![new_test_export_span_data()];
+        assert!(futures_executor::block_on(exporter.export(batch.clone())).is_ok());
+        assert_eq!(primary.get_finished_spans().unwrap(), batch);
+        assert!(secondary.get_finished_spans().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_both_failing() {
+        let mut exporter = FallbackExporter::new(
+            FailingExporter("collector down"),
+            FailingExporter("disk full"),
+        );
+
+        let err = futures_executor::block_on(exporter.export(vec![new_test_export_span_data()]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("collector down"));
+        assert!(err.contains("disk full"));
+    }
+}
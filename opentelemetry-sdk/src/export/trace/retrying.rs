@@ -0,0 +1,284 @@
+use crate::export::trace::{ExportResult, SpanData, SpanExporter};
+use crate::runtime::Runtime;
+use crate::Resource;
+use futures_util::future::{self, BoxFuture, Either};
+use opentelemetry::trace::TraceError;
+use std::cmp::min;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_ATTEMPTS: usize = 3;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+const DEFAULT_DEADLINE: Duration = Duration::from_secs(30);
+
+/// A [`SpanExporter`] retrying failed exports of the exporter it wraps with
+/// exponential backoff.
+///
+/// A failed export is retried up to the maximum number of attempts, waiting
+/// between attempts for a backoff that starts at the initial backoff and
+/// doubles after each attempt, up to the maximum backoff. If every attempt
+/// fails, the error of the last one is returned.
+///
+/// All attempts must complete within the deadline, counted from the first
+/// attempt. An attempt still running at the deadline is cancelled and
+/// [`TraceError::ExportTimedOut`] is returned, and no attempt is made if the
+/// backoff would end past the deadline. Keep the deadline below the export
+/// timeout of the span processor, e.g. the `OTEL_BSP_EXPORT_TIMEOUT` of the
+/// [`BatchSpanProcessor`], so retries don't stall it.
+///
+/// Exports are all or nothing from the point of view of this wrapper: every
+/// attempt exports the whole batch and any error is treated as if no span of
+/// the batch was exported. An inner exporter that can partially succeed
+/// should only report an error for batches it did not export at all,
+/// otherwise the spans it did export are exported again.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "testing")]
+/// # {
+/// use opentelemetry_sdk::export::trace::RetryingExporter;
+/// use opentelemetry_sdk::runtime;
+/// use opentelemetry_sdk::testing::trace::NoopSpanExporter;
+/// use std::time::Duration;
+///
+/// let exporter = RetryingExporter::new(NoopSpanExporter::new(), runtime::Tokio)
+///     .with_max_attempts(5)
+///     .with_deadline(Duration::from_secs(10));
+/// # }
+/// ```
+///
+/// [`BatchSpanProcessor`]: crate::trace::BatchSpanProcessor
+#[derive(Debug)]
+pub struct RetryingExporter<E, R> {
+    inner: Arc<Mutex<E>>,
+    runtime: R,
+    max_attempts: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    deadline: Duration,
+}
+
+impl<E, R> RetryingExporter<E, R>
+where
+    E: SpanExporter + 'static,
+    R: Runtime,
+{
+    /// Create an exporter retrying the failed exports of `inner`, waiting
+    /// for the backoff with `runtime`.
+    ///
+    /// Exports are attempted at most 3 times, with a backoff starting at
+    /// 100 milliseconds and capped at 5 seconds, within a deadline of 30
+    /// seconds.
+    pub fn new(inner: E, runtime: R) -> Self {
+        RetryingExporter {
+            inner: Arc::new(Mutex::new(inner)),
+            runtime,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            deadline: DEFAULT_DEADLINE,
+        }
+    }
+
+    /// Set the maximum number of attempts, including the first one. Values
+    /// lower than 1 are treated as 1.
+    pub fn with_max_attempts(self, max_attempts: usize) -> Self {
+        RetryingExporter {
+            max_attempts: max_attempts.max(1),
+            ..self
+        }
+    }
+
+    /// Set the time to wait after the first failed attempt.
+    pub fn with_initial_backoff(self, initial_backoff: Duration) -> Self {
+        RetryingExporter {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Set the upper bound of the time to wait between two attempts.
+    pub fn with_max_backoff(self, max_backoff: Duration) -> Self {
+        RetryingExporter {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Set the time within which all attempts must complete.
+    pub fn with_deadline(self, deadline: Duration) -> Self {
+        RetryingExporter { deadline, ..self }
+    }
+}
+
+impl<E, R> SpanExporter for RetryingExporter<E, R>
+where
+    E: SpanExporter + 'static,
+    R: Runtime + std::fmt::Debug,
+{
+    fn export(&mut self, mut batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        let inner = self.inner.clone();
+        let runtime = self.runtime.clone();
+        let max_attempts = self.max_attempts;
+        let max_backoff = self.max_backoff;
+        let deadline = self.deadline;
+        let mut backoff = self.initial_backoff;
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let mut attempt = 1;
+            loop {
+                // only keep a copy of the batch if it may be needed again
+                let spans = if attempt < max_attempts {
+                    batch.clone()
+                } else {
+                    mem::take(&mut batch)
+                };
+                let export = match inner.lock() {
+                    Ok(mut exporter) => exporter.export(spans),
+                    Err(_) => {
+                        return Err(TraceError::Other("RetryingExporter mutex poison".into()))
+                    }
+                };
+                let remaining = deadline.saturating_sub(start.elapsed());
+                let err = match future::select(export, runtime.delay(remaining)).await {
+                    Either::Left((Ok(()), _)) => return Ok(()),
+                    Either::Left((Err(err), _)) => err,
+                    Either::Right(_) => return Err(TraceError::ExportTimedOut(deadline)),
+                };
+
+                if attempt >= max_attempts || start.elapsed() + backoff >= deadline {
+                    return Err(err);
+                }
+                runtime.delay(backoff).await;
+                backoff = min(backoff * 2, max_backoff);
+                attempt += 1;
+            }
+        })
+    }
+
+    fn shutdown(&mut self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.shutdown();
+        }
+    }
+
+    fn force_flush(&mut self) -> BoxFuture<'static, ExportResult> {
+        match self.inner.lock() {
+            Ok(mut inner) => inner.force_flush(),
+            Err(_) => Box::pin(future::ready(Err(TraceError::Other(
+                "RetryingExporter mutex poison".into(),
+            )))),
+        }
+    }
+
+    fn warmup(&mut self) -> BoxFuture<'static, ExportResult> {
+        match self.inner.lock() {
+            Ok(mut inner) => inner.warmup(),
+            Err(_) => Box::pin(future::ready(Err(TraceError::Other(
+                "RetryingExporter mutex poison".into(),
+            )))),
+        }
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::RetryingExporter;
+    use crate::export::trace::{ExportResult, SpanData, SpanExporter};
+    use crate::runtime;
+    use crate::testing::trace::new_test_export_span_data;
+    use futures_util::future::BoxFuture;
+    use opentelemetry::trace::TraceError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // Fails every export until the given attempt.
+    #[derive(Debug)]
+    struct FlakyExporter {
+        succeed_on: usize,
+        attempts: Arc<AtomicUsize>,
+        exported: Arc<Mutex<Vec<SpanData>>>,
+    }
+
+    impl FlakyExporter {
+        fn new(succeed_on: usize) -> Self {
+            FlakyExporter {
+                succeed_on,
+                attempts: Arc::new(AtomicUsize::new(0)),
+                exported: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    impl SpanExporter for FlakyExporter {
+        fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            let result = if attempt >= self.succeed_on {
+                self.exported.lock().unwrap().extend(batch);
+                Ok(())
+            } else {
+                Err(TraceError::from(format!("attempt {attempt} failed")))
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let inner = FlakyExporter::new(3);
+        let (attempts, exported) = (inner.attempts.clone(), inner.exported.clone());
+        let mut exporter = RetryingExporter::new(inner, runtime::Tokio)
+            .with_max_attempts(5)
+            .with_initial_backoff(Duration::from_millis(1));
+
+        let batch = vec![new_test_export_span_data(), new_test_export_span_data()];
+        assert!(exporter.export(batch.clone()).await.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        // the spans are exported once, by the successful attempt
+        assert_eq!(*exported.lock().unwrap(), batch);
+    }
+
+    #[tokio::test]
+    async fn test_retry_always_failing() {
+        let inner = FlakyExporter::new(usize::MAX);
+        let attempts = inner.attempts.clone();
+        let mut exporter = RetryingExporter::new(inner, runtime::Tokio)
+            .with_max_attempts(4)
+            .with_initial_backoff(Duration::from_millis(1));
+
+        let err = exporter
+            .export(vec![new_test_export_span_data()])
+            .await
+            .unwrap_err();
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+        assert!(err.to_string().contains("attempt 4 failed"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_deadline() {
+        let inner = FlakyExporter::new(usize::MAX);
+        let attempts = inner.attempts.clone();
+        let mut exporter = RetryingExporter::new(inner, runtime::Tokio)
+            .with_max_attempts(10)
+            .with_initial_backoff(Duration::from_millis(40))
+            .with_deadline(Duration::from_millis(100));
+
+        assert!(exporter
+            .export(vec![new_test_export_span_data()])
+            .await
+            .is_err());
+        // 40ms then 80ms of backoff would end past the deadline
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
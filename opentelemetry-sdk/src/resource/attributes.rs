@@ -30,3 +30,31 @@ pub(crate) const TELEMETRY_SDK_NAME: &str = "telemetry.sdk.name";
 ///
 /// - `1.2.3`
 pub(crate) const TELEMETRY_SDK_VERSION: &str = "telemetry.sdk.version";
+
+/// Process identifier (PID).
+///
+/// # Examples
+///
+/// - `1234`
+pub(crate) const PROCESS_PID: &str = "process.pid";
+
+/// The name of the process executable.
+///
+/// # Examples
+///
+/// - `otelcol`
+pub(crate) const PROCESS_EXECUTABLE_NAME: &str = "process.executable.name";
+
+/// The full path to the process executable.
+///
+/// # Examples
+///
+/// - `/usr/bin/cmd/otelcol`
+pub(crate) const PROCESS_EXECUTABLE_PATH: &str = "process.executable.path";
+
+/// All the command arguments (including the command/executable itself) as received by the process.
+///
+/// # Examples
+///
+/// - `["cmd/otecol", "--config=config.yaml"]`
+pub(crate) const PROCESS_COMMAND_ARGS: &str = "process.command_args";
@@ -17,10 +17,13 @@
 //!
 //! - [`EnvResourceDetector`] - detect resource from environmental variables.
 //! - [`TelemetryResourceDetector`] - detect telemetry SDK's information.
+//! - [`ProcessResourceDetector`] - detect the running process' information.
 //!
-//! The OS and Process resource detectors are packaged separately in the
+//! The OS resource detector and a more complete process resource detector are
+//! packaged separately in the
 //! [`opentelemetry-resource-detector` crate](https://github.com/open-telemetry/opentelemetry-rust-contrib/tree/main/opentelemetry-resource-detectors).
 mod env;
+mod process;
 mod telemetry;
 
 mod attributes;
@@ -28,14 +31,18 @@ pub(crate) use attributes::*;
 
 pub use env::EnvResourceDetector;
 pub use env::SdkProvidedResourceDetector;
+pub use process::ProcessResourceDetector;
 pub use telemetry::TelemetryResourceDetector;
 
+use crate::runtime::Runtime;
+use futures_util::future::{self, BoxFuture, Either};
 use opentelemetry::{Key, KeyValue, Value};
 use std::borrow::Cow;
 use std::collections::{hash_map, HashMap};
 use std::ops::Deref;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Inner structure of `Resource` holding the actual data.
 /// This structure is designed to be shared among `Resource` instances via `Arc`.
@@ -145,6 +152,82 @@ impl Resource {
         resource
     }
 
+    /// Create a new `Resource` from resource detectors, giving up on the ones
+    /// not done within `timeout`.
+    ///
+    /// Detectors run concurrently, each on its own thread, and a detector that
+    /// hasn't returned by the deadline contributes an empty resource. Its
+    /// thread is left to finish in the background. When several detectors
+    /// return the same key, the value of the earliest detector wins.
+    pub(crate) fn from_detectors_with_deadline(
+        timeout: Duration,
+        detectors: Vec<Box<dyn ResourceDetector + Send>>,
+    ) -> Self {
+        let deadline = Instant::now() + timeout;
+        let pending: Vec<_> = detectors
+            .into_iter()
+            .map(|detector| {
+                let (sender, receiver) = mpsc::sync_channel(1);
+                thread::spawn(move || {
+                    // the receiver is gone if the deadline passed
+                    let _ = sender.send(detector.detect(timeout));
+                });
+                receiver
+            })
+            .collect();
+
+        let detected: Vec<_> = pending
+            .into_iter()
+            .map(|receiver| {
+                receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    .unwrap_or_else(|_| Resource::empty())
+            })
+            .collect();
+
+        detected
+            .iter()
+            .rev()
+            .fold(Resource::empty(), |resource, detected| {
+                resource.merge(detected)
+            })
+    }
+
+    /// Create a new `Resource` from async resource detectors, giving up on the
+    /// ones not done within `timeout`.
+    ///
+    /// Detectors are polled concurrently on the current task and share the
+    /// same deadline, timed by `runtime`. A detector that hasn't completed by
+    /// then contributes an empty resource. When several detectors return the
+    /// same key, the value of the earliest detector wins.
+    #[cfg(feature = "trace")]
+    pub(crate) async fn from_async_detectors_with_deadline<R: Runtime>(
+        timeout: Duration,
+        detectors: Vec<Box<dyn AsyncResourceDetector>>,
+        runtime: &R,
+    ) -> Self {
+        let pending: Vec<_> = detectors
+            .iter()
+            .map(|detector| future::select(detector.detect(), runtime.delay(timeout)))
+            .collect();
+
+        let detected: Vec<_> = future::join_all(pending)
+            .await
+            .into_iter()
+            .map(|done| match done {
+                Either::Left((resource, _)) => resource,
+                Either::Right(_) => Resource::empty(),
+            })
+            .collect();
+
+        detected
+            .iter()
+            .rev()
+            .fold(Resource::empty(), |resource, detected| {
+                resource.merge(detected)
+            })
+    }
+
     /// Create a new `Resource` by combining two resources.
     ///
     /// ### Key value pairs
@@ -257,6 +340,25 @@ pub trait ResourceDetector {
     fn detect(&self, timeout: Duration) -> Resource;
 }
 
+/// Detects OpenTelemetry resource information asynchronously, e.g. from a
+/// cloud provider's metadata endpoint.
+///
+/// Implementations of this trait can be passed to
+/// [`Builder::with_async_detectors`], they run when the provider is built with
+/// [`Builder::build_async`].
+///
+/// [`Builder::with_async_detectors`]: crate::trace::Builder::with_async_detectors
+/// [`Builder::build_async`]: crate::trace::Builder::build_async
+pub trait AsyncResourceDetector: Send + Sync {
+    /// Returns an initialized Resource based on gathered information.
+    ///
+    /// The returned future may be dropped before it completes, once the
+    /// detection budget is spent. If source information to construct a
+    /// Resource is inaccessible or invalid, an empty Resource should be
+    /// returned.
+    fn detect(&self) -> BoxFuture<'_, Resource>;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
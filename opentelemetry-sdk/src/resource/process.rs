@@ -0,0 +1,73 @@
+//! Process resource detector
+//!
+//! Implementation of `ResourceDetector` to extract a `Resource` describing the
+//! running process.
+use crate::resource::{Resource, ResourceDetector};
+use opentelemetry::{Array, KeyValue, StringValue, Value};
+use std::env;
+use std::process;
+use std::time::Duration;
+
+/// Detect information about the running process.
+///
+/// It provides:
+/// - The process identifier (`process.pid`).
+/// - The name and full path of the process executable
+///   (`process.executable.name`, `process.executable.path`), when available.
+/// - The command arguments the process was started with, including the
+///   executable itself (`process.command_args`).
+///
+/// See [semantic conventions](https://github.com/open-telemetry/semantic-conventions/blob/main/docs/resource/process.md)
+/// for details.
+#[derive(Debug)]
+pub struct ProcessResourceDetector;
+
+impl ResourceDetector for ProcessResourceDetector {
+    fn detect(&self, _timeout: Duration) -> Resource {
+        let mut attributes = vec![KeyValue::new(super::PROCESS_PID, process::id() as i64)];
+
+        if let Ok(path) = env::current_exe() {
+            if let Some(name) = path.file_name() {
+                attributes.push(KeyValue::new(
+                    super::PROCESS_EXECUTABLE_NAME,
+                    name.to_string_lossy().into_owned(),
+                ));
+            }
+            attributes.push(KeyValue::new(
+                super::PROCESS_EXECUTABLE_PATH,
+                path.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let args: Vec<StringValue> = env::args_os()
+            .map(|arg| arg.to_string_lossy().into_owned().into())
+            .collect();
+        attributes.push(KeyValue::new(
+            super::PROCESS_COMMAND_ARGS,
+            Value::Array(Array::String(args)),
+        ));
+
+        Resource::new(attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessResourceDetector;
+    use crate::resource::{ResourceDetector, PROCESS_COMMAND_ARGS, PROCESS_PID};
+    use opentelemetry::{Key, Value};
+    use std::process;
+    use std::time::Duration;
+
+    #[test]
+    fn detects_current_process() {
+        let resource = ProcessResourceDetector.detect(Duration::from_secs(0));
+        assert_eq!(
+            resource.get(Key::from_static_str(PROCESS_PID)),
+            Some(Value::I64(process::id() as i64))
+        );
+        assert!(resource
+            .get(Key::from_static_str(PROCESS_COMMAND_ARGS))
+            .is_some());
+    }
+}
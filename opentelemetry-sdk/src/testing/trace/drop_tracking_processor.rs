@@ -0,0 +1,409 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::{SpanContext, TraceResult};
+use opentelemetry::Context;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Why a span tracked by a [`DropTrackingProcessor`] was considered dropped.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum DropReason {
+    /// The span ended without being sampled, so it will never be exported.
+    NotSampled,
+    /// The span ended after the processor was shut down.
+    AfterShutdown,
+    /// A force flush failed while the span was still pending in the wrapped
+    /// processor.
+    FlushFailed(String),
+    /// The shutdown failed while the span was still pending in the wrapped
+    /// processor.
+    ShutdownFailed(String),
+    /// The wrapped processor rejected the span with this status, see
+    /// [`SpanProcessor::try_on_end`].
+    Rejected(OnEndStatus),
+}
+
+/// A span recorded as dropped by a [`DropTrackingProcessor`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DroppedSpan {
+    /// Name of the dropped span.
+    pub name: Cow<'static, str>,
+    /// Span context of the dropped span.
+    pub span_context: SpanContext,
+    /// Why the span is considered dropped.
+    pub reason: DropReason,
+}
+
+/// A [`SpanProcessor`] wrapper that records every span that does not make it
+/// through the wrapped processor.
+///
+/// Spans are recorded as dropped when they are not sampled, when they end
+/// after [`shutdown`](SpanProcessor::shutdown), when the wrapped processor
+/// rejects them from [`try_on_end`](SpanProcessor::try_on_end), e.g. because
+/// its queue is full, or when a force flush or shutdown of the wrapped
+/// processor fails while they are still pending. Rejections are also
+/// returned to the provider, which reports them to
+/// [`Builder::with_on_processor_drop`](crate::trace::Builder::with_on_processor_drop).
+///
+/// # Example
+///
+/// ```
+/// # use opentelemetry::trace::{Tracer, TracerProvider as _};
+/// # use opentelemetry_sdk::testing::trace::{DropTrackingProcessor, NoopSpanExporter};
+/// # use opentelemetry_sdk::trace::{BatchSpanProcessor, TracerProvider};
+/// # use opentelemetry_sdk::runtime;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let processor = DropTrackingProcessor::new(
+///     BatchSpanProcessor::builder(NoopSpanExporter::new(), runtime::Tokio).build(),
+/// );
+/// let tracker = processor.tracker();
+/// let provider = TracerProvider::builder()
+///     .with_span_processor(processor)
+///     .build();
+///
+/// provider.tracer("test").in_span("operation", |_cx| {});
+/// provider.force_flush();
+///
+/// tracker.assert_no_drops();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DropTrackingProcessor<P> {
+    inner: P,
+    tracker: DropTracker,
+    // spans handed to `inner` since the last successful flush
+    pending: Mutex<Vec<(Cow<'static, str>, SpanContext)>>,
+    is_shutdown: AtomicBool,
+}
+
+impl<P: SpanProcessor> DropTrackingProcessor<P> {
+    /// Wrap `inner`, tracking the spans it drops.
+    pub fn new(inner: P) -> Self {
+        DropTrackingProcessor {
+            inner,
+            tracker: DropTracker::default(),
+            pending: Mutex::new(Vec::new()),
+            is_shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns a handle to the recorded drops which remains usable after this
+    /// processor has been moved into a [`TracerProvider`].
+    ///
+    /// [`TracerProvider`]: crate::trace::TracerProvider
+    pub fn tracker(&self) -> DropTracker {
+        self.tracker.clone()
+    }
+
+    /// See [`DropTracker::assert_no_drops`].
+    pub fn assert_no_drops(&self) {
+        self.tracker.assert_no_drops()
+    }
+
+    fn drop_pending(&self, reason: DropReason) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        for (name, span_context) in pending {
+            self.tracker.record(name, span_context, reason.clone());
+        }
+    }
+
+    /// Records `span` if it is dropped before reaching the wrapped processor,
+    /// or adds it to the pending spans. Returns whether it is pending.
+    fn track(&self, span: &SpanData) -> bool {
+        if self.is_shutdown.load(Ordering::Relaxed) {
+            self.tracker.record(
+                span.name.clone(),
+                span.span_context.clone(),
+                DropReason::AfterShutdown,
+            );
+            false
+        } else if !span.span_context.is_sampled() {
+            self.tracker.record(
+                span.name.clone(),
+                span.span_context.clone(),
+                DropReason::NotSampled,
+            );
+            false
+        } else {
+            self.pending
+                .lock()
+                .unwrap()
+                .push((span.name.clone(), span.span_context.clone()));
+            true
+        }
+    }
+
+    fn flushed(&self, result: &TraceResult<()>, reason: fn(String) -> DropReason) {
+        match result {
+            Ok(()) => self.pending.lock().unwrap().clear(),
+            Err(err) => self.drop_pending(reason(err.to_string())),
+        }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for DropTrackingProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.track(&span);
+        self.inner.on_end(span)
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        let pending = self.track(&span);
+        let name = span.name.clone();
+        let span_context = span.span_context.clone();
+        let status = self.inner.try_on_end(span);
+        // spans dropped before reaching `inner` are already recorded
+        if status != OnEndStatus::Accepted && pending {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some(index) = pending.iter().rposition(|(_, sc)| *sc == span_context) {
+                pending.remove(index);
+            }
+            drop(pending);
+            self.tracker
+                .record(name, span_context, DropReason::Rejected(status));
+        }
+        status
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        let result = self.inner.force_flush();
+        self.flushed(&result, DropReason::FlushFailed);
+        result
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        let result = self.inner.force_flush_with_timeout(timeout);
+        self.flushed(&result, DropReason::FlushFailed);
+        result
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        let result = self.inner.shutdown();
+        self.flushed(&result, DropReason::ShutdownFailed);
+        result
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        let result = self.inner.shutdown_with_timeout(timeout);
+        self.flushed(&result, DropReason::ShutdownFailed);
+        result
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+/// Shared handle to the spans recorded as dropped by a [`DropTrackingProcessor`].
+#[derive(Clone, Default)]
+pub struct DropTracker {
+    drops: Arc<Mutex<Vec<DroppedSpan>>>,
+}
+
+impl fmt::Debug for DropTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropTracker")
+            .field("drops", &self.drops.lock().map(|drops| drops.len()))
+            .finish()
+    }
+}
+
+impl DropTracker {
+    /// Returns all spans recorded as dropped so far.
+    pub fn drops(&self) -> Vec<DroppedSpan> {
+        self.drops.lock().unwrap().clone()
+    }
+
+    /// Clears the recorded drops.
+    pub fn reset(&self) {
+        self.drops.lock().unwrap().clear()
+    }
+
+    /// Panics if any span was recorded as dropped, listing every drop and its
+    /// reason.
+    #[track_caller]
+    pub fn assert_no_drops(&self) {
+        let drops = self.drops.lock().unwrap();
+        if !drops.is_empty() {
+            let details = drops
+                .iter()
+                .map(|drop| {
+                    format!(
+                        "  {} (trace_id: {}, span_id: {}): {:?}",
+                        drop.name,
+                        drop.span_context.trace_id(),
+                        drop.span_context.span_id(),
+                        drop.reason
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            panic!("{} span(s) were dropped:\n{}", drops.len(), details);
+        }
+    }
+
+    fn record(&self, name: Cow<'static, str>, span_context: SpanContext, reason: DropReason) {
+        self.drops.lock().unwrap().push(DroppedSpan {
+            name,
+            span_context,
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DropReason, DropTrackingProcessor};
+    use crate::export::trace::SpanData;
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use crate::trace::{OnEndStatus, SimpleSpanProcessor, Span, SpanProcessor, TracerProvider};
+    use opentelemetry::trace::{SpanContext, TraceError, TraceResult, Tracer, TracerProvider as _};
+    use opentelemetry::Context;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    struct FailingProcessor;
+
+    impl SpanProcessor for FailingProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Err(TraceError::from("cannot export"))
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Err(TraceError::from("cannot shutdown"))
+        }
+    }
+
+    #[test]
+    fn no_drops_when_exported() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor =
+            DropTrackingProcessor::new(SimpleSpanProcessor::new(Box::new(exporter.clone())));
+        processor.on_end(new_test_export_span_data());
+        assert!(processor.force_flush().is_ok());
+
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+        processor.assert_no_drops();
+    }
+
+    #[test]
+    fn records_drop_reasons() {
+        let processor = DropTrackingProcessor::new(FailingProcessor);
+        let tracker = processor.tracker();
+
+        let mut unsampled = new_test_export_span_data();
+        unsampled.span_context = SpanContext::empty_context();
+        processor.on_end(unsampled);
+
+        processor.on_end(new_test_export_span_data());
+        assert!(processor.force_flush().is_err());
+
+        processor.on_end(new_test_export_span_data());
+        assert!(processor.shutdown().is_err());
+
+        processor.on_end(new_test_export_span_data());
+
+        let reasons: Vec<_> = tracker.drops().into_iter().map(|d| d.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                DropReason::NotSampled,
+                DropReason::FlushFailed("cannot export".to_string()),
+                DropReason::ShutdownFailed("cannot shutdown".to_string()),
+                DropReason::AfterShutdown,
+            ]
+        );
+
+        tracker.reset();
+        tracker.assert_no_drops();
+    }
+
+    #[derive(Debug)]
+    struct RejectingProcessor;
+
+    impl SpanProcessor for RejectingProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn try_on_end(&self, _span: SpanData) -> OnEndStatus {
+            OnEndStatus::QueueFull
+        }
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn records_and_reports_rejections() {
+        let processor = DropTrackingProcessor::new(RejectingProcessor);
+        let tracker = processor.tracker();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let on_processor_drop = reported.clone();
+        let provider = TracerProvider::builder()
+            .with_span_processor(processor)
+            .with_on_processor_drop(move |_name, status| {
+                on_processor_drop.lock().unwrap().push(status);
+            })
+            .build();
+
+        provider.tracer("test").in_span("rejected", |_cx| {});
+        // the rejected span isn't pending anymore, a failed flush can't
+        // record it twice
+        let _ = provider.force_flush();
+
+        let reasons: Vec<_> = tracker.drops().into_iter().map(|d| d.reason).collect();
+        assert_eq!(reasons, vec![DropReason::Rejected(OnEndStatus::QueueFull)]);
+        assert_eq!(*reported.lock().unwrap(), vec![OnEndStatus::QueueFull]);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 span(s) were dropped")]
+    fn assert_no_drops_panics() {
+        let processor = DropTrackingProcessor::new(FailingProcessor);
+        processor.on_end(new_test_export_span_data());
+        let _ = processor.force_flush();
+        processor.assert_no_drops();
+    }
+}
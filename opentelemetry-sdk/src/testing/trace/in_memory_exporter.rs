@@ -2,6 +2,7 @@ use crate::export::trace::{ExportResult, SpanData, SpanExporter};
 use crate::resource::Resource;
 use futures_util::future::BoxFuture;
 use opentelemetry::trace::{TraceError, TraceResult};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 /// An in-memory span exporter that stores span data in memory.
@@ -9,6 +10,11 @@ use std::sync::{Arc, Mutex};
 /// This exporter is useful for testing and debugging purposes. It stores
 /// metric data in a `Vec<SpanData>`. Metrics can be retrieved
 /// using the `get_finished_spans` method.
+///
+/// Clones share the same storage, so a clone given to a provider can be
+/// queried from the test, including after the provider is shut down. Use
+/// [`fail_exports`](InMemorySpanExporter::fail_exports) to test how
+/// instrumentation behaves when exports fail.
 /// # Example
 /// ```
 ///# use opentelemetry::trace::{SpanKind, TraceContextExt};
@@ -53,6 +59,7 @@ use std::sync::{Arc, Mutex};
 pub struct InMemorySpanExporter {
     spans: Arc<Mutex<Vec<SpanData>>>,
     resource: Arc<Mutex<Resource>>,
+    failing: Arc<AtomicBool>,
 }
 
 impl Default for InMemorySpanExporter {
@@ -88,6 +95,7 @@ impl InMemorySpanExporterBuilder {
         InMemorySpanExporter {
             spans: Arc::new(Mutex::new(Vec::new())),
             resource: Arc::new(Mutex::new(Resource::default())),
+            failing: Arc::new(AtomicBool::new(false)),
         }
     }
 }
@@ -127,10 +135,30 @@ impl InMemorySpanExporter {
     pub fn reset(&self) {
         let _ = self.spans.lock().map(|mut spans_guard| spans_guard.clear());
     }
+
+    /// Makes the following exports fail without storing their spans if
+    /// `failing` is true, until called again with false.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    ///
+    /// let exporter = InMemorySpanExporter::default();
+    /// exporter.fail_exports(true);
+    /// ```
+    pub fn fail_exports(&self, failing: bool) {
+        self.failing.store(failing, Ordering::Relaxed);
+    }
 }
 
 impl SpanExporter for InMemorySpanExporter {
     fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        if self.failing.load(Ordering::Relaxed) {
+            return Box::pin(std::future::ready(Err(TraceError::from(
+                "in memory exporter is set to fail exports",
+            ))));
+        }
         if let Err(err) = self
             .spans
             .lock()
@@ -142,10 +170,6 @@ impl SpanExporter for InMemorySpanExporter {
         Box::pin(std::future::ready(Ok(())))
     }
 
-    fn shutdown(&mut self) {
-        self.reset()
-    }
-
     fn set_resource(&mut self, resource: &Resource) {
         self.resource
             .lock()
@@ -153,3 +177,51 @@ impl SpanExporter for InMemorySpanExporter {
             .expect("Resource lock poisoned");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::InMemorySpanExporter;
+    use crate::testing::trace::new_test_export_span_data;
+    use crate::trace::{SimpleSpanProcessor, SpanProcessor};
+
+    #[test]
+    fn collects_exported_spans() {
+        let exporter = InMemorySpanExporter::default();
+        let processor = SimpleSpanProcessor::new(Box::new(exporter.clone()));
+
+        processor.on_end(new_test_export_span_data());
+        processor.on_end(new_test_export_span_data());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 2);
+
+        // still queryable once shut down
+        assert!(processor.shutdown().is_ok());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reset_clears_spans() {
+        let exporter = InMemorySpanExporter::default();
+        let processor = SimpleSpanProcessor::new(Box::new(exporter.clone()));
+
+        processor.on_end(new_test_export_span_data());
+        exporter.reset();
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+
+        processor.on_end(new_test_export_span_data());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn fail_exports_on_demand() {
+        let exporter = InMemorySpanExporter::default();
+        let processor = SimpleSpanProcessor::new(Box::new(exporter.clone()));
+
+        exporter.fail_exports(true);
+        processor.on_end(new_test_export_span_data());
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+
+        exporter.fail_exports(false);
+        processor.on_end(new_test_export_span_data());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+    }
+}
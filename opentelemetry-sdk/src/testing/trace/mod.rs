@@ -5,6 +5,9 @@
 pub mod in_memory_exporter;
 pub use in_memory_exporter::{InMemorySpanExporter, InMemorySpanExporterBuilder};
 
+mod drop_tracking_processor;
+pub use drop_tracking_processor::{DropReason, DropTracker, DropTrackingProcessor, DroppedSpan};
+
 #[doc(hidden)]
 mod span_exporters;
 pub use span_exporters::*;
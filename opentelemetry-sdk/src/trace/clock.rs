@@ -0,0 +1,41 @@
+use std::fmt;
+use std::time::SystemTime;
+
+/// Interface for reading the current time.
+///
+/// Components that depend on the wall clock read it through a `Clock` so that
+/// it can be replaced, e.g. by a fixed clock in tests.
+pub trait Clock: CloneClock + Send + Sync + fmt::Debug {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// This trait should not be used directly instead users should use [`Clock`].
+pub trait CloneClock {
+    fn box_clone(&self) -> Box<dyn Clock>;
+}
+
+impl<T> CloneClock for T
+where
+    T: Clock + Clone + 'static,
+{
+    fn box_clone(&self) -> Box<dyn Clock> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Clock> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// Default [`Clock`] implementation, reading the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        opentelemetry::time::now()
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! Configuration represents the global tracing configuration, overrides
 //! can be set for the default OpenTelemetry limits and Sampler.
-use crate::trace::{span_limit::SpanLimits, IdGenerator, RandomIdGenerator, Sampler, ShouldSample};
+use crate::trace::{
+    span_limit::SpanLimits, Clock, IdGenerator, RandomIdGenerator, Sampler, ShouldSample,
+    SystemClock, TraceStateMergePolicy,
+};
 use crate::Resource;
 use opentelemetry::global::{handle_error, Error};
+use opentelemetry::KeyValue;
 use std::borrow::Cow;
 use std::env;
 use std::str::FromStr;
@@ -16,7 +20,11 @@ pub fn config() -> Config {
 }
 
 /// Tracer configuration
-#[derive(Debug)]
+///
+/// Cloning a configuration clones its sampler, id generator and clock, so
+/// the clone can be changed and used to build another provider without
+/// affecting the original.
+#[derive(Clone, Debug)]
 #[non_exhaustive]
 pub struct Config {
     /// The sampler that the sdk should use
@@ -28,8 +36,22 @@ pub struct Config {
     /// span limits
     pub span_limits: SpanLimits,
 
+    /// The clock span start, end and event timestamps are read from, unless
+    /// they are set explicitly
+    pub clock: Box<dyn Clock>,
+
     /// Contains attributes representing an entity that produces telemetry.
     pub resource: Cow<'static, Resource>,
+
+    /// How the trace state returned by the sampler is merged with the trace
+    /// state of the parent span
+    pub trace_state_merge_policy: TraceStateMergePolicy,
+
+    /// Appended to the attribute values truncated by
+    /// [`SpanLimits::max_attribute_value_length`], counting towards the
+    /// limit. Empty by default. Not appended if it is itself longer than the
+    /// limit.
+    pub attribute_value_truncation_marker: Cow<'static, str>,
 }
 
 impl Config {
@@ -45,6 +67,13 @@ impl Config {
         self
     }
 
+    /// Specify the clock to read span timestamps from, e.g. a fixed clock
+    /// to get deterministic timestamps in tests.
+    pub fn with_clock<T: Clock + 'static>(mut self, clock: T) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
     /// Specify the number of events to be recorded per span.
     pub fn with_max_events_per_span(mut self, max_events: u32) -> Self {
         self.span_limits.max_events_per_span = max_events;
@@ -75,6 +104,31 @@ impl Config {
         self
     }
 
+    /// Specify the max length, in characters, of string attribute values.
+    /// Longer values are truncated.
+    pub fn with_max_attribute_value_length(mut self, max_length: u32) -> Self {
+        self.span_limits.max_attribute_value_length = Some(max_length);
+        self
+    }
+
+    /// Specify the marker appended to truncated attribute values, see
+    /// [`Config::attribute_value_truncation_marker`].
+    pub fn with_attribute_value_truncation_marker(
+        mut self,
+        marker: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.attribute_value_truncation_marker = marker.into();
+        self
+    }
+
+    /// Specify how the trace state returned by the sampler is merged with the
+    /// trace state of the parent span, [`TraceStateMergePolicy::Replace`] by
+    /// default.
+    pub fn with_trace_state_merge_policy(mut self, policy: TraceStateMergePolicy) -> Self {
+        self.trace_state_merge_policy = policy;
+        self
+    }
+
     /// Specify all limit via the span_limits
     pub fn with_span_limits(mut self, span_limits: SpanLimits) -> Self {
         self.span_limits = span_limits;
@@ -95,30 +149,32 @@ impl Default for Config {
             sampler: Box::new(Sampler::ParentBased(Box::new(Sampler::AlwaysOn))),
             id_generator: Box::<RandomIdGenerator>::default(),
             span_limits: SpanLimits::default(),
+            clock: Box::new(SystemClock),
             resource: Cow::Owned(Resource::default()),
+            trace_state_merge_policy: TraceStateMergePolicy::default(),
+            attribute_value_truncation_marker: Cow::Borrowed(""),
         };
 
-        if let Some(max_attributes_per_span) = env::var("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT")
-            .ok()
-            .and_then(|count_limit| u32::from_str(&count_limit).ok())
+        if let Some(max_attributes_per_span) =
+            span_limit_from_env("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT")
         {
             config.span_limits.max_attributes_per_span = max_attributes_per_span;
         }
 
-        if let Some(max_events_per_span) = env::var("OTEL_SPAN_EVENT_COUNT_LIMIT")
-            .ok()
-            .and_then(|max_events| u32::from_str(&max_events).ok())
-        {
+        if let Some(max_events_per_span) = span_limit_from_env("OTEL_SPAN_EVENT_COUNT_LIMIT") {
             config.span_limits.max_events_per_span = max_events_per_span;
         }
 
-        if let Some(max_links_per_span) = env::var("OTEL_SPAN_LINK_COUNT_LIMIT")
-            .ok()
-            .and_then(|max_links| u32::from_str(&max_links).ok())
-        {
+        if let Some(max_links_per_span) = span_limit_from_env("OTEL_SPAN_LINK_COUNT_LIMIT") {
             config.span_limits.max_links_per_span = max_links_per_span;
         }
 
+        if let Some(max_length) = span_limit_from_env("OTEL_SPAN_ATTRIBUTE_VALUE_LENGTH_LIMIT")
+            .or_else(|| span_limit_from_env("OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT"))
+        {
+            config.span_limits.max_attribute_value_length = Some(max_length);
+        }
+
         let sampler_arg = env::var("OTEL_TRACES_SAMPLER_ARG").ok();
         if let Ok(sampler) = env::var("OTEL_TRACES_SAMPLER") {
             config.sampler = match sampler.as_str() {
@@ -188,3 +244,174 @@ impl Default for Config {
         config
     }
 }
+
+/// Reads the span limit set by the environment variable `name`, reporting
+/// malformed values, which leave the default limit in place.
+fn span_limit_from_env(name: &str) -> Option<u32> {
+    let value = env::var(name).ok().filter(|value| !value.is_empty())?;
+    match u32::from_str(value.trim()) {
+        Ok(limit) => Some(limit),
+        Err(err) => {
+            handle_error(Error::Other(format!(
+                "Invalid {} value {:?}: {}. Falling back to the default limit",
+                name, value, err
+            )));
+            None
+        }
+    }
+}
+
+/// Read-only view of the configuration a [`TracerProvider`] was built with.
+///
+/// See [`TracerProvider::effective_config`].
+///
+/// [`TracerProvider`]: crate::trace::TracerProvider
+/// [`TracerProvider::effective_config`]: crate::trace::TracerProvider::effective_config
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ConfigSnapshot {
+    /// The debug representation of the sampler, e.g. `ParentBased(AlwaysOn)`.
+    pub sampler: String,
+    /// The span limits.
+    pub span_limits: SpanLimits,
+    /// The attributes of the resource, sorted by key.
+    pub resource: Vec<KeyValue>,
+    /// The schema url of the resource.
+    pub resource_schema_url: Option<String>,
+}
+
+impl ConfigSnapshot {
+    pub(crate) fn new(config: &Config) -> Self {
+        let mut resource: Vec<_> = config
+            .resource
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+            .collect();
+        resource.sort_by(|a, b| a.key.as_str().cmp(b.key.as_str()));
+
+        ConfigSnapshot {
+            sampler: format!("{:?}", config.sampler),
+            span_limits: config.span_limits,
+            resource,
+            resource_schema_url: config.resource.schema_url().map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::trace::{Sampler, SpanLimits};
+
+    fn sampler_from_env(sampler: &str, arg: Option<&str>) -> String {
+        temp_env::with_vars(
+            [
+                ("OTEL_TRACES_SAMPLER", Some(sampler)),
+                ("OTEL_TRACES_SAMPLER_ARG", arg),
+            ],
+            || format!("{:?}", Config::default().sampler),
+        )
+    }
+
+    #[test]
+    fn sampler_from_env_vars() {
+        let cases = [
+            ("always_on", None, "AlwaysOn"),
+            ("always_off", None, "AlwaysOff"),
+            ("traceidratio", Some("0.5"), "TraceIdRatioBased(0.5)"),
+            ("parentbased_always_on", None, "ParentBased(AlwaysOn)"),
+            ("parentbased_always_off", None, "ParentBased(AlwaysOff)"),
+            (
+                "parentbased_traceidratio",
+                Some("0.25"),
+                "ParentBased(TraceIdRatioBased(0.25))",
+            ),
+            // not implemented, falls back to the default sampler
+            ("jaeger_remote", None, "ParentBased(AlwaysOn)"),
+            ("parentbased_jaeger_remote", None, "ParentBased(AlwaysOn)"),
+            ("xray", None, "ParentBased(AlwaysOn)"),
+            ("unknown", None, "ParentBased(AlwaysOn)"),
+        ];
+        for (sampler, arg, expected) in cases {
+            assert_eq!(sampler_from_env(sampler, arg), expected, "{sampler}");
+        }
+    }
+
+    #[test]
+    fn malformed_sampler_arg_falls_back_to_full_ratio() {
+        for arg in [Some("half"), None] {
+            assert_eq!(
+                sampler_from_env("traceidratio", arg),
+                "TraceIdRatioBased(1.0)"
+            );
+            assert_eq!(
+                sampler_from_env("parentbased_traceidratio", arg),
+                "ParentBased(TraceIdRatioBased(1.0))"
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_sampler_wins_over_env_vars() {
+        temp_env::with_var("OTEL_TRACES_SAMPLER", Some("always_on"), || {
+            let config = Config::default().with_sampler(Sampler::AlwaysOff);
+            assert_eq!(format!("{:?}", config.sampler), "AlwaysOff");
+        });
+    }
+
+    #[test]
+    fn span_limits_from_env_vars() {
+        type Limit = fn(&SpanLimits) -> Option<u32>;
+        let cases: [(&str, Limit); 4] = [
+            ("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT", |limits| {
+                Some(limits.max_attributes_per_span)
+            }),
+            ("OTEL_SPAN_EVENT_COUNT_LIMIT", |limits| {
+                Some(limits.max_events_per_span)
+            }),
+            ("OTEL_SPAN_LINK_COUNT_LIMIT", |limits| {
+                Some(limits.max_links_per_span)
+            }),
+            ("OTEL_SPAN_ATTRIBUTE_VALUE_LENGTH_LIMIT", |limits| {
+                limits.max_attribute_value_length
+            }),
+        ];
+        for (name, limit) in cases {
+            temp_env::with_var(name, Some("7"), || {
+                assert_eq!(limit(&Config::default().span_limits), Some(7), "{name}");
+            });
+        }
+    }
+
+    #[test]
+    fn malformed_span_limits_fall_back_to_defaults() {
+        let defaults = SpanLimits::default();
+        temp_env::with_vars(
+            [
+                ("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT", Some("many")),
+                ("OTEL_SPAN_EVENT_COUNT_LIMIT", Some("-1")),
+                ("OTEL_SPAN_LINK_COUNT_LIMIT", Some("")),
+                ("OTEL_SPAN_ATTRIBUTE_VALUE_LENGTH_LIMIT", Some("1.5")),
+            ],
+            || assert_eq!(Config::default().span_limits, defaults),
+        );
+    }
+
+    #[test]
+    fn explicit_span_limits_win_over_env_vars() {
+        temp_env::with_vars(
+            [
+                ("OTEL_SPAN_ATTRIBUTE_COUNT_LIMIT", Some("7")),
+                ("OTEL_SPAN_LINK_COUNT_LIMIT", Some("7")),
+            ],
+            || {
+                let config = Config::default().with_max_attributes_per_span(3);
+                assert_eq!(config.span_limits.max_attributes_per_span, 3);
+                assert_eq!(config.span_limits.max_links_per_span, 7);
+
+                let config = Config::default().with_span_limits(SpanLimits::default());
+                assert_eq!(config.span_limits, SpanLimits::default());
+            },
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use opentelemetry::trace::{SpanId, TraceId};
-use rand::{rngs, Rng, SeedableRng};
+use rand::{rngs, Rng, RngCore, SeedableRng};
 use std::cell::RefCell;
 use std::fmt;
+use std::sync::{Arc, Mutex};
 
 /// Interface for generating IDs
-pub trait IdGenerator: Send + Sync + fmt::Debug {
+pub trait IdGenerator: CloneIdGenerator + Send + Sync + fmt::Debug {
     /// Generate a new `TraceId`
     fn new_trace_id(&self) -> TraceId;
 
@@ -12,21 +13,100 @@ pub trait IdGenerator: Send + Sync + fmt::Debug {
     fn new_span_id(&self) -> SpanId;
 }
 
+/// This trait should not be used directly instead users should use [`IdGenerator`].
+pub trait CloneIdGenerator {
+    fn box_clone(&self) -> Box<dyn IdGenerator>;
+}
+
+impl<T> CloneIdGenerator for T
+where
+    T: IdGenerator + Clone + 'static,
+{
+    fn box_clone(&self) -> Box<dyn IdGenerator> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn IdGenerator> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
 /// Default [`IdGenerator`] implementation.
 ///
-/// Generates Trace and Span ids using a random number generator.
-#[derive(Clone, Debug, Default)]
+/// Generates Trace and Span ids using a random number generator, a thread
+/// local one seeded from the OS by default, or the one set with
+/// [`RandomIdGenerator::with_rng`]. Ids are never all zeros.
+#[derive(Clone, Default)]
 pub struct RandomIdGenerator {
-    _private: (),
+    rng: Option<Arc<Mutex<Box<dyn RngCore + Send>>>>,
+}
+
+impl RandomIdGenerator {
+    /// Create a generator drawing ids from `rng`, e.g. an entropy source
+    /// approved for a FIPS environment, or a fixed sequence in tests.
+    ///
+    /// The generator and its clones share `rng`, drawing from it under a lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{Config, RandomIdGenerator, TracerProvider};
+    /// use rand::rngs::OsRng;
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_config(Config::default().with_id_generator(RandomIdGenerator::with_rng(OsRng)))
+    ///     .build();
+    /// ```
+    pub fn with_rng<R: RngCore + Send + 'static>(rng: R) -> Self {
+        RandomIdGenerator {
+            rng: Some(Arc::new(Mutex::new(Box::new(rng)))),
+        }
+    }
+}
+
+impl fmt::Debug for RandomIdGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomIdGenerator")
+            .field("custom_rng", &self.rng.is_some())
+            .finish()
+    }
 }
 
 impl IdGenerator for RandomIdGenerator {
     fn new_trace_id(&self) -> TraceId {
-        CURRENT_RNG.with(|rng| TraceId::from(rng.borrow_mut().gen::<u128>()))
+        match &self.rng {
+            Some(rng) => non_zero_trace_id(&mut **rng.lock().unwrap_or_else(|e| e.into_inner())),
+            None => CURRENT_RNG.with(|rng| non_zero_trace_id(&mut *rng.borrow_mut())),
+        }
     }
 
     fn new_span_id(&self) -> SpanId {
-        CURRENT_RNG.with(|rng| SpanId::from(rng.borrow_mut().gen::<u64>()))
+        match &self.rng {
+            Some(rng) => non_zero_span_id(&mut **rng.lock().unwrap_or_else(|e| e.into_inner())),
+            None => CURRENT_RNG.with(|rng| non_zero_span_id(&mut *rng.borrow_mut())),
+        }
+    }
+}
+
+/// Draws trace ids from `rng` until one isn't all zeros, which is invalid.
+fn non_zero_trace_id<R: Rng + ?Sized>(rng: &mut R) -> TraceId {
+    loop {
+        let id = rng.gen::<u128>();
+        if id != 0 {
+            return TraceId::from(id);
+        }
+    }
+}
+
+/// Draws span ids from `rng` until one isn't all zeros, which is invalid.
+fn non_zero_span_id<R: Rng + ?Sized>(rng: &mut R) -> SpanId {
+    loop {
+        let id = rng.gen::<u64>();
+        if id != 0 {
+            return SpanId::from(id);
+        }
     }
 }
 
@@ -34,3 +114,103 @@ thread_local! {
     /// Store random number generator for each thread
     static CURRENT_RNG: RefCell<rngs::SmallRng> = RefCell::new(rngs::SmallRng::from_entropy());
 }
+
+/// [`IdGenerator`] producing a deterministic sequence of ids from a seed.
+///
+/// Two generators created with the same seed generate the same trace and
+/// span ids in the same order, which makes exported spans reproducible, e.g.
+/// for snapshot tests. Ids are never all zeros. The generator is shared by
+/// all tracers of a provider, so the sequence seen by each tracer depends on
+/// the order in which spans are started across threads.
+///
+/// The ids are predictable and shouldn't be used outside of tests.
+///
+/// A clone starts from the current state of the sequence and then advances
+/// independently, generating the same ids as the original.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{config, SeededIdGenerator, TracerProvider};
+///
+/// let provider = TracerProvider::builder()
+///     .with_config(config().with_id_generator(SeededIdGenerator::new(42)))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct SeededIdGenerator {
+    rng: Mutex<rngs::StdRng>,
+}
+
+impl SeededIdGenerator {
+    /// Create a generator whose sequence of ids is determined by `seed`.
+    pub fn new(seed: u64) -> Self {
+        SeededIdGenerator {
+            rng: Mutex::new(rngs::StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl Clone for SeededIdGenerator {
+    fn clone(&self) -> Self {
+        let rng = self.rng.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        SeededIdGenerator {
+            rng: Mutex::new(rng),
+        }
+    }
+}
+
+impl IdGenerator for SeededIdGenerator {
+    fn new_trace_id(&self) -> TraceId {
+        non_zero_trace_id(&mut *self.rng.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        non_zero_span_id(&mut *self.rng.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdGenerator, RandomIdGenerator, SeededIdGenerator};
+    use opentelemetry::trace::{SpanId, TraceId};
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let ids = |generator: &SeededIdGenerator| {
+            (0..100)
+                .map(|_| (generator.new_trace_id(), generator.new_span_id()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = ids(&SeededIdGenerator::new(42));
+        assert_eq!(first, ids(&SeededIdGenerator::new(42)));
+        assert_ne!(first, ids(&SeededIdGenerator::new(43)));
+        assert!(first.iter().all(
+            |(trace_id, span_id)| *trace_id != TraceId::INVALID && *span_id != SpanId::INVALID
+        ));
+    }
+
+    #[test]
+    fn seeded_clone_continues_the_sequence() {
+        let generator = SeededIdGenerator::new(42);
+        generator.new_trace_id();
+
+        let clone = generator.clone();
+        assert_eq!(generator.new_trace_id(), clone.new_trace_id());
+        assert_eq!(generator.new_span_id(), clone.new_span_id());
+    }
+
+    #[test]
+    fn random_generator_with_rng() {
+        // yields 0, 1, 2, ..
+        let generator = RandomIdGenerator::with_rng(StepRng::new(0, 1));
+
+        // 0 is an invalid id and is skipped
+        assert_eq!(generator.new_span_id(), SpanId::from_u64(1));
+        assert_eq!(generator.new_trace_id(), TraceId::from_u128((3 << 64) | 2));
+        // clones share the rng
+        assert_eq!(generator.clone().new_span_id(), SpanId::from_u64(4));
+    }
+}
@@ -6,6 +6,7 @@
 //! * The [`Span`] struct with is a mutable object storing information about the
 //!   current operation execution.
 //! * The [`TracerProvider`] struct which configures and produces [`Tracer`]s.
+mod clock;
 mod config;
 mod events;
 mod id_generator;
@@ -17,24 +18,45 @@ mod span_limit;
 mod span_processor;
 mod tracer;
 
-pub use config::{config, Config};
+pub use clock::{Clock, SystemClock};
+pub use config::{config, Config, ConfigSnapshot};
 pub use events::SpanEvents;
 
-pub use id_generator::{IdGenerator, RandomIdGenerator};
+pub use id_generator::{IdGenerator, RandomIdGenerator, SeededIdGenerator};
 pub use links::SpanLinks;
-pub use provider::{Builder, TracerProvider};
-pub use sampler::{Sampler, ShouldSample};
+pub use provider::{
+    Builder, BuilderError, DiagnosticKind, FlushReport, PostShutdownSpanPolicy, ProcessorSelector,
+    ResourceMergeStrategy, ShutdownError, ShutdownGuard, SpanMetrics, StatusCounts, TracerProvider,
+    TracerSpec, TruncationStats,
+};
+pub use sampler::{
+    AdaptiveSampler, AttributeSampler, BaggageSampler, CachingSampler, CompositeMode,
+    CompositeSampler, LinkAwareSampler, OperationRatioSampler, ParentBasedSampler,
+    ProbabilityRecordingSampler, RateLimitingSampler, RecordOnlySampler, Sampler, SamplingSchedule,
+    ScheduledRatioSampler, ShadowReport, ShadowSampler, ShouldSample, TraceStateMergePolicy,
+};
 pub use span::Span;
 pub use span_limit::SpanLimits;
 pub use span_processor::{
-    BatchConfig, BatchConfigBuilder, BatchSpanProcessor, BatchSpanProcessorBuilder,
-    SimpleSpanProcessor, SpanProcessor,
+    AttributeEnrichingSpanProcessor, BatchConfig, BatchConfigBuilder, BatchSpanProcessor,
+    BatchSpanProcessorBuilder, CohortSplittingProcessor, DroppedSpanCounts,
+    EventSamplingSpanProcessor, ExportDeadline, ExportMetrics, FilteringSpanProcessor,
+    LatencySpanProcessor, MultiSpanProcessor, OnEndStatus, OverflowPolicy, ProcessorStats,
+    ProcessorStatus, ProviderTelemetry, QueueDepthGauge, SimpleSpanProcessor,
+    SimpleSpanProcessorBuilder, SpanNameRewritingProcessor, SpanProcessor,
+    ThreadLocalBufferingProcessor,
 };
 pub use tracer::Tracer;
 
 #[cfg(feature = "jaeger_remote_sampler")]
 pub use sampler::{JaegerRemoteSampler, JaegerRemoteSamplerBuilder};
 
+#[cfg(feature = "watched_file_sampler")]
+pub use sampler::WatchedFileSampler;
+
+#[cfg(feature = "shutdown_signals")]
+pub use provider::Signal;
+
 #[cfg(test)]
 mod runtime_tests;
 
@@ -359,4 +381,45 @@ mod tests {
         assert_eq!(attributes[0].key, "test_k".into());
         assert_eq!(attributes[0].value, "test_v".into());
     }
+
+    #[test]
+    fn resource_attributes_on_spans() {
+        // Arrange
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(SimpleSpanProcessor::new(Box::new(exporter.clone())))
+            .with_config(Config::default().with_resource(crate::Resource::new(vec![
+                KeyValue::new("service.name", "test_service"),
+                KeyValue::new("host.name", "test_host"),
+                KeyValue::new("not.copied", "value"),
+            ])))
+            .with_resource_attributes_on_spans(vec![
+                "service.name".into(),
+                "host.name".into(),
+                "missing".into(),
+            ])
+            .build();
+
+        // Act
+        let tracer = provider.tracer("test_tracer");
+        tracer
+            .span_builder("span_name")
+            .with_attributes(vec![KeyValue::new("host.name", "span_host")])
+            .start(&tracer)
+            .end();
+
+        // Assert
+        let exported_spans = exporter
+            .get_finished_spans()
+            .expect("Spans are expected to be exported.");
+        assert_eq!(exported_spans.len(), 1);
+        let span = &exported_spans[0];
+        assert_eq!(
+            span.attributes,
+            vec![
+                KeyValue::new("host.name", "span_host"),
+                KeyValue::new("service.name", "test_service"),
+            ]
+        );
+    }
 }
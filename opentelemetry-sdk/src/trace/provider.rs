@@ -8,19 +8,39 @@
 //! propagators) are provided by the [`TracerProvider`]. [`Tracer`] instances do
 //! not duplicate this data to avoid that different [`Tracer`] instances
 //! of the [`TracerProvider`] have different versions of these data.
-use crate::runtime::RuntimeChannel;
+use crate::resource::{AsyncResourceDetector, EnvResourceDetector, ResourceDetector};
+use crate::runtime::{Runtime, RuntimeChannel};
+use crate::trace::tracer::DefaultSpanAttributes;
 use crate::trace::{
-    BatchSpanProcessor, Config, RandomIdGenerator, Sampler, SimpleSpanProcessor, SpanLimits, Tracer,
+    BatchConfig, BatchConfigBuilder, BatchSpanProcessor, Config, ConfigSnapshot, OnEndStatus,
+    ProcessorStatus, ProviderTelemetry, RandomIdGenerator, Sampler, ShouldSample,
+    SimpleSpanProcessor, SpanLimits, SystemClock, TraceStateMergePolicy, Tracer,
 };
 use crate::{export::trace::SpanExporter, trace::SpanProcessor};
 use crate::{InstrumentationLibrary, Resource};
 use once_cell::sync::{Lazy, OnceCell};
-use opentelemetry::trace::TraceError;
-use opentelemetry::{global, trace::TraceResult};
+use opentelemetry::trace::{Status, TraceError};
+use opentelemetry::{global, trace::TraceResult, Key, KeyValue, Value};
 use std::borrow::Cow;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Index;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use std::{panic, thread};
+use thiserror::Error;
 
+mod snapshot;
+
+use snapshot::SnapshotCell;
+
+/// Upper bound on the time a shutdown on OS signals waits for span processors,
+/// see [`TracerProvider::install_shutdown_on_signals`].
+#[cfg(feature = "shutdown_signals")]
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default upper bound on the time [`Builder::build`] waits for resource detectors.
+const DEFAULT_DETECTOR_TIMEOUT: Duration = Duration::from_secs(5);
 /// Default tracer name if empty string is provided.
 const DEFAULT_COMPONENT_NAME: &str = "rust.opentelemetry.io/sdk/tracer";
 static PROVIDER_RESOURCE: OnceCell<Resource> = OnceCell::new();
@@ -28,390 +48,4722 @@ static PROVIDER_RESOURCE: OnceCell<Resource> = OnceCell::new();
 // a no nop tracer provider used as placeholder when the provider is shutdown
 static NOOP_TRACER_PROVIDER: Lazy<TracerProvider> = Lazy::new(|| TracerProvider {
     inner: Arc::new(TracerProviderInner {
-        processors: Vec::new(),
+        processors: Default::default(),
         config: Config {
             // cannot use default here as the default resource is not empty
             sampler: Box::new(Sampler::ParentBased(Box::new(Sampler::AlwaysOn))),
             id_generator: Box::<RandomIdGenerator>::default(),
             span_limits: SpanLimits::default(),
+            clock: Box::new(SystemClock),
             resource: Cow::Owned(Resource::empty()),
+            trace_state_merge_policy: TraceStateMergePolicy::default(),
+            attribute_value_truncation_marker: Cow::Borrowed(""),
         },
+        status_counter: StatusCounter::default(),
+        truncation_counter: TruncationCounter::default(),
+        span_counter: SpanCounter::default(),
+        post_shutdown_spans: PostShutdownSpans::default(),
+        resource_attributes_on_spans: DefaultSpanAttributes::default(),
+        tracer_samplers: Vec::new(),
+        on_sampling_drop: None,
+        on_processor_drop: None,
+        on_span_end: None,
+        diagnostic_filter: None,
+        default_tracer_name: Cow::Borrowed(DEFAULT_COMPONENT_NAME),
+        tracer_cache: Default::default(),
     }),
     is_shutdown: Arc::new(AtomicBool::new(true)),
+    shutdown_signal: Arc::new(ShutdownSignal::completed()),
 });
 
 /// TracerProvider inner type
 #[derive(Debug)]
 pub(crate) struct TracerProviderInner {
-    processors: Vec<Box<dyn SpanProcessor>>,
+    // swapped as a whole by `reload_processors` and `replace_processor`,
+    // callers work on a snapshot
+    processors: SnapshotCell<Vec<Arc<dyn SpanProcessor>>>,
     config: crate::trace::Config,
+    status_counter: StatusCounter,
+    truncation_counter: TruncationCounter,
+    span_counter: SpanCounter,
+    post_shutdown_spans: PostShutdownSpans,
+    // resolved against the resource once, as it doesn't change
+    resource_attributes_on_spans: DefaultSpanAttributes,
+    tracer_samplers: Vec<TracerSampler>,
+    on_sampling_drop: Option<OnSamplingDrop>,
+    on_processor_drop: Option<OnProcessorDrop>,
+    on_span_end: Option<OnSpanEnd>,
+    diagnostic_filter: Option<DiagnosticFilter>,
+    // name of the tracers requested with an empty name
+    default_tracer_name: Cow<'static, str>,
+    tracer_cache: TracerCache,
 }
 
-impl Drop for TracerProviderInner {
-    fn drop(&mut self) {
-        for processor in &mut self.processors {
-            if let Err(err) = processor.shutdown() {
-                global::handle_error(err);
-            }
-        }
-    }
-}
+/// Maximum number of tracers a [`TracerCache`] holds.
+const TRACER_CACHE_CAPACITY: usize = 1024;
 
-/// Creator and registry of named [`Tracer`] instances.
+/// Tracers created so far, so that getting the same tracer again, e.g. on a
+/// hot path, neither allocates a new library nor looks its sampler up again.
 ///
-/// `TracerProvider` is lightweight container holding pointers to `SpanProcessor` and other components.
-/// Cloning and dropping them will not stop the span processing. To stop span processing, users
-/// must either call `shutdown` method explicitly, or drop every clone of `TracerProvider`.
-#[derive(Clone, Debug)]
-pub struct TracerProvider {
-    inner: Arc<TracerProviderInner>,
-    is_shutdown: Arc<AtomicBool>,
+/// Tracers are cached without their provider, which owns the cache, so the
+/// cache doesn't keep the provider alive. At most [`TRACER_CACHE_CAPACITY`]
+/// tracers are cached: once full, tracers missing from the cache are built
+/// anew on each call, so that tracer names or attributes derived from
+/// unbounded data don't grow the cache forever.
+#[derive(Debug, Default)]
+struct TracerCache {
+    tracers: RwLock<CachedTracers>,
 }
 
-impl Default for TracerProvider {
-    fn default() -> Self {
-        TracerProvider::builder().build()
-    }
+#[derive(Debug, Default)]
+struct CachedTracers {
+    // `InstrumentationLibrary` equality ignores attributes, tracers of
+    // libraries only differing by their attributes, or by their default span
+    // attributes, share a bucket
+    buckets: HashMap<InstrumentationLibrary, Vec<CachedTracer>>,
+    len: usize,
 }
 
-impl TracerProvider {
-    /// Build a new tracer provider
-    pub(crate) fn new(inner: TracerProviderInner) -> Self {
-        TracerProvider {
-            inner: Arc::new(inner),
-            is_shutdown: Arc::new(AtomicBool::new(false)),
-        }
-    }
-
-    /// Create a new [`TracerProvider`] builder.
-    pub fn builder() -> Builder {
-        Builder::default()
-    }
-
-    /// Span processors associated with this provider
-    pub(crate) fn span_processors(&self) -> &[Box<dyn SpanProcessor>] {
-        &self.inner.processors
-    }
+/// A [`Tracer`] without its provider.
+#[derive(Clone, Debug)]
+struct CachedTracer {
+    library: Arc<InstrumentationLibrary>,
+    sampler: Option<Arc<dyn ShouldSample>>,
+    default_span_attributes: Arc<DefaultSpanAttributes>,
+}
 
-    /// Config associated with this tracer
-    pub(crate) fn config(&self) -> &crate::trace::Config {
-        &self.inner.config
+impl CachedTracer {
+    fn matches(
+        &self,
+        library: &InstrumentationLibrary,
+        default_span_attributes: &[KeyValue],
+    ) -> bool {
+        self.library.attributes == library.attributes
+            && self.default_span_attributes.attributes() == default_span_attributes
     }
 
-    /// true if the provider has been shutdown
-    /// Don't start span or export spans when provider is shutdown
-    pub(crate) fn is_shutdown(&self) -> bool {
-        self.is_shutdown.load(Ordering::Relaxed)
+    fn into_tracer(self, provider: TracerProvider) -> Tracer {
+        Tracer::new(
+            self.library,
+            provider,
+            self.sampler,
+            self.default_span_attributes,
+        )
     }
+}
 
-    /// Force flush all remaining spans in span processors and return results.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use opentelemetry::global;
-    /// use opentelemetry_sdk::trace::TracerProvider;
-    ///
-    /// fn init_tracing() -> TracerProvider {
-    ///     let provider = TracerProvider::default();
-    ///
-    ///     // Set provider to be used as global tracer provider
-    ///     let _ = global::set_tracer_provider(provider.clone());
-    ///
-    ///     provider
-    /// }
-    ///
-    /// fn main() {
-    ///     let provider = init_tracing();
-    ///
-    ///     // create spans..
-    ///
-    ///     // force all spans to flush
-    ///     for result in provider.force_flush() {
-    ///         if let Err(err) = result {
-    ///             // .. handle flush error
-    ///         }
-    ///     }
-    ///
-    ///     // create more spans..
-    ///
-    ///     // dropping provider and shutting down global provider ensure all
-    ///     // remaining spans are exported
-    ///     drop(provider);
-    ///     global::shutdown_tracer_provider();
-    /// }
-    /// ```
-    pub fn force_flush(&self) -> Vec<TraceResult<()>> {
-        self.span_processors()
+impl TracerCache {
+    fn get(
+        &self,
+        library: &InstrumentationLibrary,
+        default_span_attributes: &[KeyValue],
+    ) -> Option<CachedTracer> {
+        let tracers = self
+            .tracers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        tracers
+            .buckets
+            .get(library)?
             .iter()
-            .map(|processor| processor.force_flush())
-            .collect()
+            .find(|cached| cached.matches(library, default_span_attributes))
+            .cloned()
     }
 
-    /// Shuts down the current `TracerProvider`.
-    ///
-    /// Note that shut down doesn't means the TracerProvider has dropped
-    pub fn shutdown(&self) -> TraceResult<()> {
-        if self
-            .is_shutdown
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .is_ok()
-        {
-            // propagate the shutdown signal to processors
-            // it's up to the processor to properly block new spans after shutdown
-            let mut errs = vec![];
-            for processor in &self.inner.processors {
-                if let Err(err) = processor.shutdown() {
-                    errs.push(err);
-                }
-            }
+    /// Returns the cached tracer of `library`, attributes included, and
+    /// `default_span_attributes`, creating it with the sampler returned by
+    /// `sampler` and caching it if there is none.
+    fn get_or_insert_with(
+        &self,
+        library: Arc<InstrumentationLibrary>,
+        default_span_attributes: DefaultSpanAttributes,
+        sampler: impl FnOnce(&InstrumentationLibrary) -> Option<Arc<dyn ShouldSample>>,
+    ) -> CachedTracer {
+        if let Some(cached) = self.get(&library, default_span_attributes.attributes()) {
+            return cached;
+        }
+        let mut tracers = self
+            .tracers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // another thread may have cached it in between
+        if let Some(cached) = tracers.buckets.get(library.as_ref()).and_then(|bucket| {
+            bucket
+                .iter()
+                .find(|cached| cached.matches(&library, default_span_attributes.attributes()))
+        }) {
+            return cached.clone();
+        }
 
-            if errs.is_empty() {
-                Ok(())
-            } else {
-                Err(TraceError::Other(format!("{errs:?}").into()))
-            }
-        } else {
-            Err(TraceError::Other(
-                "tracer provider already shut down".into(),
-            ))
+        let tracer = CachedTracer {
+            sampler: sampler(&library),
+            library,
+            default_span_attributes: Arc::new(default_span_attributes),
+        };
+        if tracers.len < TRACER_CACHE_CAPACITY {
+            tracers
+                .buckets
+                .entry(tracer.library.as_ref().clone())
+                .or_default()
+                .push(tracer.clone());
+            tracers.len += 1;
         }
+        tracer
     }
 }
 
-impl opentelemetry::trace::TracerProvider for TracerProvider {
-    /// This implementation of `TracerProvider` produces `Tracer` instances.
-    type Tracer = Tracer;
-
-    /// Create a new versioned `Tracer` instance.
-    fn versioned_tracer(
-        &self,
-        name: impl Into<Cow<'static, str>>,
-        version: Option<impl Into<Cow<'static, str>>>,
-        schema_url: Option<impl Into<Cow<'static, str>>>,
-        attributes: Option<Vec<opentelemetry::KeyValue>>,
-    ) -> Self::Tracer {
-        // Use default value if name is invalid empty string
-        let name = name.into();
-        let component_name = if name.is_empty() {
-            Cow::Borrowed(DEFAULT_COMPONENT_NAME)
-        } else {
-            name
-        };
+type SamplingDropCallback = Arc<dyn Fn(&str, &[KeyValue]) + Send + Sync>;
 
-        let mut builder = self.tracer_builder(component_name);
+/// Callback invoked with the name and attributes of spans dropped by the sampler.
+pub(crate) struct OnSamplingDrop(SamplingDropCallback);
 
-        if let Some(v) = version {
-            builder = builder.with_version(v);
-        }
-        if let Some(s) = schema_url {
-            builder = builder.with_schema_url(s);
-        }
-        if let Some(a) = attributes {
-            builder = builder.with_attributes(a);
-        }
+impl OnSamplingDrop {
+    pub(crate) fn call(&self, name: &str, attributes: &[KeyValue]) {
+        (self.0)(name, attributes)
+    }
+}
 
-        builder.build()
+impl fmt::Debug for OnSamplingDrop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnSamplingDrop")
     }
+}
 
-    fn library_tracer(&self, library: Arc<InstrumentationLibrary>) -> Self::Tracer {
-        if self.is_shutdown.load(Ordering::Relaxed) {
-            return Tracer::new(library, NOOP_TRACER_PROVIDER.clone());
-        }
-        Tracer::new(library, self.clone())
+type ProcessorDropCallback = Arc<dyn Fn(&str, OnEndStatus) + Send + Sync>;
+
+/// Callback invoked when a span processor drops an ended span.
+pub(crate) struct OnProcessorDrop(ProcessorDropCallback);
+
+impl OnProcessorDrop {
+    pub(crate) fn call(&self, processor: &str, status: OnEndStatus) {
+        (self.0)(processor, status)
     }
 }
 
-/// Builder for provider attributes.
-#[derive(Debug, Default)]
-pub struct Builder {
-    processors: Vec<Box<dyn SpanProcessor>>,
-    config: crate::trace::Config,
+impl fmt::Debug for OnProcessorDrop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnProcessorDrop")
+    }
 }
 
-impl Builder {
-    /// The `SpanExporter` that this provider should use.
-    pub fn with_simple_exporter<T: SpanExporter + 'static>(self, exporter: T) -> Self {
-        let mut processors = self.processors;
-        processors.push(Box::new(SimpleSpanProcessor::new(Box::new(exporter))));
+/// Callback invoked with every ended recording span.
+pub(crate) struct OnSpanEnd(Arc<dyn Fn(&crate::export::trace::SpanData) + Send + Sync>);
 
-        Builder { processors, ..self }
+impl OnSpanEnd {
+    pub(crate) fn call(&self, span: &crate::export::trace::SpanData) {
+        (self.0)(span)
     }
+}
 
-    /// The [`SpanExporter`] setup using a default [`BatchSpanProcessor`] that this provider should use.
-    pub fn with_batch_exporter<T: SpanExporter + 'static, R: RuntimeChannel>(
-        self,
-        exporter: T,
-        runtime: R,
-    ) -> Self {
-        let batch = BatchSpanProcessor::builder(exporter, runtime).build();
-        self.with_span_processor(batch)
+impl fmt::Debug for OnSpanEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnSpanEnd")
     }
+}
 
-    /// The [`SpanProcessor`] that this provider should use.
-    pub fn with_span_processor<T: SpanProcessor + 'static>(self, processor: T) -> Self {
-        let mut processors = self.processors;
-        processors.push(Box::new(processor));
+/// Whether a provider was built without span processors in this process, see
+/// [`Builder::allow_no_processors`].
+static NO_PROCESSORS_REPORTED: AtomicBool = AtomicBool::new(false);
 
-        Builder { processors, ..self }
+/// Category of the problems a [`TracerProvider`] reports to the global error
+/// handler, see [`Builder::with_diagnostic_filter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// The builder configuration is invalid or incomplete, e.g. the provider
+    /// is built without span processors.
+    Configuration,
+    /// A span processor failed to flush, e.g. when processors are reloaded.
+    ForceFlush,
+    /// A span processor failed to shut down, e.g. when the provider is
+    /// dropped.
+    Shutdown,
+    /// A span was started after the provider was shut down, see
+    /// [`Builder::on_post_shutdown_span`].
+    PostShutdownSpan,
+}
+
+/// Minimum time between two warnings about spans started after shutdown.
+const POST_SHUTDOWN_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A span processor of a [`TracerProvider`], see
+/// [`TracerProvider::replace_processor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessorSelector<'a> {
+    /// The processor at this index, in the order the processors were
+    /// registered.
+    Index(usize),
+    /// The first processor with this [name](SpanProcessor::name).
+    Name(&'a str),
+}
+
+impl From<usize> for ProcessorSelector<'_> {
+    fn from(index: usize) -> Self {
+        ProcessorSelector::Index(index)
     }
+}
 
-    /// The sdk [`crate::trace::Config`] that this provider will use.
-    pub fn with_config(self, config: crate::trace::Config) -> Self {
-        Builder { config, ..self }
+impl<'a> From<&'a str> for ProcessorSelector<'a> {
+    fn from(name: &'a str) -> Self {
+        ProcessorSelector::Name(name)
     }
+}
 
-    /// Create a new provider from this configuration.
-    pub fn build(self) -> TracerProvider {
-        let mut config = self.config;
+/// What a [`TracerProvider`] does when a span is started after it was shut
+/// down, see [`Builder::on_post_shutdown_span`].
+///
+/// Whatever the policy, such spans are not recording.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PostShutdownSpanPolicy {
+    /// Do nothing.
+    #[default]
+    Silent,
+    /// Report an error to the global error handler, at most once a minute.
+    Warn,
+    /// Count the spans, see [`TracerProvider::post_shutdown_spans`].
+    Count,
+}
 
-        // Standard config will contain an owned [`Resource`] (either sdk default or use supplied)
-        // we can optimize the common case with a static ref to avoid cloning the underlying
-        // resource data for each span.
-        //
-        // For the uncommon case where there are multiple tracer providers with different resource
-        // configurations, users can optionally provide their own borrowed static resource.
-        if matches!(config.resource, Cow::Owned(_)) {
-            config.resource = match PROVIDER_RESOURCE.try_insert(config.resource.into_owned()) {
-                Ok(static_resource) => Cow::Borrowed(static_resource),
-                Err((prev, new)) => {
-                    if prev == &new {
-                        Cow::Borrowed(prev)
-                    } else {
-                        Cow::Owned(new)
-                    }
-                }
-            }
-        }
+/// How the attributes of `OTEL_RESOURCE_ATTRIBUTES` combine with the resource
+/// configured in code, see [`Builder::with_resource_merge_strategy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResourceMergeStrategy {
+    /// The configured resource overrides the environment.
+    #[default]
+    UserWins,
+    /// The environment overrides the configured resource, e.g. so operators
+    /// can override attributes without a release.
+    EnvWins,
+    /// Attributes set with different values by the environment and the
+    /// configured resource are a configuration error, see
+    /// [`BuilderError::ResourceAttributeCollision`].
+    ErrorOnCollision,
+}
 
-        // Create a new vector to hold the modified processors
-        let mut processors = self.processors;
+/// Applies the [`PostShutdownSpanPolicy`] of a provider.
+#[derive(Debug, Default)]
+struct PostShutdownSpans {
+    policy: PostShutdownSpanPolicy,
+    count: AtomicU64,
+    last_warning: Mutex<Option<Instant>>,
+}
 
-        // Set the resource for each processor
-        for p in &mut processors {
-            p.set_resource(config.resource.as_ref());
+impl PostShutdownSpans {
+    fn new(policy: PostShutdownSpanPolicy) -> Self {
+        PostShutdownSpans {
+            policy,
+            ..Default::default()
         }
+    }
+}
+
+/// Filter deciding which diagnostics of a provider reach the global error
+/// handler.
+#[derive(Clone)]
+pub(crate) struct DiagnosticFilter(Arc<dyn Fn(DiagnosticKind) -> bool + Send + Sync>);
 
-        TracerProvider::new(TracerProviderInner { processors, config })
+impl fmt::Debug for DiagnosticFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DiagnosticFilter")
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::export::trace::SpanData;
-    use crate::resource::{
-        SERVICE_NAME, TELEMETRY_SDK_LANGUAGE, TELEMETRY_SDK_NAME, TELEMETRY_SDK_VERSION,
-    };
-    use crate::trace::provider::TracerProviderInner;
-    use crate::trace::{Config, Span, SpanProcessor};
-    use crate::Resource;
-    use opentelemetry::trace::{TraceError, TraceResult, Tracer, TracerProvider};
-    use opentelemetry::{Context, Key, KeyValue, Value};
-    use std::borrow::Cow;
-    use std::env;
-    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-    use std::sync::Arc;
+/// Reports `err` to the global error handler, unless `filter` drops its
+/// `kind`.
+fn report(filter: Option<&DiagnosticFilter>, kind: DiagnosticKind, err: TraceError) {
+    if filter.map_or(true, |filter| (filter.0)(kind)) {
+        global::handle_error(err);
+    }
+}
 
-    // fields below is wrapped with Arc so we can assert it
-    #[derive(Default, Debug)]
-    struct AssertInfo {
-        started_span: AtomicU32,
-        is_shutdown: AtomicBool,
+/// Sampler used instead of the provider one by the tracers of a library.
+#[derive(Debug)]
+struct TracerSampler {
+    name: Cow<'static, str>,
+    version: Option<Cow<'static, str>>,
+    sampler: Arc<dyn ShouldSample>,
+}
+
+impl TracerSampler {
+    fn matches(&self, library: &InstrumentationLibrary) -> bool {
+        self.name == library.name
+            && self
+                .version
+                .as_ref()
+                .map_or(true, |version| library.version.as_ref() == Some(version))
     }
+}
 
-    #[derive(Default, Debug, Clone)]
-    struct SharedAssertInfo(Arc<AssertInfo>);
+/// Instrumentation scope of a tracer created with [`TracerProvider::tracers`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TracerSpec {
+    name: Cow<'static, str>,
+    version: Option<Cow<'static, str>>,
+    schema_url: Option<Cow<'static, str>>,
+    attributes: Vec<KeyValue>,
+}
 
-    impl SharedAssertInfo {
-        fn started_span_count(&self, count: u32) -> bool {
-            self.0.started_span.load(Ordering::SeqCst) == count
+impl TracerSpec {
+    /// Create the scope of a tracer named `name`.
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        TracerSpec {
+            name: name.into(),
+            ..Default::default()
         }
     }
 
-    #[derive(Debug)]
-    struct TestSpanProcessor {
-        success: bool,
-        assert_info: SharedAssertInfo,
+    /// Set the version of the instrumentation library.
+    pub fn with_version(self, version: impl Into<Cow<'static, str>>) -> Self {
+        TracerSpec {
+            version: Some(version.into()),
+            ..self
+        }
     }
 
-    impl TestSpanProcessor {
-        fn new(success: bool) -> TestSpanProcessor {
-            TestSpanProcessor {
-                success,
-                assert_info: SharedAssertInfo::default(),
-            }
+    /// Set the schema url of the instrumentation library.
+    pub fn with_schema_url(self, schema_url: impl Into<Cow<'static, str>>) -> Self {
+        TracerSpec {
+            schema_url: Some(schema_url.into()),
+            ..self
         }
+    }
 
-        // get handle to assert info
-        fn assert_info(&self) -> SharedAssertInfo {
-            self.assert_info.clone()
+    /// Set the attributes of the instrumentation scope.
+    pub fn with_attributes(self, attributes: impl IntoIterator<Item = KeyValue>) -> Self {
+        TracerSpec {
+            attributes: attributes.into_iter().collect(),
+            ..self
         }
     }
+}
 
-    impl SpanProcessor for TestSpanProcessor {
-        fn on_start(&self, _span: &mut Span, _cx: &Context) {
-            self.assert_info
-                .0
-                .started_span
-                .fetch_add(1, Ordering::SeqCst);
-        }
+/// Invalid configuration rejected by [`Builder::build_checked`].
+#[derive(Clone, Debug, Error, PartialEq)]
+#[non_exhaustive]
+pub enum BuilderError {
+    /// The sampler, or a sampler it defers to, is a
+    /// [`Sampler::TraceIdRatioBased`] with a NaN ratio, see
+    /// [`ShouldSample::validate`].
+    #[error("the sampler ratio is NaN")]
+    NanSamplerRatio,
+    /// Resource detectors are set along with a zero detector timeout, so
+    /// none of them gets to detect anything.
+    #[error("resource detectors are set with a zero detector timeout")]
+    ZeroDetectorTimeout,
+    /// The batch span processors created by the builder, e.g. with
+    /// [`Builder::with_batch_exporter`], are configured by the
+    /// `OTEL_BSP_MAX_EXPORT_BATCH_SIZE` and `OTEL_BSP_MAX_QUEUE_SIZE`
+    /// environment variables to export batches larger than their queue. The
+    /// batch size is capped to the queue size.
+    #[error("the max export batch size {max_export_batch_size} is larger than the max queue size {max_queue_size}")]
+    ExportBatchLargerThanQueue {
+        /// The configured max export batch size.
+        max_export_batch_size: usize,
+        /// The configured max queue size.
+        max_queue_size: usize,
+    },
+    /// Several samplers are set for the same tracer name and version, only
+    /// the first one is ever used.
+    #[error("several samplers are set for tracer {name:?} with version {version:?}")]
+    DuplicateTracerSampler {
+        /// Name of the tracer.
+        name: Cow<'static, str>,
+        /// Version of the tracer, if the samplers are versioned.
+        version: Option<Cow<'static, str>>,
+    },
+    /// A resource attribute is set with different values by
+    /// `OTEL_RESOURCE_ATTRIBUTES` and the configured resource, with
+    /// [`ResourceMergeStrategy::ErrorOnCollision`].
+    #[error("resource attribute {key} is set to {env_value} by OTEL_RESOURCE_ATTRIBUTES but to {configured_value} in code")]
+    ResourceAttributeCollision {
+        /// Key of the attribute.
+        key: Key,
+        /// Value of the attribute in `OTEL_RESOURCE_ATTRIBUTES`.
+        env_value: Value,
+        /// Value of the attribute in the configured resource.
+        configured_value: Value,
+    },
+}
+
+/// Error returned when shutting down a [`TracerProvider`] fails.
+///
+/// Converts into a [`TraceError`], e.g. with `?` in functions returning a
+/// [`TraceResult`].
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum ShutdownError {
+    /// The provider was already shut down by an earlier call.
+    #[error("tracer provider already shut down")]
+    AlreadyShutdown,
+    /// The shutdown started by another caller did not complete in time.
+    #[error("tracer provider shutdown timed out after {0:?}")]
+    TimedOut(Duration),
+    /// Some span processors failed to shut down, or did not complete in
+    /// time, along with their [name](SpanProcessor::name). Processors that
+    /// shut down successfully are not listed.
+    ///
+    /// Errors are shared with the other callers that waited for the same
+    /// shutdown, see [`TracerProvider::await_shutdown`].
+    #[error("span processors failed to shut down: {}", format_processor_errors(.0))]
+    Processors(Vec<(String, Arc<TraceError>)>),
+}
+
+impl From<ShutdownError> for TraceError {
+    fn from(err: ShutdownError) -> Self {
+        TraceError::Other(Box::new(err))
+    }
+}
+
+fn format_processor_errors<E: fmt::Display>(errors: &[(String, E)]) -> String {
+    errors
+        .iter()
+        .map(|(name, err)| format!("{name}: {err}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn processors_result(errors: Vec<(String, Arc<TraceError>)>) -> Result<(), ShutdownError> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ShutdownError::Processors(errors))
+    }
+}
+
+/// Number of ended spans per [`Status`] observed by a [`TracerProvider`].
+///
+/// See [`TracerProvider::status_counts`].
+///
+/// [`Status`]: opentelemetry::trace::Status
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatusCounts {
+    /// Number of spans ended with [`Status::Unset`](opentelemetry::trace::Status::Unset).
+    pub unset: u64,
+    /// Number of spans ended with [`Status::Ok`](opentelemetry::trace::Status::Ok).
+    pub ok: u64,
+    /// Number of spans ended with [`Status::Error`](opentelemetry::trace::Status::Error).
+    pub error: u64,
+}
+
+/// Lock free tally of ended spans by status.
+#[derive(Debug, Default)]
+pub(crate) struct StatusCounter {
+    unset: AtomicU64,
+    ok: AtomicU64,
+    error: AtomicU64,
+}
+
+impl StatusCounter {
+    pub(crate) fn record(&self, status: &Status) {
+        let counter = match status {
+            Status::Unset => &self.unset,
+            Status::Ok => &self.ok,
+            Status::Error { .. } => &self.error,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        for counter in [&self.unset, &self.ok, &self.error] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> StatusCounts {
+        StatusCounts {
+            unset: self.unset.load(Ordering::Relaxed),
+            ok: self.ok.load(Ordering::Relaxed),
+            error: self.error.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Number of span attributes, events and links dropped because they exceeded
+/// the [`SpanLimits`] of a [`TracerProvider`].
+///
+/// See [`TracerProvider::truncation_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TruncationStats {
+    /// Span attributes dropped because of
+    /// [`SpanLimits::max_attributes_per_span`].
+    pub attributes: u64,
+    /// Span events dropped because of [`SpanLimits::max_events_per_span`].
+    pub events: u64,
+    /// Span links dropped because of [`SpanLimits::max_links_per_span`].
+    pub links: u64,
+    /// Event attributes dropped because of
+    /// [`SpanLimits::max_attributes_per_event`].
+    pub event_attributes: u64,
+    /// Link attributes dropped because of
+    /// [`SpanLimits::max_attributes_per_link`].
+    pub link_attributes: u64,
+}
+
+/// Lock free tally of span data dropped by the span limits.
+#[derive(Debug, Default)]
+pub(crate) struct TruncationCounter {
+    attributes: AtomicU64,
+    events: AtomicU64,
+    links: AtomicU64,
+    event_attributes: AtomicU64,
+    link_attributes: AtomicU64,
+}
+
+impl TruncationCounter {
+    pub(crate) fn record_attributes(&self, count: usize) {
+        Self::add(&self.attributes, count)
+    }
+
+    pub(crate) fn record_events(&self, count: usize) {
+        Self::add(&self.events, count)
+    }
+
+    pub(crate) fn record_links(&self, count: usize) {
+        Self::add(&self.links, count)
+    }
+
+    pub(crate) fn record_event_attributes(&self, count: usize) {
+        Self::add(&self.event_attributes, count)
+    }
+
+    pub(crate) fn record_link_attributes(&self, count: usize) {
+        Self::add(&self.link_attributes, count)
+    }
+
+    fn add(counter: &AtomicU64, count: usize) {
+        if count > 0 {
+            counter.fetch_add(count as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.attributes,
+            &self.events,
+            &self.links,
+            &self.event_attributes,
+            &self.link_attributes,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> TruncationStats {
+        TruncationStats {
+            attributes: self.attributes.load(Ordering::Relaxed),
+            events: self.events.load(Ordering::Relaxed),
+            links: self.links.load(Ordering::Relaxed),
+            event_attributes: self.event_attributes.load(Ordering::Relaxed),
+            link_attributes: self.link_attributes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Number of recording spans started and ended by a [`TracerProvider`].
+///
+/// See [`TracerProvider::span_metrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpanMetrics {
+    /// Number of recording spans started.
+    pub started_total: u64,
+    /// Number of recording spans ended.
+    pub ended_total: u64,
+    /// Number of recording spans started but not ended yet.
+    pub active: u64,
+}
+
+/// Lock free tally of started and ended recording spans.
+#[derive(Debug, Default)]
+pub(crate) struct SpanCounter {
+    started: AtomicU64,
+    ended: AtomicU64,
+}
+
+impl SpanCounter {
+    pub(crate) fn record_start(&self) {
+        self.started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_end(&self) {
+        self.ended.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> SpanMetrics {
+        // load ended first, so that a span ending in between can't make it
+        // exceed started
+        let ended_total = self.ended.load(Ordering::Relaxed);
+        let started_total = self.started.load(Ordering::Relaxed);
+        SpanMetrics {
+            started_total,
+            ended_total,
+            active: started_total.saturating_sub(ended_total),
+        }
+    }
+}
+
+/// Lets callers wait for an in progress shutdown to complete.
+#[derive(Debug, Default)]
+struct ShutdownSignal {
+    state: Mutex<ShutdownState>,
+    cvar: Condvar,
+    /// A task listens for OS signals to shut the provider down.
+    #[cfg(feature = "shutdown_signals")]
+    signals_installed: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct ShutdownState {
+    /// The processors that failed in the shutdown of the caller that started
+    /// it.
+    result: Option<Vec<(String, Arc<TraceError>)>>,
+}
+
+impl ShutdownSignal {
+    fn completed() -> Self {
+        ShutdownSignal {
+            state: Mutex::new(ShutdownState {
+                result: Some(Vec::new()),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn set_result(&self, errors: &[(String, Arc<TraceError>)]) {
+        if let Ok(mut state) = self.state.lock() {
+            state.result = Some(errors.to_vec());
+        }
+        self.cvar.notify_all();
+    }
+
+    fn has_result(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .result
+            .is_some()
+    }
+
+    /// Waits up to `timeout`, or without limit if `None`, for the caller that
+    /// started the shutdown to return and returns the same result.
+    fn wait_result(&self, timeout: Option<Duration>) -> Result<(), ShutdownError> {
+        let state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let state = match timeout {
+            Some(timeout) => {
+                self.cvar
+                    .wait_timeout_while(state, timeout, |state| state.result.is_none())
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .0
+            }
+            None => self
+                .cvar
+                .wait_while(state, |state| state.result.is_none())
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        };
+        match &state.result {
+            Some(errors) => processors_result(errors.clone()),
+            None => Err(ShutdownError::TimedOut(timeout.unwrap_or_default())),
+        }
+    }
+}
+
+// Processors are shut down inline rather than on a spawned task, so the last
+// drop of a provider right before the process exits still flushes its spans.
+impl Drop for TracerProviderInner {
+    fn drop(&mut self) {
+        for processor in self.processors.load().iter() {
+            if let Err(err) = processor.shutdown() {
+                report(
+                    self.diagnostic_filter.as_ref(),
+                    DiagnosticKind::Shutdown,
+                    err,
+                );
+            }
+        }
+    }
+}
+
+/// OS signals a provider can be shut down on, see
+/// [`TracerProvider::install_shutdown_on_signals`].
+#[cfg(feature = "shutdown_signals")]
+#[cfg_attr(docsrs, doc(cfg(feature = "shutdown_signals")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Signal {
+    /// `SIGINT` on unix, `Ctrl-C` on Windows.
+    Interrupt,
+    /// `SIGTERM`, only supported on unix.
+    Terminate,
+}
+
+#[cfg(feature = "shutdown_signals")]
+impl Signal {
+    #[cfg(unix)]
+    fn listen(self) -> std::io::Result<tokio::signal::unix::Signal> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        signal(match self {
+            Signal::Interrupt => SignalKind::interrupt(),
+            Signal::Terminate => SignalKind::terminate(),
+        })
+    }
+
+    #[cfg(windows)]
+    fn listen(self) -> std::io::Result<tokio::signal::windows::CtrlC> {
+        match self {
+            Signal::Interrupt => tokio::signal::windows::ctrl_c(),
+            Signal::Terminate => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "SIGTERM can only be listened for on unix",
+            )),
+        }
+    }
+}
+
+/// Creator and registry of named [`Tracer`] instances.
+///
+/// `TracerProvider` is lightweight container holding pointers to `SpanProcessor` and other components.
+/// Cloning and dropping them will not stop the span processing. To stop span processing, users
+/// must either call `shutdown` method explicitly, or drop every clone of `TracerProvider`.
+#[derive(Clone, Debug)]
+pub struct TracerProvider {
+    inner: Arc<TracerProviderInner>,
+    is_shutdown: Arc<AtomicBool>,
+    shutdown_signal: Arc<ShutdownSignal>,
+}
+
+impl Default for TracerProvider {
+    fn default() -> Self {
+        // can't have processors, warning about it would be noise
+        TracerProvider::builder().allow_no_processors().build()
+    }
+}
+
+impl TracerProvider {
+    /// Build a new tracer provider
+    pub(crate) fn new(inner: TracerProviderInner) -> Self {
+        TracerProvider {
+            inner: Arc::new(inner),
+            is_shutdown: Arc::new(AtomicBool::new(false)),
+            shutdown_signal: Arc::new(ShutdownSignal::default()),
+        }
+    }
+
+    /// Create a new [`TracerProvider`] builder.
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Create a provider that drops every span.
+    ///
+    /// The provider has no span processors and never samples, so spans are
+    /// not recording and cost next to nothing. [`force_flush`] and
+    /// [`shutdown`] do nothing and return `Ok`. This is useful in tests or to
+    /// disable tracing in some builds while still passing a
+    /// [`TracerProvider`] around, e.g. to [`global::set_tracer_provider`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::global;
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let _ = global::set_tracer_provider(TracerProvider::noop());
+    /// ```
+    ///
+    /// [`force_flush`]: TracerProvider::force_flush
+    /// [`shutdown`]: TracerProvider::shutdown
+    pub fn noop() -> Self {
+        TracerProvider::new(TracerProviderInner {
+            processors: Default::default(),
+            config: Config {
+                sampler: Box::new(Sampler::AlwaysOff),
+                id_generator: Box::<RandomIdGenerator>::default(),
+                span_limits: SpanLimits::default(),
+                clock: Box::new(SystemClock),
+                resource: Cow::Owned(Resource::empty()),
+                trace_state_merge_policy: TraceStateMergePolicy::default(),
+                attribute_value_truncation_marker: Cow::Borrowed(""),
+            },
+            status_counter: StatusCounter::default(),
+            truncation_counter: TruncationCounter::default(),
+            span_counter: SpanCounter::default(),
+            post_shutdown_spans: PostShutdownSpans::default(),
+            resource_attributes_on_spans: DefaultSpanAttributes::default(),
+            tracer_samplers: Vec::new(),
+            on_sampling_drop: None,
+            on_processor_drop: None,
+            on_span_end: None,
+            diagnostic_filter: None,
+            default_tracer_name: Cow::Borrowed(DEFAULT_COMPONENT_NAME),
+            tracer_cache: Default::default(),
+        })
+    }
+
+    /// Snapshot of the span processors associated with this provider
+    ///
+    /// Processors reloaded afterwards don't affect the snapshot, spans keep
+    /// the one taken when they start so they end on the same processors.
+    pub(crate) fn span_processors(&self) -> Arc<Vec<Arc<dyn SpanProcessor>>> {
+        self.inner.processors.load()
+    }
+
+    /// Returns a snapshot of the configuration this provider was built with:
+    /// its sampler, span limits and resource, once defaults, environment
+    /// variables and resource detectors are applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn log_startup(provider: &TracerProvider) {
+    ///     let config = provider.effective_config();
+    ///     println!("sampler: {}", config.sampler);
+    ///     println!("span limits: {:?}", config.span_limits);
+    /// }
+    /// ```
+    pub fn effective_config(&self) -> ConfigSnapshot {
+        ConfigSnapshot::new(&self.inner.config)
+    }
+
+    /// Returns a copy of the configuration this provider was built with, to
+    /// build another provider configured the same way with
+    /// [`Builder::with_config`].
+    ///
+    /// The sampler, id generator and clock are cloned, so the copy can be
+    /// changed without affecting this provider.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{SpanProcessor, TracerProvider};
+    ///
+    /// fn debug_provider(
+    ///     provider: &TracerProvider,
+    ///     processor: impl SpanProcessor + 'static,
+    /// ) -> TracerProvider {
+    ///     TracerProvider::builder()
+    ///         .with_config(provider.config_template())
+    ///         .with_span_processor(processor)
+    ///         .build()
+    /// }
+    /// ```
+    pub fn config_template(&self) -> crate::trace::Config {
+        self.inner.config.clone()
+    }
+
+    /// Config associated with this tracer
+    pub(crate) fn config(&self) -> &crate::trace::Config {
+        &self.inner.config
+    }
+
+    /// Resource attributes copied onto every recording span
+    pub(crate) fn resource_attributes_on_spans(&self) -> &DefaultSpanAttributes {
+        &self.inner.resource_attributes_on_spans
+    }
+
+    /// Create a tracer for each of `specs`, in the same order.
+    ///
+    /// This is equivalent to calling [`versioned_tracer`] for each spec, but
+    /// cheaper for libraries registering many instrumentation scopes at
+    /// startup. Like other tracers, the tracers returned once the provider is
+    /// shut down are noops.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{TracerProvider, TracerSpec};
+    ///
+    /// let provider = TracerProvider::default();
+    /// let tracers = provider.tracers(vec![
+    ///     TracerSpec::new("my-http-client").with_version("1.0.0"),
+    ///     TracerSpec::new("my-db-client").with_version("2.1.0"),
+    /// ]);
+    /// ```
+    ///
+    /// [`versioned_tracer`]: opentelemetry::trace::TracerProvider::versioned_tracer
+    pub fn tracers(&self, specs: Vec<TracerSpec>) -> Vec<Tracer> {
+        let is_shutdown = self.is_shutdown();
+        specs
+            .into_iter()
+            .map(|spec| {
+                let name = if spec.name.is_empty() {
+                    self.inner.default_tracer_name.clone()
+                } else {
+                    spec.name
+                };
+                let mut builder =
+                    InstrumentationLibrary::builder(name).with_attributes(spec.attributes);
+                if let Some(version) = spec.version {
+                    builder = builder.with_version(version);
+                }
+                if let Some(schema_url) = spec.schema_url {
+                    builder = builder.with_schema_url(schema_url);
+                }
+                self.cached_tracer(builder.build(), is_shutdown)
+            })
+            .collect()
+    }
+
+    /// Tracer for `library`, without allocating a new `Arc` if an identical
+    /// tracer is cached already
+    fn cached_tracer(&self, library: InstrumentationLibrary, is_shutdown: bool) -> Tracer {
+        if !is_shutdown {
+            if let Some(cached) = self.inner.tracer_cache.get(&library, &[]) {
+                return cached.into_tracer(self.clone());
+            }
+        }
+        self.tracer_for(Arc::new(library), Vec::new(), is_shutdown)
+    }
+
+    /// Tracer for `library` adding `default_span_attributes` to its spans, a
+    /// noop one if the provider `is_shutdown`
+    pub(crate) fn tracer_for(
+        &self,
+        library: Arc<InstrumentationLibrary>,
+        default_span_attributes: Vec<KeyValue>,
+        is_shutdown: bool,
+    ) -> Tracer {
+        let default_span_attributes = DefaultSpanAttributes::new(default_span_attributes);
+        if is_shutdown {
+            let default_span_attributes = Arc::new(default_span_attributes);
+            // keep the provider if its spans are still watched
+            if self.inner.post_shutdown_spans.policy == PostShutdownSpanPolicy::Silent {
+                return Tracer::new(
+                    library,
+                    NOOP_TRACER_PROVIDER.clone(),
+                    None,
+                    default_span_attributes,
+                );
+            }
+            return Tracer::new(library, self.clone(), None, default_span_attributes);
+        }
+        self.inner
+            .tracer_cache
+            .get_or_insert_with(library, default_span_attributes, |library| {
+                self.tracer_sampler(library)
+            })
+            .into_tracer(self.clone())
+    }
+
+    /// The sampler overriding the provider one for tracers of `library`, if any
+    fn tracer_sampler(&self, library: &InstrumentationLibrary) -> Option<Arc<dyn ShouldSample>> {
+        self.inner
+            .tracer_samplers
+            .iter()
+            .find(|tracer_sampler| tracer_sampler.matches(library))
+            .map(|tracer_sampler| tracer_sampler.sampler.clone())
+    }
+
+    /// Callback to invoke when the sampler drops a span, if any
+    pub(crate) fn on_sampling_drop(&self) -> Option<&OnSamplingDrop> {
+        self.inner.on_sampling_drop.as_ref()
+    }
+
+    /// Callback to invoke when a span processor drops an ended span, if any
+    pub(crate) fn on_processor_drop(&self) -> Option<&OnProcessorDrop> {
+        self.inner.on_processor_drop.as_ref()
+    }
+
+    /// Callback to invoke with every ended recording span, if any
+    pub(crate) fn on_span_end(&self) -> Option<&OnSpanEnd> {
+        self.inner.on_span_end.as_ref()
+    }
+
+    /// Reports `err` to the global error handler, unless the diagnostic
+    /// filter of this provider drops its `kind`
+    fn report(&self, kind: DiagnosticKind, err: TraceError) {
+        report(self.inner.diagnostic_filter.as_ref(), kind, err)
+    }
+
+    /// Status tally updated by spans created from this provider
+    pub(crate) fn status_counter(&self) -> &StatusCounter {
+        &self.inner.status_counter
+    }
+
+    /// Tally of span data dropped by the span limits of this provider
+    pub(crate) fn truncation_counter(&self) -> &TruncationCounter {
+        &self.inner.truncation_counter
+    }
+
+    /// Tally of recording spans started and ended from this provider
+    pub(crate) fn span_counter(&self) -> &SpanCounter {
+        &self.inner.span_counter
+    }
+
+    /// Applies the [`PostShutdownSpanPolicy`] to a span started after the
+    /// provider was shut down
+    pub(crate) fn record_post_shutdown_span(&self) {
+        let post_shutdown_spans = &self.inner.post_shutdown_spans;
+        match post_shutdown_spans.policy {
+            PostShutdownSpanPolicy::Silent => {}
+            PostShutdownSpanPolicy::Warn => {
+                let now = Instant::now();
+                let mut last_warning = post_shutdown_spans
+                    .last_warning
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                if last_warning.map_or(true, |last| {
+                    now.duration_since(last) >= POST_SHUTDOWN_WARNING_INTERVAL
+                }) {
+                    *last_warning = Some(now);
+                    drop(last_warning);
+                    self.report(
+                        DiagnosticKind::PostShutdownSpan,
+                        TraceError::Other(
+                            "span started after the tracer provider was shut down".into(),
+                        ),
+                    );
+                }
+            }
+            PostShutdownSpanPolicy::Count => {
+                post_shutdown_spans.count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns the number of spans started after the provider was shut
+    /// down, when configured with [`PostShutdownSpanPolicy::Count`], `0`
+    /// otherwise.
+    pub fn post_shutdown_spans(&self) -> u64 {
+        self.inner.post_shutdown_spans.count.load(Ordering::Relaxed)
+    }
+
+    /// true if the provider has been shutdown
+    /// Don't start span or export spans when provider is shutdown
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of spans ended so far, grouped by their [`Status`].
+    ///
+    /// The tally is updated when a span is ended and handed to the span
+    /// processors. Spans ended after the provider has been shut down are not
+    /// counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::trace::{Span, Status, Tracer, TracerProvider as _};
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let provider = TracerProvider::default();
+    /// let tracer = provider.tracer("example");
+    ///
+    /// let mut span = tracer.start("failing");
+    /// span.set_status(Status::error("boom"));
+    /// span.end();
+    ///
+    /// let counts = provider.status_counts();
+    /// let error_rate = counts.error as f64 / (counts.unset + counts.ok + counts.error) as f64;
+    /// assert_eq!(error_rate, 1.0);
+    /// ```
+    pub fn status_counts(&self) -> StatusCounts {
+        self.inner.status_counter.snapshot()
+    }
+
+    /// Returns the number of span attributes, events and links dropped so
+    /// far because they exceeded the configured [`SpanLimits`].
+    ///
+    /// Data is dropped silently when a span reaches its limits, this tally
+    /// helps to tell whether the limits are too low.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn report_truncation(provider: &TracerProvider) {
+    ///     let stats = provider.truncation_stats();
+    ///     if stats.attributes > 0 {
+    ///         println!("{} span attributes dropped, consider raising the limit", stats.attributes);
+    ///     }
+    /// }
+    /// ```
+    pub fn truncation_stats(&self) -> TruncationStats {
+        self.inner.truncation_counter.snapshot()
+    }
+
+    /// Returns the number of spans started and ended so far, and the number
+    /// of spans currently active, i.e. started but not ended yet.
+    ///
+    /// Only recording spans are counted, spans dropped by the sampler are
+    /// not. A span is counted as ended once, when it is ended or dropped,
+    /// including after the provider has been shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn report_capacity(provider: &TracerProvider) {
+    ///     let metrics = provider.span_metrics();
+    ///     println!("{} spans started, {} active", metrics.started_total, metrics.active);
+    /// }
+    /// ```
+    pub fn span_metrics(&self) -> SpanMetrics {
+        self.inner.span_counter.snapshot()
+    }
+
+    /// Returns the self-telemetry of every span processor, e.g. queue depths,
+    /// exported and dropped span counts and export latency, aggregated in a
+    /// single value suitable for a metrics endpoint.
+    ///
+    /// Processors that don't report stats through [`SpanProcessor::stats`]
+    /// are listed as `None`. With the `serde` feature enabled the returned
+    /// value is serializable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn report_metrics(provider: &TracerProvider) {
+    ///     let telemetry = provider.telemetry_snapshot();
+    ///     println!("queued spans: {}", telemetry.total.queue_depth);
+    ///     println!("dropped spans: {:?}", telemetry.total.dropped_spans);
+    /// }
+    /// ```
+    pub fn telemetry_snapshot(&self) -> ProviderTelemetry {
+        ProviderTelemetry::new(
+            self.span_processors()
+                .iter()
+                .map(|processor| processor.stats())
+                .collect(),
+        )
+    }
+
+    /// Returns the health of every span processor, in the order the
+    /// processors were registered, e.g. to answer a readiness probe.
+    ///
+    /// Unlike [`force_flush`](TracerProvider::force_flush) this doesn't wait
+    /// for anything to be exported. Processors that don't report a status
+    /// through [`SpanProcessor::status`] are listed with their
+    /// [name](SpanProcessor::name) and the shutdown state of the provider
+    /// only.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn is_ready(provider: &TracerProvider) -> bool {
+    ///     provider
+    ///         .pipeline_status()
+    ///         .iter()
+    ///         .all(|status| !status.is_shutdown && status.queue_depth.unwrap_or(0) < 1024)
+    /// }
+    /// ```
+    pub fn pipeline_status(&self) -> Vec<ProcessorStatus> {
+        self.span_processors()
+            .iter()
+            .map(|processor| {
+                processor
+                    .status()
+                    .unwrap_or_else(|| ProcessorStatus::new(processor.name(), self.is_shutdown()))
+            })
+            .collect()
+    }
+
+    /// Force flush all remaining spans in span processors and return results.
+    ///
+    /// Processors are flushed concurrently. The returned [`FlushReport`] holds
+    /// the result of each processor along with its
+    /// [name](SpanProcessor::name), in the order the processors were
+    /// registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::global;
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn init_tracing() -> TracerProvider {
+    ///     let provider = TracerProvider::default();
+    ///
+    ///     // Set provider to be used as global tracer provider
+    ///     let _ = global::set_tracer_provider(provider.clone());
+    ///
+    ///     provider
+    /// }
+    ///
+    /// fn main() {
+    ///     let provider = init_tracing();
+    ///
+    ///     // create spans..
+    ///
+    ///     // force all spans to flush
+    ///     for (processor, result) in provider.force_flush().iter() {
+    ///         if let Err(err) = result {
+    ///             // .. handle flush error of `processor`
+    ///         }
+    ///     }
+    ///
+    ///     // create more spans..
+    ///
+    ///     // dropping provider and shutting down global provider ensure all
+    ///     // remaining spans are exported
+    ///     drop(provider);
+    ///     global::shutdown_tracer_provider();
+    /// }
+    /// ```
+    pub fn force_flush(&self) -> FlushReport {
+        let processors = self.span_processors();
+        force_flush_processors(
+            processors
+                .iter()
+                .map(|processor| processor.as_ref())
+                .collect(),
+        )
+    }
+
+    /// Force flush the span processors whose [name](SpanProcessor::name)
+    /// matches `predicate`, e.g. to flush a fast exporter without waiting for
+    /// a slow one.
+    ///
+    /// Like [`force_flush`](TracerProvider::force_flush), matching processors
+    /// are flushed concurrently and the report lists them in the order they
+    /// were registered. The report is empty if no processor matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn flush_before_crash_dump(provider: &TracerProvider) {
+    ///     let report = provider.force_flush_matching(|name| name.contains("SimpleSpanProcessor"));
+    ///     for (processor, err) in report.errors() {
+    ///         eprintln!("failed to flush {processor}: {err}");
+    ///     }
+    /// }
+    /// ```
+    pub fn force_flush_matching<F>(&self, predicate: F) -> FlushReport
+    where
+        F: Fn(&str) -> bool,
+    {
+        let processors = self.span_processors();
+        force_flush_processors(
+            processors
+                .iter()
+                .map(|processor| processor.as_ref())
+                .filter(|processor| predicate(processor.name()))
+                .collect(),
+        )
+    }
+
+    /// Force flushes the span processors on the calling thread, waiting at
+    /// most `timeout` for them, e.g. from the `Drop` implementation of a type
+    /// owning the provider.
+    ///
+    /// Processors are flushed one after the other with what is left of
+    /// `timeout`, see [`SpanProcessor::force_flush_with_timeout`]: a batch
+    /// processor stops waiting for its worker once the time is up, while
+    /// processors flushing inline, like the simple processor, run to
+    /// completion. Processors reached after `timeout` elapsed are not flushed
+    /// and get an error. Returns the results in the order the processors were
+    /// registered.
+    ///
+    /// Called from a thread of a Tokio runtime, which blocking could stall
+    /// along with the batch processors it drives, every result is an error
+    /// and nothing is flushed. Use [`force_flush`] from a blocking task there
+    /// instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    /// use std::time::Duration;
+    ///
+    /// struct App {
+    ///     provider: TracerProvider,
+    /// }
+    ///
+    /// impl Drop for App {
+    ///     fn drop(&mut self) {
+    ///         for result in self.provider.force_flush_blocking(Duration::from_secs(1)) {
+    ///             if let Err(err) = result {
+    ///                 eprintln!("failed to flush spans: {err}");
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`force_flush`]: TracerProvider::force_flush
+    pub fn force_flush_blocking(&self, timeout: Duration) -> Vec<TraceResult<()>> {
+        let processors = self.span_processors();
+        if in_tokio_runtime() {
+            return processors
+                .iter()
+                .map(|_| {
+                    Err(TraceError::from(
+                        "cannot block a Tokio runtime thread to flush span processors",
+                    ))
+                })
+                .collect();
+        }
+
+        let deadline = Instant::now() + timeout;
+        processors
+            .iter()
+            .map(|processor| {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(TraceError::from(format!(
+                        "did not flush within {timeout:?}"
+                    )));
+                }
+                processor.force_flush_with_timeout(remaining)
+            })
+            .collect()
+    }
+
+    /// Force flush all remaining spans in span processors and return results
+    /// in the order the processors were registered.
+    #[deprecated(since = "0.25.0", note = "Please use force_flush() instead")]
+    pub fn force_flush_results(&self) -> Vec<TraceResult<()>> {
+        self.force_flush().into_iter().collect()
+    }
+
+    /// Force flushes the span processors, like
+    /// [`force_flush`](TracerProvider::force_flush), then resets the stats
+    /// of the provider and its processors to zero, e.g. to measure each
+    /// cycle of a benchmark separately.
+    ///
+    /// The reset covers the [status counts](TracerProvider::status_counts),
+    /// the [truncation stats](TracerProvider::truncation_stats) and the
+    /// [processor stats](SpanProcessor::reset_stats). Spans ending while the
+    /// stats are reset may be counted in either cycle. The provider keeps
+    /// working as before.
+    ///
+    /// Returns the flush results in the order the processors were
+    /// registered.
+    pub fn flush_and_reset_stats(&self) -> Vec<TraceResult<()>> {
+        let results = self.force_flush().into_iter().collect();
+
+        self.inner.status_counter.reset();
+        self.inner.truncation_counter.reset();
+        for processor in self.span_processors().iter() {
+            processor.reset_stats();
+        }
+        results
+    }
+
+    /// Warms up the exporters of the span processors, e.g. to establish
+    /// their connections at startup instead of on the first export, see
+    /// [`SpanExporter::warmup`].
+    ///
+    /// Processors are warmed up concurrently. Spans ending after the
+    /// returned future completes are exported after the warmup. Returns an
+    /// error naming the processors whose warmup failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// async fn init_tracing(provider: TracerProvider) -> TracerProvider {
+    ///     if let Err(err) = provider.warmup().await {
+    ///         eprintln!("exporters are not reachable yet: {err}");
+    ///     }
+    ///     provider
+    /// }
+    /// ```
+    pub async fn warmup(&self) -> TraceResult<()> {
+        let processors = self.span_processors();
+        let results =
+            futures_util::future::join_all(processors.iter().map(|processor| processor.warmup()))
+                .await;
+        let errors: Vec<_> = processors
+            .iter()
+            .zip(results)
+            .filter_map(|(processor, result)| {
+                result.err().map(|err| (processor.name().to_string(), err))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TraceError::Other(
+                format!(
+                    "span processors failed to warm up: {}",
+                    format_processor_errors(&errors)
+                )
+                .into(),
+            ))
+        }
+    }
+
+    /// Replaces the span processors of this provider by `processors`, e.g.
+    /// to switch to another export endpoint without building and registering
+    /// a new provider.
+    ///
+    /// The swap is atomic: spans starting or ending after it only reach the
+    /// new processors, and a span never reaches a mix of old and new ones.
+    /// The old processors are flushed and shut down on a background thread,
+    /// errors are reported to the global error handler.
+    ///
+    /// Returns an error, leaving the processors unchanged, if the provider is
+    /// shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{SpanProcessor, TracerProvider};
+    ///
+    /// fn reload(provider: &TracerProvider, processors: Vec<Box<dyn SpanProcessor>>) {
+    ///     provider
+    ///         .reload_processors(processors)
+    ///         .expect("provider is not shut down");
+    /// }
+    /// ```
+    pub fn reload_processors(
+        &self,
+        mut processors: Vec<Box<dyn SpanProcessor>>,
+    ) -> TraceResult<()> {
+        for processor in &mut processors {
+            processor.set_resource(self.config().resource.as_ref());
+        }
+
+        let old_processors = {
+            let mut current = self.inner.processors.lock();
+            // checked under the lock so a concurrent shutdown either sees
+            // the new processors or makes the reload fail
+            if self.is_shutdown() {
+                return Err(TraceError::Other(
+                    "cannot reload the processors of a shut down tracer provider".into(),
+                ));
+            }
+            current.store(processors.into_iter().map(Arc::from).collect())
+        };
+
+        self.retire_processors(old_processors);
+        Ok(())
+    }
+
+    /// Replaces a single span processor of this provider, selected by its
+    /// index in registration order or by its [name](SpanProcessor::name), by
+    /// `processor`, e.g. to swap the processor of a failing exporter and
+    /// keep the others.
+    ///
+    /// Like with [`reload_processors`], the swap is atomic: a span reaches
+    /// either the old or the new processor, never none of them. The new
+    /// processor is given the resource of the provider before the swap. The
+    /// old processor is flushed and shut down on a background thread, errors
+    /// are reported to the global error handler.
+    ///
+    /// Returns an error, leaving the processors unchanged, if no processor
+    /// matches or if the provider is shut down. When several processors have
+    /// the same name, the first one is replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{ProcessorSelector, SpanProcessor, TracerProvider};
+    ///
+    /// fn replace_first(provider: &TracerProvider, processor: Box<dyn SpanProcessor>) {
+    ///     provider
+    ///         .replace_processor(ProcessorSelector::Index(0), processor)
+    ///         .expect("the provider has a first processor");
+    /// }
+    /// ```
+    ///
+    /// [`reload_processors`]: TracerProvider::reload_processors
+    pub fn replace_processor<'a>(
+        &self,
+        selector: impl Into<ProcessorSelector<'a>>,
+        mut processor: Box<dyn SpanProcessor>,
+    ) -> TraceResult<()> {
+        let selector = selector.into();
+        processor.set_resource(self.config().resource.as_ref());
+
+        let old_processor = {
+            let mut current = self.inner.processors.lock();
+            // checked under the lock so a concurrent shutdown either sees
+            // the new processor or makes the replacement fail
+            if self.is_shutdown() {
+                return Err(TraceError::Other(
+                    "cannot replace a processor of a shut down tracer provider".into(),
+                ));
+            }
+            let processors = current.current();
+            let index = match selector {
+                ProcessorSelector::Index(index) => Some(index).filter(|i| *i < processors.len()),
+                ProcessorSelector::Name(name) => processors.iter().position(|p| p.name() == name),
+            }
+            .ok_or_else(|| TraceError::Other(format!("no span processor {:?}", selector).into()))?;
+
+            // snapshots taken before keep the old processor
+            let mut processors = processors.to_vec();
+            let old_processor = std::mem::replace(&mut processors[index], Arc::from(processor));
+            current.store(processors);
+            old_processor
+        };
+
+        self.retire_processors(Arc::new(vec![old_processor]));
+        Ok(())
+    }
+
+    /// Flushes and shuts down `processors`, no longer used by this provider,
+    /// on a background thread.
+    fn retire_processors(&self, processors: Arc<Vec<Arc<dyn SpanProcessor>>>) {
+        let filter = self.inner.diagnostic_filter.clone();
+        thread::spawn(move || {
+            for processor in processors.iter() {
+                if let Err(err) = processor.force_flush() {
+                    report(filter.as_ref(), DiagnosticKind::ForceFlush, err);
+                }
+                if let Err(err) = processor.shutdown() {
+                    report(filter.as_ref(), DiagnosticKind::Shutdown, err);
+                }
+            }
+        });
+    }
+
+    /// Shuts down the current `TracerProvider`.
+    ///
+    /// Span processors are shut down one after the other on the calling
+    /// thread, with [`SpanProcessor::shutdown`], and waited for without a
+    /// time limit. Use
+    /// [`shutdown_with_timeout`](TracerProvider::shutdown_with_timeout) to
+    /// bound the wait.
+    ///
+    /// Span processors are shut down once, by the first call. Calls made
+    /// while that shutdown is in progress wait for it and return the same
+    /// result. Calls made after it returned get
+    /// [`ShutdownError::AlreadyShutdown`].
+    ///
+    /// Note that shut down doesn't means the TracerProvider has dropped
+    pub fn shutdown(&self) -> Result<(), ShutdownError> {
+        self.shutdown_within(None)
+    }
+
+    /// Shuts down the current `TracerProvider`, waiting at most `timeout` for
+    /// the span processors to shut down.
+    ///
+    /// Each processor is shut down with
+    /// [`SpanProcessor::shutdown_with_timeout`]. A single processor is shut
+    /// down on the calling thread, several ones concurrently, each on its own
+    /// thread, so a processor that hangs, e.g. because its exporter is stuck
+    /// on a dead connection, cannot block the others or the caller past
+    /// `timeout`. The returned [`ShutdownError::Processors`] lists the
+    /// processors that failed to shut down or did not complete in time, by
+    /// name. Those keep shutting down in the background.
+    ///
+    /// The provider is marked as shut down even if the timeout elapses, so
+    /// later calls return an error.
+    ///
+    /// Span processors are shut down once, by the first call. Calls made
+    /// while that shutdown is in progress, e.g. from a signal handler racing
+    /// with `main`, wait up to their own `timeout` for it and return the same
+    /// result. Calls made after it returned get
+    /// [`ShutdownError::AlreadyShutdown`].
+    pub fn shutdown_with_timeout(&self, timeout: Duration) -> Result<(), ShutdownError> {
+        self.shutdown_within(Some(timeout))
+    }
+
+    /// Shuts the provider down, waiting up to `timeout` for span processors,
+    /// or without limit if `None`.
+    fn shutdown_within(&self, timeout: Option<Duration>) -> Result<(), ShutdownError> {
+        if self.begin_shutdown() {
+            let errors: Vec<_> = match timeout {
+                Some(timeout) => self.shutdown_processors(timeout),
+                None => self
+                    .span_processors()
+                    .iter()
+                    .filter_map(|processor| {
+                        let err = processor.shutdown().err()?;
+                        Some((processor.name().to_string(), err))
+                    })
+                    .collect(),
+            }
+            .into_iter()
+            .map(|(name, err)| (name, Arc::new(err)))
+            .collect();
+            self.shutdown_signal.set_result(&errors);
+            processors_result(errors)
+        } else if self.shutdown_signal.has_result() {
+            Err(ShutdownError::AlreadyShutdown)
+        } else {
+            self.shutdown_signal.wait_result(timeout)
+        }
+    }
+
+    /// Shuts the provider down when the process receives one of `signals`,
+    /// waiting up to 5 seconds for the span processors, so that buffered
+    /// spans are exported when the service is asked to stop.
+    ///
+    /// A task listening for the signals is spawned on the current Tokio
+    /// runtime, it returns an error if called outside of one or if a signal
+    /// can't be listened for. Listening for a signal replaces its default
+    /// action: the process doesn't exit on it anymore, the application is
+    /// expected to exit on its own once the provider is shut down.
+    ///
+    /// Only the first successful call installs a listener, later calls
+    /// return `Ok` without doing anything. Shutting the provider down
+    /// explicitly, before or after the signal, is fine: whichever comes
+    /// second gets [`ShutdownError::AlreadyShutdown`], which the listener
+    /// ignores.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{Signal, TracerProvider};
+    ///
+    /// // called from the service's Tokio runtime
+    /// fn drain_on_stop(provider: &TracerProvider) {
+    ///     provider
+    ///         .install_shutdown_on_signals(&[Signal::Interrupt, Signal::Terminate])
+    ///         .expect("cannot listen for signals");
+    /// }
+    /// ```
+    #[cfg(feature = "shutdown_signals")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "shutdown_signals")))]
+    pub fn install_shutdown_on_signals(&self, signals: &[Signal]) -> TraceResult<()> {
+        if signals.is_empty()
+            || self
+                .shutdown_signal
+                .signals_installed
+                .swap(true, Ordering::SeqCst)
+        {
+            return Ok(());
+        }
+        let result = self.spawn_signal_listener(signals);
+        if result.is_err() {
+            self.shutdown_signal
+                .signals_installed
+                .store(false, Ordering::SeqCst);
+        }
+        result
+    }
+
+    #[cfg(feature = "shutdown_signals")]
+    fn spawn_signal_listener(&self, signals: &[Signal]) -> TraceResult<()> {
+        let runtime =
+            tokio::runtime::Handle::try_current().map_err(|err| TraceError::Other(err.into()))?;
+        // listeners are registered now, so no signal raised after this
+        // returns is missed
+        let _entered = runtime.enter();
+        let mut listeners = signals
+            .iter()
+            .map(|signal| signal.listen())
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(|err| TraceError::Other(err.into()))?;
+
+        // the listener doesn't keep the provider alive
+        let inner = Arc::downgrade(&self.inner);
+        let is_shutdown = self.is_shutdown.clone();
+        let shutdown_signal = self.shutdown_signal.clone();
+        runtime.spawn(async move {
+            futures_util::future::select_all(
+                listeners
+                    .iter_mut()
+                    .map(|listener| Box::pin(listener.recv())),
+            )
+            .await;
+            let provider = match inner.upgrade() {
+                Some(inner) => TracerProvider {
+                    inner,
+                    is_shutdown,
+                    shutdown_signal,
+                },
+                None => return,
+            };
+            // processors may block, or need this runtime to shut down
+            let _ = tokio::task::spawn_blocking(move || {
+                match provider.shutdown_with_timeout(DEFAULT_SHUTDOWN_TIMEOUT) {
+                    Ok(()) | Err(ShutdownError::AlreadyShutdown) => {}
+                    Err(err) => provider.report(DiagnosticKind::Shutdown, err.into()),
+                }
+            })
+            .await;
+        });
+        Ok(())
+    }
+
+    /// Returns a guard that shuts down the provider when it is dropped.
+    ///
+    /// Holding the guard in `main` makes sure the spans still buffered in span
+    /// processors are exported when the application exits, including when it
+    /// returns early with an error. The guard blocks in [`Drop`] until the
+    /// shutdown completes, see [`shutdown`](TracerProvider::shutdown).
+    ///
+    /// If the provider was already shut down when the guard is dropped, e.g.
+    /// by an explicit call to [`shutdown`](TracerProvider::shutdown), the
+    /// guard does nothing. Conversely, calling `shutdown` after the guard was
+    /// dropped returns the "already shut down" error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::global;
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// fn main() {
+    ///     let provider = TracerProvider::default();
+    ///     let _guard = provider.shutdown_guard();
+    ///     let _ = global::set_tracer_provider(provider);
+    ///
+    ///     // create spans..
+    /// } // the provider is shut down here
+    /// ```
+    pub fn shutdown_guard(&self) -> ShutdownGuard {
+        ShutdownGuard {
+            provider: self.clone(),
+        }
+    }
+
+    /// Shuts down the current `TracerProvider` if it isn't already, and waits
+    /// up to `timeout` for all span processors to finish shutting down.
+    ///
+    /// Behaves like
+    /// [`shutdown_with_timeout`](TracerProvider::shutdown_with_timeout),
+    /// except once the shutdown returned: instead of
+    /// [`ShutdownError::AlreadyShutdown`], later calls get the result of the
+    /// shutdown right away.
+    pub fn await_shutdown(&self, timeout: Duration) -> Result<(), ShutdownError> {
+        match self.shutdown_with_timeout(timeout) {
+            Err(ShutdownError::AlreadyShutdown) => self.shutdown_signal.wait_result(Some(timeout)),
+            result => result,
+        }
+    }
+
+    /// Flips the shutdown flag, returning false if the provider was already shut down.
+    fn begin_shutdown(&self) -> bool {
+        self.is_shutdown
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Returns the name and error of the processors that failed to shut down.
+    fn shutdown_processors(&self, timeout: Duration) -> Vec<(String, TraceError)> {
+        let processors = self.span_processors();
+        let processor_count = processors.len();
+        // propagate the shutdown signal to processors
+        // it's up to the processor to properly block new spans after shutdown
+        if processor_count <= 1 {
+            return processors
+                .iter()
+                .filter_map(|processor| {
+                    let err = processor.shutdown_with_timeout(timeout).err()?;
+                    Some((processor.name().to_string(), err))
+                })
+                .collect();
+        }
+
+        // one thread per processor so a hanging one cannot block the others
+        let (result_sender, result_receiver) = mpsc::channel();
+        for index in 0..processor_count {
+            let processors = processors.clone();
+            let result_sender = result_sender.clone();
+            thread::spawn(move || {
+                let result = processors[index].shutdown_with_timeout(timeout);
+                let _ = result_sender.send((index, result));
+            });
+        }
+        drop(result_sender);
+
+        let deadline = Instant::now() + timeout;
+        let mut completed = vec![false; processor_count];
+        let mut errs = vec![];
+        while completed.contains(&false) {
+            let remaining_time = deadline.saturating_duration_since(Instant::now());
+            match result_receiver.recv_timeout(remaining_time) {
+                Ok((index, result)) => {
+                    completed[index] = true;
+                    if let Err(err) = result {
+                        errs.push((index, err));
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+
+        errs.extend(
+            completed
+                .iter()
+                .enumerate()
+                .filter(|(_, completed)| !**completed)
+                .map(|(index, _)| {
+                    let err = format!("did not shut down within {timeout:?}");
+                    (index, TraceError::from(err))
+                }),
+        );
+        // in registration order, whatever the order processors completed in
+        errs.sort_by_key(|(index, _)| *index);
+        errs.into_iter()
+            .map(|(index, err)| (processors[index].name().to_string(), err))
+            .collect()
+    }
+}
+
+/// Whether the calling thread runs in a Tokio runtime, which blocking could
+/// stall.
+#[cfg(any(feature = "rt-tokio", feature = "rt-tokio-current-thread"))]
+fn in_tokio_runtime() -> bool {
+    tokio::runtime::Handle::try_current().is_ok()
+}
+
+#[cfg(not(any(feature = "rt-tokio", feature = "rt-tokio-current-thread")))]
+fn in_tokio_runtime() -> bool {
+    false
+}
+
+fn force_flush_processors(processors: Vec<&dyn SpanProcessor>) -> FlushReport {
+    let results: Vec<_> = if processors.len() <= 1 {
+        processors
+            .iter()
+            .map(|processor| processor.force_flush())
+            .collect()
+    } else {
+        force_flush_concurrently(&processors)
+    };
+
+    FlushReport {
+        results: processors
+            .iter()
+            .map(|processor| processor.name().to_string())
+            .zip(results)
+            .collect(),
+    }
+}
+
+fn force_flush_concurrently(processors: &[&dyn SpanProcessor]) -> Vec<TraceResult<()>> {
+    // flush concurrently so the total latency is the one of the slowest
+    // processor, results are collected in registration order
+    thread::scope(|scope| {
+        let flushes: Vec<_> = processors
+            .iter()
+            .map(|&processor| scope.spawn(move || processor.force_flush()))
+            .collect();
+        flushes
+            .into_iter()
+            .map(|flush| {
+                flush
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// Result of [`TracerProvider::force_flush`] for each span processor.
+///
+/// Use [`iter`](FlushReport::iter) to get the result of each processor along
+/// with the processor's [name](SpanProcessor::name). Indexing or iterating over
+/// the report by value yields the results alone, in the order the processors
+/// were registered.
+#[derive(Debug)]
+pub struct FlushReport {
+    results: Vec<(String, TraceResult<()>)>,
+}
+
+impl FlushReport {
+    /// Returns the name and flush result of each processor, in the order the
+    /// processors were registered.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TraceResult<()>)> {
+        self.results
+            .iter()
+            .map(|(name, result)| (name.as_str(), result))
+    }
+
+    /// Returns the name and error of each processor that failed to flush.
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &TraceError)> {
+        self.iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|err| (name, err)))
+    }
+
+    /// Returns true if every processor flushed successfully.
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Number of processors flushed.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns true if the provider has no processors.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+impl Index<usize> for FlushReport {
+    type Output = TraceResult<()>;
+
+    /// Returns the result of the processor registered at `index`.
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.results[index].1
+    }
+}
+
+impl IntoIterator for FlushReport {
+    type Item = TraceResult<()>;
+    type IntoIter = std::iter::Map<
+        std::vec::IntoIter<(String, TraceResult<()>)>,
+        fn((String, TraceResult<()>)) -> TraceResult<()>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter().map(|(_, result)| result)
+    }
+}
+
+/// Shuts down a [`TracerProvider`] when dropped.
+///
+/// See [`TracerProvider::shutdown_guard`].
+#[must_use = "dropping the guard immediately shuts down the provider"]
+#[derive(Debug)]
+pub struct ShutdownGuard {
+    provider: TracerProvider,
+}
+
+impl ShutdownGuard {
+    /// The provider shut down by this guard.
+    pub fn provider(&self) -> &TracerProvider {
+        &self.provider
+    }
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        if self.provider.is_shutdown() {
+            return;
+        }
+        if let Err(err) = self.provider.shutdown() {
+            self.provider.report(DiagnosticKind::Shutdown, err.into());
+        }
+    }
+}
+
+impl opentelemetry::trace::TracerProvider for TracerProvider {
+    /// This implementation of `TracerProvider` produces `Tracer` instances.
+    type Tracer = Tracer;
+
+    /// Create a new versioned `Tracer` instance.
+    fn versioned_tracer(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        version: Option<impl Into<Cow<'static, str>>>,
+        schema_url: Option<impl Into<Cow<'static, str>>>,
+        attributes: Option<Vec<opentelemetry::KeyValue>>,
+    ) -> Self::Tracer {
+        // Use default value if name is invalid empty string
+        let name = name.into();
+        let component_name = if name.is_empty() {
+            self.inner.default_tracer_name.clone()
+        } else {
+            name
+        };
+
+        let mut builder = InstrumentationLibrary::builder(component_name);
+
+        if let Some(v) = version {
+            builder = builder.with_version(v);
+        }
+        if let Some(s) = schema_url {
+            builder = builder.with_schema_url(s);
+        }
+        if let Some(a) = attributes {
+            builder = builder.with_attributes(a);
+        }
+
+        self.cached_tracer(builder.build(), self.is_shutdown())
+    }
+
+    fn tracer(&self, name: impl Into<Cow<'static, str>>) -> Self::Tracer {
+        self.cached_tracer(
+            InstrumentationLibrary::builder(name).build(),
+            self.is_shutdown(),
+        )
+    }
+
+    /// Create a new `Tracer` for `library`.
+    ///
+    /// Tracers of identical libraries, attributes included, are cached and
+    /// share the same `Arc<InstrumentationLibrary>` and sampler.
+    fn library_tracer(&self, library: Arc<InstrumentationLibrary>) -> Self::Tracer {
+        self.tracer_for(library, Vec::new(), self.is_shutdown())
+    }
+}
+
+/// Builder for provider attributes.
+#[derive(Debug, Default)]
+pub struct Builder {
+    processors: Vec<Box<dyn SpanProcessor>>,
+    // index in `processors` of the processors given their own resource
+    processor_resources: Vec<(usize, Resource)>,
+    // index in `processors` of the processors given a priority, others have 0
+    processor_priorities: Vec<(usize, i32)>,
+    config: crate::trace::Config,
+    resource: Option<Resource>,
+    detectors: Detectors,
+    async_detectors: AsyncDetectors,
+    detector_timeout: Option<Duration>,
+    resource_attributes_on_spans: Vec<Key>,
+    tracer_samplers: Vec<TracerSampler>,
+    on_sampling_drop: Option<OnSamplingDrop>,
+    on_processor_drop: Option<OnProcessorDrop>,
+    on_span_end: Option<OnSpanEnd>,
+    diagnostic_filter: Option<DiagnosticFilter>,
+    post_shutdown_span_policy: PostShutdownSpanPolicy,
+    resource_merge_strategy: ResourceMergeStrategy,
+    default_tracer_name: Option<Cow<'static, str>>,
+    resource_interning_disabled: bool,
+    no_processors_allowed: bool,
+    // whether batch processors configured by the environment were created
+    env_batch_processors: bool,
+}
+
+#[derive(Default)]
+struct Detectors(Vec<Box<dyn ResourceDetector + Send>>);
+
+impl fmt::Debug for Detectors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Detectors")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+#[derive(Default)]
+struct AsyncDetectors(Vec<Box<dyn AsyncResourceDetector>>);
+
+impl fmt::Debug for AsyncDetectors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncDetectors")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl Builder {
+    /// The `SpanExporter` that this provider should use.
+    pub fn with_simple_exporter<T: SpanExporter + 'static>(self, exporter: T) -> Self {
+        let mut processors = self.processors;
+        processors.push(Box::new(SimpleSpanProcessor::new(Box::new(exporter))));
+
+        Builder { processors, ..self }
+    }
+
+    /// The [`SpanExporter`] setup using a default [`BatchSpanProcessor`] that this provider should use.
+    pub fn with_batch_exporter<T: SpanExporter + 'static, R: RuntimeChannel>(
+        self,
+        exporter: T,
+        runtime: R,
+    ) -> Self {
+        let batch = BatchSpanProcessor::builder(exporter, runtime).build();
+        Builder {
+            env_batch_processors: true,
+            ..self.with_span_processor(batch)
+        }
+    }
+
+    /// The [`SpanExporter`]s, each setup using a default [`BatchSpanProcessor`],
+    /// that this provider should use, e.g. to export to several backends.
+    ///
+    /// The runtime is cloned for each exporter. An empty list of exporters
+    /// adds no processor.
+    pub fn with_batch_exporters<R: RuntimeChannel>(
+        self,
+        exporters: Vec<Box<dyn SpanExporter>>,
+        runtime: R,
+    ) -> Self {
+        exporters
+            .into_iter()
+            .fold(self, |builder, exporter| Builder {
+                env_batch_processors: true,
+                ..builder.with_span_processor(BatchSpanProcessor::new(
+                    exporter,
+                    BatchConfig::default(),
+                    runtime.clone(),
+                ))
+            })
+    }
+
+    /// The [`SpanProcessor`] that this provider should use.
+    pub fn with_span_processor<T: SpanProcessor + 'static>(self, processor: T) -> Self {
+        let mut processors = self.processors;
+        processors.push(Box::new(processor));
+
+        Builder { processors, ..self }
+    }
+
+    /// The [`SpanProcessor`] that this provider should use, dispatched to
+    /// according to `priority` rather than registration order.
+    ///
+    /// Processors are called in decreasing priority order at `on_start` and
+    /// `on_end`, e.g. so that an enriching processor always sees spans before
+    /// the exporting ones. Processors registered with the other methods have
+    /// a priority of `0`, and processors with the same priority keep their
+    /// registration order. Other methods going through all the processors,
+    /// like [`TracerProvider::force_flush`], follow the same order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::export::trace::SpanExporter;
+    /// use opentelemetry_sdk::trace::{SpanProcessor, TracerProvider};
+    ///
+    /// fn init_tracing<E, P>(exporter: E, enriching: P) -> TracerProvider
+    /// where
+    ///     E: SpanExporter + 'static,
+    ///     P: SpanProcessor + 'static,
+    /// {
+    ///     TracerProvider::builder()
+    ///         .with_simple_exporter(exporter)
+    ///         // runs before the exporter's processor
+    ///         .with_span_processor_ordered(enriching, 10)
+    ///         .build()
+    /// }
+    /// ```
+    pub fn with_span_processor_ordered<T: SpanProcessor + 'static>(
+        self,
+        processor: T,
+        priority: i32,
+    ) -> Self {
+        let mut builder = self.with_span_processor(processor);
+        let index = builder.processors.len() - 1;
+        builder.processor_priorities.push((index, priority));
+        builder
+    }
+
+    /// The [`SpanProcessor`] that this provider should use, with its own
+    /// resource.
+    ///
+    /// `resource` is merged into the provider resource, taking precedence,
+    /// and the result is given to the processor instead of the provider
+    /// resource, e.g. so that spans exported for another tenant carry its
+    /// `service.name`. The provider resource and the other processors are
+    /// unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_sdk::trace::{SpanProcessor, TracerProvider};
+    /// use opentelemetry_sdk::Resource;
+    ///
+    /// fn init_tracing<P, T>(processor: P, tenant_processor: T) -> TracerProvider
+    /// where
+    ///     P: SpanProcessor + 'static,
+    ///     T: SpanProcessor + 'static,
+    /// {
+    ///     TracerProvider::builder()
+    ///         .with_span_processor(processor)
+    ///         .with_span_processor_resource(
+    ///             tenant_processor,
+    ///             Resource::new(vec![KeyValue::new("service.name", "tenant-a")]),
+    ///         )
+    ///         .build()
+    /// }
+    /// ```
+    pub fn with_span_processor_resource<T: SpanProcessor + 'static>(
+        self,
+        processor: T,
+        resource: Resource,
+    ) -> Self {
+        let mut builder = self.with_span_processor(processor);
+        let index = builder.processors.len() - 1;
+        builder.processor_resources.push((index, resource));
+        builder
+    }
+
+    /// The sdk [`crate::trace::Config`] that this provider will use.
+    pub fn with_config(self, config: crate::trace::Config) -> Self {
+        Builder { config, ..self }
+    }
+
+    /// Describe the entity producing telemetry with `resource`.
+    ///
+    /// Unlike [`Config::with_resource`], the resource is merged into the
+    /// configured one instead of replacing it. By default that is the resource
+    /// detected from the SDK and the `OTEL_RESOURCE_ATTRIBUTES` and
+    /// `OTEL_SERVICE_NAME` environment variables, so detected attributes are
+    /// kept. When both define the same key, the value of `resource` wins.
+    ///
+    /// Calling this several times merges every resource, later ones winning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_sdk::{trace::TracerProvider, Resource};
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_resource(Resource::new([KeyValue::new("service.name", "checkout")]))
+    ///     .build();
+    /// ```
+    pub fn with_resource(self, resource: Resource) -> Self {
+        let resource = match self.resource {
+            Some(previous) => previous.merge(&resource),
+            None => resource,
+        };
+        Builder {
+            resource: Some(resource),
+            ..self
+        }
+    }
+
+    /// Detect the resource with `detectors` when the provider is built.
+    ///
+    /// The detected resource is merged into the configured one, by default
+    /// the resource detected from the environment, and the resource set with
+    /// [`Builder::with_resource`] is merged on top of it. When several
+    /// detectors return the same key, the value of the earliest detector wins.
+    ///
+    /// Detectors run concurrently and [`build`] waits for them at most for
+    /// the timeout set with [`Builder::with_detector_timeout`], 5 seconds by
+    /// default. A detector that doesn't return in time contributes nothing.
+    ///
+    /// Calling this several times adds detectors after the previous ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::resource::{EnvResourceDetector, ProcessResourceDetector};
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_detectors(vec![
+    ///         Box::new(EnvResourceDetector::new()),
+    ///         Box::new(ProcessResourceDetector),
+    ///     ])
+    ///     .build();
+    /// ```
+    ///
+    /// [`build`]: Builder::build
+    pub fn with_detectors(self, detectors: Vec<Box<dyn ResourceDetector + Send>>) -> Self {
+        let mut all = self.detectors.0;
+        all.extend(detectors);
+        Builder {
+            detectors: Detectors(all),
+            ..self
+        }
+    }
+
+    /// Detect the resource with the async `detectors` when the provider is
+    /// built with [`Builder::build_async`], [`build`] doesn't run them.
+    ///
+    /// The detected resource is merged like the one of the detectors set with
+    /// [`Builder::with_detectors`], below it: on conflicting keys, sync
+    /// detectors win over async ones, and the earliest async detector wins
+    /// over the later ones.
+    ///
+    /// Calling this several times adds detectors after the previous ones.
+    ///
+    /// [`build`]: Builder::build
+    pub fn with_async_detectors(self, detectors: Vec<Box<dyn AsyncResourceDetector>>) -> Self {
+        let mut all = self.async_detectors.0;
+        all.extend(detectors);
+        Builder {
+            async_detectors: AsyncDetectors(all),
+            ..self
+        }
+    }
+
+    /// How long [`Builder::build`] waits for the detectors set with
+    /// [`Builder::with_detectors`], and [`Builder::build_async`] for the ones
+    /// set with [`Builder::with_async_detectors`].
+    pub fn with_detector_timeout(self, timeout: Duration) -> Self {
+        Builder {
+            detector_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Copy the values of the given [`Resource`] attributes onto every span
+    /// when it starts.
+    ///
+    /// This is useful for backends that don't index resource attributes. Keys
+    /// that are missing from the resource are ignored.
+    ///
+    /// Copying adds one attribute per key to every recording span, costing an
+    /// allocation and a clone of the value for each of them. Copied attributes
+    /// count towards [`SpanLimits::max_attributes_per_span`] and are added
+    /// after the attributes provided when the span is started, so they're the
+    /// first ones to be dropped when the limit is exceeded. If a span is
+    /// started with an attribute with the same key, the span attribute wins
+    /// and the resource value is not copied.
+    pub fn with_resource_attributes_on_spans(self, keys: Vec<Key>) -> Self {
+        Builder {
+            resource_attributes_on_spans: keys,
+            ..self
+        }
+    }
+
+    /// Sample the spans of the tracers named `name` with `sampler` instead of
+    /// the sampler of the [`Config`].
+    ///
+    /// This lets a library use its own sampling, e.g. always sample the spans
+    /// of a database instrumentation, without changing the decision for the
+    /// other tracers. The sampler is picked when the tracer is created, for
+    /// any version of the library. When several overrides match a tracer,
+    /// the one registered first wins.
+    ///
+    /// The override replaces the provider sampler entirely, so wrap it in
+    /// [`Sampler::ParentBased`] for the spans of the library to keep following
+    /// the decision of their parent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{Sampler, TracerProvider};
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_tracer_sampler("db", Sampler::ParentBased(Box::new(Sampler::AlwaysOn)))
+    ///     .build();
+    /// ```
+    pub fn with_tracer_sampler<T: ShouldSample + 'static>(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        sampler: T,
+    ) -> Self {
+        self.push_tracer_sampler(name.into(), None, sampler)
+    }
+
+    /// Like [`Builder::with_tracer_sampler`], but only for the tracers of the
+    /// given `version` of the library.
+    pub fn with_versioned_tracer_sampler<T: ShouldSample + 'static>(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        version: impl Into<Cow<'static, str>>,
+        sampler: T,
+    ) -> Self {
+        self.push_tracer_sampler(name.into(), Some(version.into()), sampler)
+    }
+
+    /// Call `on_sampling_drop` with the name and start attributes of every
+    /// span the sampler decides to drop.
+    ///
+    /// Dropped spans never reach the span processors, so this is the only
+    /// way to observe them, e.g. to log that an instrumentation is being
+    /// sampled out. The callback is called on the thread starting the span,
+    /// so it should be cheap. Without a callback, which is the default,
+    /// dropping a span costs nothing extra.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_on_sampling_drop(|name, _attributes| println!("dropped span {name}"))
+    ///     .build();
+    /// ```
+    pub fn with_on_sampling_drop<F>(self, on_sampling_drop: F) -> Self
+    where
+        F: Fn(&str, &[KeyValue]) + Send + Sync + 'static,
+    {
+        Builder {
+            on_sampling_drop: Some(OnSamplingDrop(Arc::new(on_sampling_drop))),
+            ..self
+        }
+    }
+
+    /// Call `on_processor_drop` with the [name](SpanProcessor::name) of the
+    /// processor and the reason every time a span processor drops an ended
+    /// span, e.g. because the queue of a [`BatchSpanProcessor`] is full.
+    ///
+    /// This lets instrumentation back off, e.g. by creating fewer spans, when
+    /// the pipeline can't keep up. The callback is called on the thread
+    /// ending the span, so it should be cheap. Processors report drops from
+    /// [`SpanProcessor::try_on_end`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_on_processor_drop(|processor, status| {
+    ///         println!("{processor} dropped a span: {status:?}")
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn with_on_processor_drop<F>(self, on_processor_drop: F) -> Self
+    where
+        F: Fn(&str, OnEndStatus) + Send + Sync + 'static,
+    {
+        Builder {
+            on_processor_drop: Some(OnProcessorDrop(Arc::new(on_processor_drop))),
+            ..self
+        }
+    }
+
+    /// Call `on_span_end` with every recording span when it ends, before it
+    /// is handed to the span processors.
+    ///
+    /// This gives low latency integrations, e.g. a continuous profiler
+    /// correlating CPU samples with span boundaries, access to the ids and
+    /// timing of every recorded span, including the ones that are recorded
+    /// but not sampled and so never exported. The callback is called on the
+    /// thread ending the span, so it should be cheap. It is not called for
+    /// spans ending after the provider is shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_span_end_callback(|span| {
+    ///         println!(
+    ///             "span {} of trace {} took {:?}",
+    ///             span.span_context.span_id(),
+    ///             span.span_context.trace_id(),
+    ///             span.end_time.duration_since(span.start_time),
+    ///         )
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn with_span_end_callback<F>(self, on_span_end: F) -> Self
+    where
+        F: Fn(&crate::export::trace::SpanData) + Send + Sync + 'static,
+    {
+        Builder {
+            on_span_end: Some(OnSpanEnd(Arc::new(on_span_end))),
+            ..self
+        }
+    }
+
+    /// Choose what the provider does when a span is started after it was
+    /// shut down, which is usually a lifecycle bug. Such spans are not
+    /// recording, by default silently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{PostShutdownSpanPolicy, TracerProvider};
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .on_post_shutdown_span(PostShutdownSpanPolicy::Warn)
+    ///     .build();
+    /// ```
+    pub fn on_post_shutdown_span(self, policy: PostShutdownSpanPolicy) -> Self {
+        Builder {
+            post_shutdown_span_policy: policy,
+            ..self
+        }
+    }
+
+    /// Choose how the attributes of `OTEL_RESOURCE_ATTRIBUTES` combine with
+    /// the resource set with [`Builder::with_resource`] or the config, by
+    /// default [`ResourceMergeStrategy::UserWins`].
+    ///
+    /// With [`ResourceMergeStrategy::ErrorOnCollision`], [`build_checked`]
+    /// fails when an attribute is set with different values by both, while
+    /// [`build`] reports the collision and lets the configured resource win.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{ResourceMergeStrategy, TracerProvider};
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_resource_merge_strategy(ResourceMergeStrategy::EnvWins)
+    ///     .build();
+    /// ```
+    ///
+    /// [`build_checked`]: Builder::build_checked
+    /// [`build`]: Builder::build
+    pub fn with_resource_merge_strategy(self, strategy: ResourceMergeStrategy) -> Self {
+        Builder {
+            resource_merge_strategy: strategy,
+            ..self
+        }
+    }
+
+    /// Keep the resource of the provider to itself instead of sharing it in
+    /// a process wide static.
+    ///
+    /// By default the first provider built stores its resource in a static,
+    /// and later providers with an identical resource borrow it rather than
+    /// owning a copy. Disabling the interning avoids the resource of one
+    /// provider, e.g. of a test case, outliving it in that static, at the
+    /// cost of every provider owning its resource.
+    pub fn without_resource_interning(self) -> Self {
+        Builder {
+            resource_interning_disabled: true,
+            ..self
+        }
+    }
+
+    /// Only report to the global error handler the diagnostics of the
+    /// provider for which `filter` returns `true`, e.g. to silence transient
+    /// flush failures while still seeing shutdown failures.
+    ///
+    /// All diagnostics are reported by default. Errors reported by span
+    /// processors and exporters themselves, e.g. failed exports, are not
+    /// filtered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{DiagnosticKind, TracerProvider};
+    ///
+    /// let provider = TracerProvider::builder()
+    ///     .with_diagnostic_filter(|kind| kind != DiagnosticKind::ForceFlush)
+    ///     .build();
+    /// ```
+    pub fn with_diagnostic_filter<F>(self, filter: F) -> Self
+    where
+        F: Fn(DiagnosticKind) -> bool + Send + Sync + 'static,
+    {
+        Builder {
+            diagnostic_filter: Some(DiagnosticFilter(Arc::new(filter))),
+            ..self
+        }
+    }
+
+    /// Name the tracers requested with an empty name `name`, instead of
+    /// `rust.opentelemetry.io/sdk/tracer`, so that they are attributed to
+    /// the application rather than to the SDK.
+    pub fn with_default_tracer_name(self, name: impl Into<Cow<'static, str>>) -> Self {
+        Builder {
+            default_tracer_name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Build the provider without span processors quietly.
+    ///
+    /// A provider without processors records nothing, so the first one built
+    /// in the process is reported to the global error handler unless this is
+    /// set, e.g. for a provider whose processors are set later with
+    /// [`TracerProvider::reload_processors`].
+    pub fn allow_no_processors(self) -> Self {
+        Builder {
+            no_processors_allowed: true,
+            ..self
+        }
+    }
+
+    /// Whether building now would report the lack of span processors.
+    fn lacks_processors(&self) -> bool {
+        self.processors.is_empty() && !self.no_processors_allowed
+    }
+
+    fn push_tracer_sampler<T: ShouldSample + 'static>(
+        self,
+        name: Cow<'static, str>,
+        version: Option<Cow<'static, str>>,
+        sampler: T,
+    ) -> Self {
+        let mut tracer_samplers = self.tracer_samplers;
+        tracer_samplers.push(TracerSampler {
+            name,
+            version,
+            sampler: Arc::new(sampler),
+        });
+        Builder {
+            tracer_samplers,
+            ..self
+        }
+    }
+
+    /// Checks the configuration, returning the first invariant it breaks.
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.config.sampler.validate()?;
+        if !(self.detectors.0.is_empty() && self.async_detectors.0.is_empty())
+            && self.detector_timeout == Some(Duration::ZERO)
+        {
+            return Err(BuilderError::ZeroDetectorTimeout);
+        }
+        if self.env_batch_processors {
+            let (max_export_batch_size, max_queue_size) = BatchConfigBuilder::env_sizes();
+            if max_export_batch_size > max_queue_size {
+                return Err(BuilderError::ExportBatchLargerThanQueue {
+                    max_export_batch_size,
+                    max_queue_size,
+                });
+            }
+        }
+        for (i, tracer_sampler) in self.tracer_samplers.iter().enumerate() {
+            if self.tracer_samplers[..i].iter().any(|previous| {
+                previous.name == tracer_sampler.name && previous.version == tracer_sampler.version
+            }) {
+                return Err(BuilderError::DuplicateTracerSampler {
+                    name: tracer_sampler.name.clone(),
+                    version: tracer_sampler.version.clone(),
+                });
+            }
+        }
+        if self.resource_merge_strategy == ResourceMergeStrategy::ErrorOnCollision {
+            let configured = match &self.resource {
+                Some(resource) => self.config.resource.merge(resource),
+                None => self.config.resource.clone().into_owned(),
+            };
+            let env = EnvResourceDetector::new().detect(Duration::ZERO);
+            for (key, env_value) in env.iter() {
+                match configured.get(key.clone()) {
+                    Some(configured_value) if configured_value != *env_value => {
+                        return Err(BuilderError::ResourceAttributeCollision {
+                            key: key.clone(),
+                            env_value: env_value.clone(),
+                            configured_value,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a new provider from this configuration, or return a
+    /// [`BuilderError`] if it's invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_sdk::trace::{BuilderError, Config, Sampler, TracerProvider};
+    ///
+    /// let result = TracerProvider::builder()
+    ///     .with_config(Config::default().with_sampler(Sampler::TraceIdRatioBased(f64::NAN)))
+    ///     .build_checked();
+    /// assert_eq!(result.unwrap_err(), BuilderError::NanSamplerRatio);
+    /// ```
+    pub fn build_checked(self) -> Result<TracerProvider, BuilderError> {
+        self.validate()?;
+        Ok(self.build())
+    }
+
+    /// Create a new provider from this configuration, after running the
+    /// detectors set with [`Builder::with_async_detectors`].
+    ///
+    /// The async detectors are polled concurrently and share a single budget,
+    /// the timeout set with [`Builder::with_detector_timeout`], 5 seconds by
+    /// default, timed by `runtime`. The resources of the detectors done within
+    /// it are merged, the others contribute nothing. The provider is then
+    /// built like with [`Builder::build`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::future::{BoxFuture, FutureExt};
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_sdk::resource::AsyncResourceDetector;
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    /// use opentelemetry_sdk::Resource;
+    ///
+    /// #[derive(Debug)]
+    /// struct RegionDetector;
+    ///
+    /// impl AsyncResourceDetector for RegionDetector {
+    ///     fn detect(&self) -> BoxFuture<'_, Resource> {
+    ///         // e.g. query the instance metadata endpoint
+    ///         async { Resource::new(vec![KeyValue::new("cloud.region", "eu-west-1")]) }.boxed()
+    ///     }
+    /// }
+    ///
+    /// # #[cfg(feature = "rt-tokio")]
+    /// async fn init_tracing() -> TracerProvider {
+    ///     TracerProvider::builder()
+    ///         .with_async_detectors(vec![Box::new(RegionDetector)])
+    ///         .build_async(opentelemetry_sdk::runtime::Tokio)
+    ///         .await
+    /// }
+    /// ```
+    pub async fn build_async<R: Runtime>(mut self, runtime: R) -> TracerProvider {
+        let detectors = std::mem::take(&mut self.async_detectors.0);
+        if !detectors.is_empty() {
+            if self.detector_timeout == Some(Duration::ZERO) {
+                report(
+                    self.diagnostic_filter.as_ref(),
+                    DiagnosticKind::Configuration,
+                    TraceError::Other(BuilderError::ZeroDetectorTimeout.into()),
+                );
+                self.detector_timeout = None;
+            }
+            let timeout = self.detector_timeout.unwrap_or(DEFAULT_DETECTOR_TIMEOUT);
+            let detected =
+                Resource::from_async_detectors_with_deadline(timeout, detectors, &runtime).await;
+            // below the sync detectors, merged on top of the configured resource
+            self.config.resource = Cow::Owned(self.config.resource.merge(&detected));
+        }
+        self.build()
+    }
+
+    /// Create a new provider from this configuration.
+    ///
+    /// Invalid values are reported to the global error handler and replaced
+    /// by their defaults, e.g. a sampler with a NaN ratio is replaced by the
+    /// default `ParentBased(AlwaysOn)` sampler. Use [`Builder::build_checked`]
+    /// to reject them instead.
+    ///
+    /// Async detectors only run with [`Builder::build_async`], they are
+    /// reported to the global error handler and ignored here. So is the lack
+    /// of span processors, once per process, see
+    /// [`Builder::allow_no_processors`].
+    pub fn build(mut self) -> TracerProvider {
+        let filter = self.diagnostic_filter.clone();
+        let report_configuration =
+            |err: TraceError| report(filter.as_ref(), DiagnosticKind::Configuration, err);
+        // reported once, programs building providers on the fly, e.g. in
+        // tests, would flood the error handler otherwise
+        if self.lacks_processors() && !NO_PROCESSORS_REPORTED.swap(true, Ordering::Relaxed) {
+            report_configuration(TraceError::Other(
+                "tracer provider built without span processors, its spans are not exported".into(),
+            ));
+        }
+        if !self.async_detectors.0.is_empty() {
+            report_configuration(TraceError::Other(
+                "async resource detectors are ignored, use Builder::build_async to run them".into(),
+            ));
+        }
+        if let Err(err) = self.validate() {
+            report_configuration(TraceError::Other(err.into()));
+            if self.config.sampler.validate().is_err() {
+                self.config.sampler = Box::new(Sampler::ParentBased(Box::new(Sampler::AlwaysOn)));
+            }
+            if self.detector_timeout == Some(Duration::ZERO) {
+                self.detector_timeout = None;
+            }
+        }
+
+        let mut config = self.config;
+        if !self.detectors.0.is_empty() {
+            let detected = Resource::from_detectors_with_deadline(
+                self.detector_timeout.unwrap_or(DEFAULT_DETECTOR_TIMEOUT),
+                self.detectors.0,
+            );
+            config.resource = Cow::Owned(config.resource.merge(&detected));
+        }
+        if let Some(resource) = self.resource {
+            config.resource = Cow::Owned(config.resource.merge(&resource));
+        }
+        if self.resource_merge_strategy == ResourceMergeStrategy::EnvWins {
+            let env = EnvResourceDetector::new().detect(Duration::ZERO);
+            config.resource = Cow::Owned(config.resource.merge(&env));
+        }
+
+        // Standard config will contain an owned [`Resource`] (either sdk default or use supplied)
+        // we can optimize the common case with a static ref to avoid cloning the underlying
+        // resource data for each span.
+        //
+        // For the uncommon case where there are multiple tracer providers with different resource
+        // configurations, users can optionally provide their own borrowed static resource.
+        if !self.resource_interning_disabled && matches!(config.resource, Cow::Owned(_)) {
+            config.resource = match PROVIDER_RESOURCE.try_insert(config.resource.into_owned()) {
+                Ok(static_resource) => Cow::Borrowed(static_resource),
+                Err((prev, new)) => {
+                    if prev == &new {
+                        Cow::Borrowed(prev)
+                    } else {
+                        Cow::Owned(new)
+                    }
+                }
+            }
+        }
+
+        // Create a new vector to hold the modified processors
+        let mut processors = self.processors;
+
+        // Set the resource for each processor, before the provider exists so no
+        // span can reach a processor whose resource is not set yet
+        for (index, p) in processors.iter_mut().enumerate() {
+            match self
+                .processor_resources
+                .iter()
+                .find(|(processor_index, _)| *processor_index == index)
+            {
+                Some((_, resource)) => p.set_resource(&config.resource.merge(resource)),
+                None => p.set_resource(config.resource.as_ref()),
+            }
+        }
+
+        // stable, so processors of the same priority keep their registration order
+        if !self.processor_priorities.is_empty() {
+            let mut ordered: Vec<_> = processors
+                .into_iter()
+                .enumerate()
+                .map(|(index, processor)| {
+                    let priority = self
+                        .processor_priorities
+                        .iter()
+                        .find(|(processor_index, _)| *processor_index == index)
+                        .map_or(0, |(_, priority)| *priority);
+                    (priority, processor)
+                })
+                .collect();
+            ordered.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+            processors = ordered
+                .into_iter()
+                .map(|(_, processor)| processor)
+                .collect();
+        }
+
+        let resource_attributes_on_spans = DefaultSpanAttributes::new(
+            self.resource_attributes_on_spans
+                .into_iter()
+                .filter_map(|key| {
+                    let value = config.resource.get(key.clone())?;
+                    Some(KeyValue::new(key, value))
+                })
+                .collect(),
+        );
+        TracerProvider::new(TracerProviderInner {
+            processors: SnapshotCell::new(processors.into_iter().map(Arc::from).collect()),
+            config,
+            status_counter: StatusCounter::default(),
+            truncation_counter: TruncationCounter::default(),
+            span_counter: SpanCounter::default(),
+            post_shutdown_spans: PostShutdownSpans::new(self.post_shutdown_span_policy),
+            resource_attributes_on_spans,
+            tracer_samplers: self.tracer_samplers,
+            on_sampling_drop: self.on_sampling_drop,
+            on_processor_drop: self.on_processor_drop,
+            on_span_end: self.on_span_end,
+            diagnostic_filter: self.diagnostic_filter,
+            default_tracer_name: self
+                .default_tracer_name
+                .unwrap_or(Cow::Borrowed(DEFAULT_COMPONENT_NAME)),
+            tracer_cache: TracerCache::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::trace::SpanData;
+    use crate::resource::{
+        ResourceDetector, SERVICE_NAME, TELEMETRY_SDK_LANGUAGE, TELEMETRY_SDK_NAME,
+        TELEMETRY_SDK_VERSION,
+    };
+    use crate::trace::provider::{
+        BuilderError, DiagnosticKind, PostShutdownSpanPolicy, ResourceMergeStrategy, ShutdownError,
+        SnapshotCell, SpanMetrics, TracerProviderInner, TracerSpec, DEFAULT_COMPONENT_NAME,
+        TRACER_CACHE_CAPACITY,
+    };
+    use crate::trace::{
+        Config, DroppedSpanCounts, ProcessorStats, RecordOnlySampler, Sampler, Span, SpanLimits,
+        SpanProcessor,
+    };
+    use crate::{InstrumentationLibrary, Resource};
+    use opentelemetry::trace::{
+        Span as _, SpanContext, SpanId, SpanKind, Status, TraceContextExt, TraceError, TraceFlags,
+        TraceId, TraceResult, TraceState, Tracer, TracerProvider,
+    };
+    use opentelemetry::{Context, Key, KeyValue, Value};
+    use std::borrow::Cow;
+    use std::env;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::{Arc, Barrier, Mutex};
+    use std::time::{Duration, Instant};
+
+    // fields below is wrapped with Arc so we can assert it
+    #[derive(Default, Debug)]
+    struct AssertInfo {
+        started_span: AtomicU32,
+        is_shutdown: AtomicBool,
+    }
+
+    #[derive(Default, Debug, Clone)]
+    struct SharedAssertInfo(Arc<AssertInfo>);
+
+    impl SharedAssertInfo {
+        fn started_span_count(&self, count: u32) -> bool {
+            self.0.started_span.load(Ordering::SeqCst) == count
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestSpanProcessor {
+        success: bool,
+        assert_info: SharedAssertInfo,
+    }
+
+    impl TestSpanProcessor {
+        fn new(success: bool) -> TestSpanProcessor {
+            TestSpanProcessor {
+                success,
+                assert_info: SharedAssertInfo::default(),
+            }
+        }
+
+        // get handle to assert info
+        fn assert_info(&self) -> SharedAssertInfo {
+            self.assert_info.clone()
+        }
+    }
+
+    impl SpanProcessor for TestSpanProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {
+            self.assert_info
+                .0
+                .started_span
+                .fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_end(&self, _span: SpanData) {
+            // ignore
+        }
+
+        fn force_flush(&self) -> TraceResult<()> {
+            if self.success {
+                Ok(())
+            } else {
+                Err(TraceError::from("cannot export"))
+            }
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            if self.assert_info.0.is_shutdown.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                let _ = self.assert_info.0.is_shutdown.compare_exchange(
+                    false,
+                    true,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+                self.force_flush()
+            }
+        }
+    }
+
+    #[test]
+    fn test_force_flush() {
+        let processors: Vec<Arc<dyn SpanProcessor>> = vec![
+            Arc::new(TestSpanProcessor::new(true)),
+            Arc::new(TestSpanProcessor::new(false)),
+        ];
+        let tracer_provider = super::TracerProvider::new(TracerProviderInner {
+            processors: SnapshotCell::new(processors),
+            config: Default::default(),
+            status_counter: Default::default(),
+            truncation_counter: Default::default(),
+            span_counter: Default::default(),
+            post_shutdown_spans: Default::default(),
+            resource_attributes_on_spans: Default::default(),
+            tracer_samplers: Vec::new(),
+            on_sampling_drop: None,
+            on_processor_drop: None,
+            on_span_end: None,
+            diagnostic_filter: None,
+            default_tracer_name: Cow::Borrowed(DEFAULT_COMPONENT_NAME),
+            tracer_cache: Default::default(),
+        });
+
+        let results = tracer_provider.force_flush();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[derive(Debug)]
+    struct NamedProcessor(&'static str, bool);
+
+    impl SpanProcessor for NamedProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            if self.1 {
+                Ok(())
+            } else {
+                Err(TraceError::from("cannot flush"))
+            }
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_force_flush_report() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(NamedProcessor("healthy", true))
+            .with_span_processor(TestSpanProcessor::new(true))
+            .with_span_processor(NamedProcessor("failing", false))
+            .build();
+
+        let report = tracer_provider.force_flush();
+        let names: Vec<_> = report.iter().map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "healthy",
+                std::any::type_name::<TestSpanProcessor>(),
+                "failing"
+            ]
+        );
+        assert!(!report.is_ok());
+        let errors: Vec<_> = report.errors().map(|(name, _)| name).collect();
+        assert_eq!(errors, vec!["failing"]);
+
+        #[allow(deprecated)]
+        let results = tracer_provider.force_flush_results();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_tracer_provider_default_resource() {
+        let assert_resource = |provider: &super::TracerProvider,
+                               resource_key: &'static str,
+                               expect: Option<&'static str>| {
+            assert_eq!(
+                provider
+                    .config()
+                    .resource
+                    .get(Key::from_static_str(resource_key))
+                    .map(|v| v.to_string()),
+                expect.map(|s| s.to_string())
+            );
+        };
+        let assert_telemetry_resource = |provider: &super::TracerProvider| {
+            assert_eq!(
+                provider
+                    .config()
+                    .resource
+                    .get(TELEMETRY_SDK_LANGUAGE.into()),
+                Some(Value::from("rust"))
+            );
+            assert_eq!(
+                provider.config().resource.get(TELEMETRY_SDK_NAME.into()),
+                Some(Value::from("opentelemetry"))
+            );
+            assert_eq!(
+                provider.config().resource.get(TELEMETRY_SDK_VERSION.into()),
+                Some(Value::from(env!("CARGO_PKG_VERSION")))
+            );
+        };
+
+        // If users didn't provide a resource and there isn't a env var set. Use default one.
+        temp_env::with_var_unset("OTEL_RESOURCE_ATTRIBUTES", || {
+            let default_config_provider = super::TracerProvider::builder().build();
+            assert_resource(
+                &default_config_provider,
+                SERVICE_NAME,
+                Some("unknown_service"),
+            );
+            assert_telemetry_resource(&default_config_provider);
+        });
+
+        // If user provided a resource, use that.
+        let custom_config_provider = super::TracerProvider::builder()
+            .with_config(Config {
+                resource: Cow::Owned(Resource::new(vec![KeyValue::new(
+                    SERVICE_NAME,
+                    "test_service",
+                )])),
+                ..Default::default()
+            })
+            .build();
+        assert_resource(&custom_config_provider, SERVICE_NAME, Some("test_service"));
+        assert_eq!(custom_config_provider.config().resource.len(), 1);
+
+        // If `OTEL_RESOURCE_ATTRIBUTES` is set, read them automatically
+        temp_env::with_var(
+            "OTEL_RESOURCE_ATTRIBUTES",
+            Some("key1=value1, k2, k3=value2"),
+            || {
+                let env_resource_provider = super::TracerProvider::builder().build();
+                assert_resource(
+                    &env_resource_provider,
+                    SERVICE_NAME,
+                    Some("unknown_service"),
+                );
+                assert_resource(&env_resource_provider, "key1", Some("value1"));
+                assert_resource(&env_resource_provider, "k3", Some("value2"));
+                assert_telemetry_resource(&env_resource_provider);
+                assert_eq!(env_resource_provider.config().resource.len(), 6);
+            },
+        );
+
+        // When `OTEL_RESOURCE_ATTRIBUTES` is set and also user provided config
+        temp_env::with_var(
+            "OTEL_RESOURCE_ATTRIBUTES",
+            Some("my-custom-key=env-val,k2=value2"),
+            || {
+                let user_provided_resource_config_provider = super::TracerProvider::builder()
+                    .with_config(Config {
+                        resource: Cow::Owned(Resource::default().merge(&mut Resource::new(vec![
+                            KeyValue::new("my-custom-key", "my-custom-value"),
+                            KeyValue::new("my-custom-key2", "my-custom-value2"),
+                        ]))),
+                        ..Default::default()
+                    })
+                    .build();
+                assert_resource(
+                    &user_provided_resource_config_provider,
+                    SERVICE_NAME,
+                    Some("unknown_service"),
+                );
+                assert_resource(
+                    &user_provided_resource_config_provider,
+                    "my-custom-key",
+                    Some("my-custom-value"),
+                );
+                assert_resource(
+                    &user_provided_resource_config_provider,
+                    "my-custom-key2",
+                    Some("my-custom-value2"),
+                );
+                assert_resource(
+                    &user_provided_resource_config_provider,
+                    "k2",
+                    Some("value2"),
+                );
+                assert_telemetry_resource(&user_provided_resource_config_provider);
+                assert_eq!(
+                    user_provided_resource_config_provider
+                        .config()
+                        .resource
+                        .len(),
+                    7
+                );
+            },
+        );
+
+        // If user provided a resource, it takes priority during collision.
+        let no_service_name = super::TracerProvider::builder()
+            .with_config(Config {
+                resource: Cow::Owned(Resource::empty()),
+                ..Default::default()
+            })
+            .build();
+
+        assert_eq!(no_service_name.config().resource.len(), 0)
+    }
+
+    #[test]
+    fn test_shutdown_noops() {
+        let processor = TestSpanProcessor::new(false);
+        let assert_handle = processor.assert_info();
+        let processors: Vec<Arc<dyn SpanProcessor>> = vec![Arc::new(processor)];
+        let tracer_provider = super::TracerProvider::new(TracerProviderInner {
+            processors: SnapshotCell::new(processors),
+            config: Default::default(),
+            status_counter: Default::default(),
+            truncation_counter: Default::default(),
+            span_counter: Default::default(),
+            post_shutdown_spans: Default::default(),
+            resource_attributes_on_spans: Default::default(),
+            tracer_samplers: Vec::new(),
+            on_sampling_drop: None,
+            on_processor_drop: None,
+            on_span_end: None,
+            diagnostic_filter: None,
+            default_tracer_name: Cow::Borrowed(DEFAULT_COMPONENT_NAME),
+            tracer_cache: Default::default(),
+        });
+
+        let test_tracer_1 = tracer_provider.tracer("test1");
+        let _ = test_tracer_1.start("test");
+
+        assert!(assert_handle.started_span_count(1));
+
+        let _ = test_tracer_1.start("test");
+
+        assert!(assert_handle.started_span_count(2));
+
+        let shutdown = |tracer_provider: super::TracerProvider| {
+            let _ = tracer_provider.shutdown(); // shutdown once
+        };
+
+        // assert tracer provider can be shutdown using on a cloned version
+        shutdown(tracer_provider.clone());
+
+        // after shutdown we should get noop tracer
+        let noop_tracer = tracer_provider.tracer("noop");
+        // noop tracer cannot start anything
+        let _ = noop_tracer.start("test");
+        assert!(assert_handle.started_span_count(2));
+        // noop tracer's tracer provider should be shutdown
+        assert!(noop_tracer.provider().is_shutdown.load(Ordering::SeqCst));
+
+        // existing tracer becomes noops after shutdown
+        let _ = test_tracer_1.start("test");
+        assert!(assert_handle.started_span_count(2));
+    }
+
+    #[test]
+    fn test_span_metrics() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(TestSpanProcessor::new(true))
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let mut first = tracer.start("first");
+        let second = tracer.start("second");
+        // dropped by the default parent based sampler, so never started
+        let unsampled_parent = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_u128(1),
+            SpanId::from_u64(1),
+            TraceFlags::default(),
+            true,
+            TraceState::default(),
+        ));
+        let dropped = tracer.start_with_context("dropped", &unsampled_parent);
+        assert!(!dropped.is_recording());
+        assert_eq!(
+            tracer_provider.span_metrics(),
+            SpanMetrics {
+                started_total: 2,
+                ended_total: 0,
+                active: 2,
+            }
+        );
+
+        first.end();
+        drop(second);
+        drop(dropped);
+        assert_eq!(
+            tracer_provider.span_metrics(),
+            SpanMetrics {
+                started_total: 2,
+                ended_total: 2,
+                active: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_status_counts() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(TestSpanProcessor::new(true))
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let statuses = [
+            Status::Unset,
+            Status::Ok,
+            Status::error("first"),
+            Status::error("second"),
+        ];
+        for status in statuses {
+            let mut span = tracer.start("test");
+            span.set_status(status);
+            span.end();
+        }
+
+        assert_eq!(
+            tracer_provider.status_counts(),
+            super::StatusCounts {
+                unset: 1,
+                ok: 1,
+                error: 2,
+            }
+        );
+
+        // spans ended after shutdown are not counted
+        let mut span = tracer.start("test");
+        let _ = tracer_provider.shutdown();
+        span.set_status(Status::Ok);
+        span.end();
+        assert_eq!(tracer_provider.status_counts().ok, 1);
+    }
+
+    #[derive(Debug)]
+    struct SlowShutdownProcessor(Duration);
+
+    impl SpanProcessor for SlowShutdownProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            std::thread::sleep(self.0);
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+            if self.0 > timeout {
+                std::thread::sleep(timeout);
+                return Err(TraceError::from("timed out"));
+            }
+            self.shutdown()
+        }
+    }
+
+    #[test]
+    fn test_await_shutdown_timeout() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(SlowShutdownProcessor(Duration::from_millis(500)))
+            .build();
+
+        let start = Instant::now();
+        let result = tracer_provider.await_shutdown(Duration::from_millis(50));
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_millis(400));
+        assert!(tracer_provider.is_shutdown());
+
+        // a second call returns the errors of the first one right away
+        let second = tracer_provider.await_shutdown(Duration::from_secs(5));
+        match (result, second) {
+            (Err(ShutdownError::Processors(first)), Err(ShutdownError::Processors(second))) => {
+                assert_eq!(first.len(), 1);
+                assert_eq!(first[0].0, second[0].0);
+                assert!(Arc::ptr_eq(&first[0].1, &second[0].1));
+            }
+            other => panic!("unexpected shutdown results {other:?}"),
+        }
+        assert!(start.elapsed() < Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_await_shutdown() {
+        let processor = TestSpanProcessor::new(true);
+        let assert_handle = processor.assert_info();
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(processor)
+            .build();
+
+        assert!(tracer_provider
+            .await_shutdown(Duration::from_secs(5))
+            .is_ok());
+        assert!(assert_handle.0.is_shutdown.load(Ordering::SeqCst));
+        assert!(matches!(
+            tracer_provider.shutdown(),
+            Err(ShutdownError::AlreadyShutdown)
+        ));
+        // unlike shutdown, the result of the first shutdown is returned
+        assert!(tracer_provider
+            .await_shutdown(Duration::from_secs(5))
+            .is_ok());
+    }
+
+    #[derive(Debug)]
+    struct StatsProcessor(ProcessorStats);
+
+    impl SpanProcessor for StatsProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn stats(&self) -> Option<ProcessorStats> {
+            Some(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_telemetry_snapshot() {
+        let first = ProcessorStats {
+            queue_depth: 3,
+            exported_spans: 10,
+            dropped_spans: DroppedSpanCounts {
+                queue_full: 2,
+                shutdown: 0,
+            },
+            exports: 2,
+            export_failures: 1,
+            last_export_latency: Some(Duration::from_millis(20)),
+            total_export_latency: Duration::from_millis(30),
+        };
+        let second = ProcessorStats {
+            queue_depth: 1,
+            exported_spans: 5,
+            dropped_spans: DroppedSpanCounts {
+                queue_full: 0,
+                shutdown: 4,
+            },
+            exports: 1,
+            export_failures: 0,
+            last_export_latency: Some(Duration::from_millis(5)),
+            total_export_latency: Duration::from_millis(5),
+        };
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(TestSpanProcessor::new(true))
+            .with_span_processor(StatsProcessor(first.clone()))
+            .with_span_processor(StatsProcessor(second.clone()))
+            .build();
+
+        let telemetry = tracer_provider.telemetry_snapshot();
+        assert_eq!(telemetry.processors, vec![None, Some(first), Some(second)]);
+        assert_eq!(
+            telemetry.total,
+            ProcessorStats {
+                queue_depth: 4,
+                exported_spans: 15,
+                dropped_spans: DroppedSpanCounts {
+                    queue_full: 2,
+                    shutdown: 4,
+                },
+                exports: 3,
+                export_failures: 1,
+                last_export_latency: Some(Duration::from_millis(20)),
+                total_export_latency: Duration::from_millis(35),
+            }
+        );
+    }
+
+    /// Hangs on its first shutdown, the one of the provider, but not on the
+    /// one of the provider's `Drop`.
+    #[derive(Debug, Default)]
+    struct HangingShutdownProcessor(AtomicBool);
+
+    impl SpanProcessor for HangingShutdownProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            if !self.0.swap(true, Ordering::SeqCst) {
+                loop {
+                    std::thread::park();
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_shutdown_error_lists_failed_processors() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(CountingShutdownProcessor(Arc::new(AtomicU32::new(0))))
+            .with_span_processor(TestSpanProcessor::new(false))
+            .build();
+
+        match tracer_provider.shutdown() {
+            Err(ShutdownError::Processors(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert!(errors[0].0.ends_with("TestSpanProcessor"));
+                assert_eq!(errors[0].1.to_string(), "cannot export");
+            }
+            other => panic!("unexpected shutdown result {other:?}"),
+        }
+
+        // still a `TraceError` for callers that don't need the details
+        let err: TraceError = tracer_provider.shutdown().unwrap_err().into();
+        assert!(err.to_string().contains("already shut down"));
+    }
+
+    #[test]
+    fn test_shutdown_with_timeout() {
+        let processor = TestSpanProcessor::new(true);
+        let assert_handle = processor.assert_info();
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(HangingShutdownProcessor::default())
+            .with_span_processor(processor)
+            .build();
+
+        let start = Instant::now();
+        let err = tracer_provider
+            .shutdown_with_timeout(Duration::from_millis(100))
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(err.to_string().contains("HangingShutdownProcessor"));
+        assert!(!err.to_string().contains("TestSpanProcessor"));
+        // the other processor is not blocked by the hanging one
+        assert!(assert_handle.0.is_shutdown.load(Ordering::SeqCst));
+
+        // the shutdown flag flipped even though the timeout fired
+        assert!(tracer_provider.is_shutdown());
+        assert!(tracer_provider
+            .shutdown_with_timeout(Duration::from_millis(100))
+            .unwrap_err()
+            .to_string()
+            .contains("already shut down"));
+    }
+
+    #[derive(Debug)]
+    struct SlowFlushProcessor {
+        delay: Duration,
+        success: bool,
+    }
+
+    impl SpanProcessor for SlowFlushProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            std::thread::sleep(self.delay);
+            if self.success {
+                Ok(())
+            } else {
+                Err(TraceError::from("cannot flush"))
+            }
+        }
+
+        fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+            if self.delay > timeout {
+                std::thread::sleep(timeout);
+                return Err(TraceError::from("timed out"));
+            }
+            self.force_flush()
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_force_flush_is_concurrent() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(SlowFlushProcessor {
+                delay: Duration::from_millis(300),
+                success: false,
+            })
+            .with_span_processor(SlowFlushProcessor {
+                delay: Duration::from_millis(200),
+                success: true,
+            })
+            .build();
+
+        let start = Instant::now();
+        let results = tracer_provider.force_flush();
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_millis(500), "took {elapsed:?}");
+        // results keep the order of the processors
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_err());
+        assert!(results[1].is_ok());
+    }
+
+    #[derive(Debug)]
+    struct NamedFlushProcessor {
+        name: &'static str,
+        flushes: Arc<AtomicU32>,
+    }
+
+    impl SpanProcessor for NamedFlushProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            self.flushes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    #[test]
+    fn test_force_flush_matching() {
+        let fast_flushes = Arc::new(AtomicU32::new(0));
+        let slow_flushes = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(NamedFlushProcessor {
+                name: "fast",
+                flushes: fast_flushes.clone(),
+            })
+            .with_span_processor(NamedFlushProcessor {
+                name: "slow",
+                flushes: slow_flushes.clone(),
+            })
+            .build();
+
+        let report = tracer_provider.force_flush_matching(|name| name == "fast");
+        assert_eq!(
+            report.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["fast"]
+        );
+        assert!(report.is_ok());
+        assert_eq!(fast_flushes.load(Ordering::SeqCst), 1);
+        assert_eq!(slow_flushes.load(Ordering::SeqCst), 0);
+
+        let report = tracer_provider.force_flush_matching(|name| name == "unknown");
+        assert!(report.is_empty());
+        assert_eq!(fast_flushes.load(Ordering::SeqCst), 1);
+        assert_eq!(slow_flushes.load(Ordering::SeqCst), 0);
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingShutdownProcessor(Arc<AtomicU32>);
+
+    impl SpanProcessor for CountingShutdownProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_force_flush_blocking() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(SlowFlushProcessor {
+                delay: Duration::ZERO,
+                success: true,
+            })
+            .with_span_processor(NamedProcessor("failing", false))
+            .with_span_processor(SlowFlushProcessor {
+                delay: Duration::from_secs(5),
+                success: true,
+            })
+            .with_span_processor(SlowFlushProcessor {
+                delay: Duration::ZERO,
+                success: true,
+            })
+            .build();
+
+        let start = Instant::now();
+        let results = tracer_provider.force_flush_blocking(Duration::from_millis(100));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_err());
+        // no time left for the last one
+        assert!(results[3]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("did not flush within"));
+    }
+
+    #[cfg(feature = "testing")]
+    fn assert_force_flush_blocking_refused() {
+        let flushes = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(NamedFlushProcessor {
+                name: "processor",
+                flushes: flushes.clone(),
+            })
+            .build();
+
+        let results = tracer_provider.force_flush_blocking(Duration::from_secs(1));
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+        assert_eq!(flushes.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_force_flush_blocking_in_current_thread_runtime() {
+        assert_force_flush_blocking_refused();
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_force_flush_blocking_in_multi_thread_runtime() {
+        assert_force_flush_blocking_refused();
+    }
+
+    #[test]
+    fn test_drop_shuts_down_processors() {
+        let shutdowns = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(CountingShutdownProcessor(shutdowns.clone()))
+            .build();
+        let clone = tracer_provider.clone();
+
+        drop(tracer_provider);
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 0);
+
+        // no runtime is driving anything here, the last drop shuts down inline
+        drop(clone);
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Debug)]
+    struct RecordingProcessor(Arc<Mutex<Vec<String>>>);
+
+    impl SpanProcessor for RecordingProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span.name.into_owned());
+        }
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reload_processors() {
+        let old_spans = Arc::new(Mutex::new(Vec::new()));
+        let new_spans = Arc::new(Mutex::new(Vec::new()));
+        let old_shutdowns = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(RecordingProcessor(old_spans.clone()))
+            .with_span_processor(CountingShutdownProcessor(old_shutdowns.clone()))
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let _ = tracer.start("before");
+        let spanning = tracer.start("spanning");
+        tracer_provider
+            .reload_processors(vec![Box::new(RecordingProcessor(new_spans.clone()))])
+            .unwrap();
+        let _ = tracer.start("after");
+        // ends on the processors it started on
+        drop(spanning);
+
+        assert_eq!(
+            *old_spans.lock().unwrap(),
+            vec!["before".to_string(), "spanning".to_string()]
+        );
+        assert_eq!(*new_spans.lock().unwrap(), vec!["after".to_string()]);
+
+        // old processors are shut down in the background
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while old_shutdowns.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(old_shutdowns.load(Ordering::SeqCst), 1);
+
+        assert!(tracer_provider.shutdown().is_ok());
+        assert!(tracer_provider.reload_processors(Vec::new()).is_err());
+    }
+
+    #[derive(Debug)]
+    struct EndCountingProcessor(&'static str, Arc<AtomicU32>);
+
+    impl SpanProcessor for EndCountingProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {
+            self.1.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_replace_processor() {
+        let wait_for = |ended: &AtomicU32| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while ended.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+        let old_ended = Arc::new(AtomicU32::new(0));
+        let new_ended = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(NamedProcessor("other", true))
+            .with_span_processor(EndCountingProcessor("exporter", old_ended.clone()))
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let producer = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let mut ended = 0;
+                while !stop.load(Ordering::SeqCst) {
+                    tracer.start("span").end();
+                    ended += 1;
+                }
+                ended
+            })
+        };
+        wait_for(&old_ended);
+        tracer_provider
+            .replace_processor(
+                "exporter",
+                Box::new(EndCountingProcessor("exporter", new_ended.clone())),
+            )
+            .unwrap();
+        wait_for(&new_ended);
+        stop.store(true, Ordering::SeqCst);
+        let ended = producer.join().unwrap();
+
+        // every span reached either processor
+        assert_eq!(
+            old_ended.load(Ordering::SeqCst) + new_ended.load(Ordering::SeqCst),
+            ended
+        );
+        assert_eq!(tracer_provider.span_processors()[0].name(), "other");
+
+        assert!(tracer_provider
+            .replace_processor("missing", Box::new(NamedProcessor("new", true)))
+            .is_err());
+        assert!(tracer_provider
+            .replace_processor(2usize, Box::new(NamedProcessor("new", true)))
+            .is_err());
+        assert!(tracer_provider
+            .replace_processor(0usize, Box::new(NamedProcessor("new", true)))
+            .is_ok());
+        assert_eq!(tracer_provider.span_processors()[0].name(), "new");
+
+        assert!(tracer_provider.shutdown().is_ok());
+        assert!(tracer_provider
+            .replace_processor(0usize, Box::new(NamedProcessor("other", true)))
+            .is_err());
+    }
+
+    #[cfg(all(unix, feature = "shutdown_signals", feature = "testing"))]
+    #[tokio::test]
+    async fn test_install_shutdown_on_signals() {
+        let shutdowns = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(CountingShutdownProcessor(shutdowns.clone()))
+            .build();
+        let signals = [super::Signal::Terminate];
+        tracer_provider
+            .install_shutdown_on_signals(&signals)
+            .unwrap();
+        // no second listener, which would shut down again
+        tracer_provider
+            .install_shutdown_on_signals(&signals)
+            .unwrap();
+
+        let killed = std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(killed.success());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while shutdowns.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            tracer_provider.shutdown(),
+            Err(ShutdownError::AlreadyShutdown)
+        ));
+    }
+
+    #[derive(Debug)]
+    struct FailingProcessor;
+
+    impl SpanProcessor for FailingProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Err(TraceError::from("diagnostic filter test: flush failed"))
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Err(TraceError::from("diagnostic filter test: shutdown failed"))
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_filter() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let handler_reported = reported.clone();
+        opentelemetry::global::set_error_handler(move |err| {
+            let message = err.to_string();
+            // other tests report errors concurrently
+            if message.contains("diagnostic filter test") {
+                handler_reported.lock().unwrap().push(message);
+            }
+        })
+        .unwrap();
+
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(FailingProcessor)
+            .with_diagnostic_filter(|kind| kind != DiagnosticKind::ForceFlush)
+            .build();
+        // the old processor is flushed and shut down in the background
+        tracer_provider
+            .reload_processors(vec![Box::new(NamedProcessor("new", true))])
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while reported.lock().unwrap().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 1);
+        assert!(reported[0].contains("shutdown failed"));
+    }
+
+    #[test]
+    fn test_post_shutdown_span_silent() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(TestSpanProcessor::new(true))
+            .build();
+        let tracer = tracer_provider.tracer("test");
+        assert!(tracer_provider.shutdown().is_ok());
+
+        assert!(!tracer.start("after shutdown").is_recording());
+        assert_eq!(tracer_provider.post_shutdown_spans(), 0);
+    }
+
+    #[test]
+    fn test_post_shutdown_span_warn() {
+        let warnings = Arc::new(AtomicU32::new(0));
+        let filter_warnings = warnings.clone();
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(TestSpanProcessor::new(true))
+            .on_post_shutdown_span(PostShutdownSpanPolicy::Warn)
+            // count the warnings without reaching the global error handler
+            .with_diagnostic_filter(move |kind| {
+                if kind == DiagnosticKind::PostShutdownSpan {
+                    filter_warnings.fetch_add(1, Ordering::SeqCst);
+                }
+                false
+            })
+            .build();
+        let tracer = tracer_provider.tracer("test");
+        assert!(tracer_provider.shutdown().is_ok());
+
+        for _ in 0..3 {
+            assert!(!tracer.start("after shutdown").is_recording());
+        }
+        // rate limited
+        assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        assert_eq!(tracer_provider.post_shutdown_spans(), 0);
+    }
+
+    #[test]
+    fn test_post_shutdown_span_count() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(TestSpanProcessor::new(true))
+            .on_post_shutdown_span(PostShutdownSpanPolicy::Count)
+            .build();
+        let tracer = tracer_provider.tracer("test");
+        assert!(tracer_provider.shutdown().is_ok());
+
+        assert!(!tracer.start("after shutdown").is_recording());
+        // tracers requested after shutdown count too
+        let late_tracer = tracer_provider.tracer("late");
+        assert!(!late_tracer.start("after shutdown").is_recording());
+        assert_eq!(tracer_provider.post_shutdown_spans(), 2);
+        assert_eq!(tracer_provider.span_metrics().started_total, 0);
+    }
+
+    #[test]
+    fn test_shutdown_guard() {
+        let shutdowns = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(CountingShutdownProcessor(shutdowns.clone()))
+            .build();
+
+        let guard = tracer_provider.shutdown_guard();
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 0);
+        drop(guard);
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+        assert!(tracer_provider.is_shutdown());
+
+        // an explicit shutdown afterwards hits the already shut down path
+        assert!(tracer_provider.shutdown().is_err());
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_shutdown_guard_after_shutdown() {
+        let shutdowns = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            .with_span_processor(CountingShutdownProcessor(shutdowns.clone()))
+            .build();
+
+        let guard = tracer_provider.shutdown_guard();
+        assert!(tracer_provider.shutdown().is_ok());
+        drop(guard);
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_concurrent_shutdown() {
+        let shutdowns = Arc::new(AtomicU32::new(0));
+        let tracer_provider = super::TracerProvider::builder()
+            // keeps the shutdown in progress while the other callers arrive
+            .with_span_processor(SlowShutdownProcessor(Duration::from_millis(200)))
+            .with_span_processor(CountingShutdownProcessor(shutdowns.clone()))
+            .build();
+
+        let callers = 8;
+        let barrier = Arc::new(Barrier::new(callers));
+        let handles: Vec<_> = (0..callers)
+            .map(|_| {
+                let tracer_provider = tracer_provider.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    tracer_provider.shutdown()
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap().is_ok());
+        }
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+
+        // once the shutdown returned, later calls get an error
+        assert!(tracer_provider
+            .shutdown()
+            .unwrap_err()
+            .to_string()
+            .contains("already shut down"));
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_default_tracer_name() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_default_tracer_name("my-service")
+            .build();
+        let tracer = tracer_provider.versioned_tracer("", Some("1.0"), None::<&str>, None);
+        assert_eq!(tracer.instrumentation_library().name, "my-service");
+        assert_eq!(
+            tracer.instrumentation_library().version.as_deref(),
+            Some("1.0")
+        );
+
+        let tracer_provider = super::TracerProvider::builder().build();
+        let tracer = tracer_provider.versioned_tracer("", None::<&str>, None::<&str>, None);
+        assert_eq!(
+            tracer.instrumentation_library().name,
+            super::DEFAULT_COMPONENT_NAME
+        );
+    }
+
+    #[test]
+    fn test_tracer_cache() {
+        let tracer_provider = super::TracerProvider::builder().build();
+
+        let first = tracer_provider.tracer("test");
+        let second = tracer_provider.tracer("test");
+        assert!(std::ptr::eq(
+            first.instrumentation_library(),
+            second.instrumentation_library()
+        ));
+
+        let library = Arc::new(
+            InstrumentationLibrary::builder("test")
+                .with_attributes(vec![KeyValue::new("k", "v")])
+                .build(),
+        );
+        let with_attributes = tracer_provider.library_tracer(library.clone());
+        assert!(!std::ptr::eq(
+            first.instrumentation_library(),
+            with_attributes.instrumentation_library()
+        ));
+        let again = tracer_provider.library_tracer(Arc::new(library.as_ref().clone()));
+        assert!(std::ptr::eq(
+            with_attributes.instrumentation_library(),
+            again.instrumentation_library()
+        ));
+
+        // default span attributes are part of the key
+        let cached = || {
+            tracer_provider
+                .inner
+                .tracer_cache
+                .tracers
+                .read()
+                .unwrap()
+                .len
+        };
+        let before = cached();
+        let with_defaults = first.with_default_span_attributes(vec![KeyValue::new("k", "v")]);
+        assert!(std::ptr::eq(
+            first.instrumentation_library(),
+            with_defaults.instrumentation_library()
+        ));
+        assert_eq!(cached(), before + 1);
+        let _again = second.with_default_span_attributes(vec![KeyValue::new("k", "v")]);
+        assert_eq!(cached(), before + 1);
+
+        // cached tracers are noops once the provider is shut down
+        assert!(tracer_provider.shutdown().is_ok());
+        let noop = tracer_provider.tracer("test");
+        assert!(noop.provider().is_shutdown());
+        assert!(!noop.start("test").is_recording());
+    }
+
+    #[test]
+    fn test_tracer_cache_is_bounded() {
+        let tracer_provider = super::TracerProvider::builder().build();
+        for index in 0..TRACER_CACHE_CAPACITY {
+            tracer_provider.tracer(format!("tracer-{index}"));
+        }
+
+        // past the capacity, tracers are still created but not cached
+        let first = tracer_provider.tracer("uncached");
+        let second = tracer_provider.tracer("uncached");
+        assert!(!std::ptr::eq(
+            first.instrumentation_library(),
+            second.instrumentation_library()
+        ));
+        assert!(first.start("test").is_recording());
+        let cached = tracer_provider
+            .inner
+            .tracer_cache
+            .tracers
+            .read()
+            .unwrap()
+            .len;
+        assert_eq!(cached, TRACER_CACHE_CAPACITY);
+    }
+
+    #[test]
+    fn test_resource_merge_strategy() {
+        let build = |strategy| {
+            super::TracerProvider::builder()
+                .with_resource(Resource::new(vec![
+                    KeyValue::new("deployment.environment", "staging"),
+                    KeyValue::new("service.version", "1.0.0"),
+                ]))
+                .with_resource_merge_strategy(strategy)
+                .build_checked()
+        };
+        let resource_value = |provider: &super::TracerProvider, key| {
+            provider.config().resource.get(Key::from_static_str(key))
+        };
+
+        temp_env::with_var(
+            "OTEL_RESOURCE_ATTRIBUTES",
+            Some("deployment.environment=production,k8s.pod.name=pod"),
+            || {
+                let user_wins = build(ResourceMergeStrategy::UserWins).unwrap();
+                assert_eq!(
+                    resource_value(&user_wins, "deployment.environment"),
+                    Some(Value::from("staging"))
+                );
+                assert_eq!(
+                    resource_value(&user_wins, "k8s.pod.name"),
+                    Some(Value::from("pod"))
+                );
+
+                let env_wins = build(ResourceMergeStrategy::EnvWins).unwrap();
+                assert_eq!(
+                    resource_value(&env_wins, "deployment.environment"),
+                    Some(Value::from("production"))
+                );
+                assert_eq!(
+                    resource_value(&env_wins, "service.version"),
+                    Some(Value::from("1.0.0"))
+                );
+
+                let err = build(ResourceMergeStrategy::ErrorOnCollision).unwrap_err();
+                assert_eq!(
+                    err,
+                    BuilderError::ResourceAttributeCollision {
+                        key: Key::from_static_str("deployment.environment"),
+                        env_value: Value::from("production"),
+                        configured_value: Value::from("staging"),
+                    }
+                );
+                assert_eq!(
+                    err.to_string(),
+                    "resource attribute deployment.environment is set to production by OTEL_RESOURCE_ATTRIBUTES but to staging in code"
+                );
+            },
+        );
+
+        // attributes set with the same value, or by one side only, don't collide
+        temp_env::with_var(
+            "OTEL_RESOURCE_ATTRIBUTES",
+            Some("deployment.environment=staging,k8s.pod.name=pod"),
+            || assert!(build(ResourceMergeStrategy::ErrorOnCollision).is_ok()),
+        );
+    }
+
+    #[test]
+    fn test_tracers() {
+        let tracer_provider = super::TracerProvider::builder().build();
+
+        let tracers = tracer_provider.tracers(vec![
+            TracerSpec::new("http").with_version("1.0.0"),
+            TracerSpec::new("db")
+                .with_schema_url("https://opentelemetry.io/schemas/1.17.0")
+                .with_attributes([KeyValue::new("k", "v")]),
+            TracerSpec::new(""),
+        ]);
+        let libraries: Vec<_> = tracers
+            .iter()
+            .map(|tracer| tracer.instrumentation_library().clone())
+            .collect();
+        assert_eq!(
+            libraries,
+            vec![
+                InstrumentationLibrary::builder("http")
+                    .with_version("1.0.0")
+                    .build(),
+                InstrumentationLibrary::builder("db")
+                    .with_schema_url("https://opentelemetry.io/schemas/1.17.0")
+                    .with_attributes([KeyValue::new("k", "v")])
+                    .build(),
+                InstrumentationLibrary::builder(DEFAULT_COMPONENT_NAME).build(),
+            ]
+        );
+        assert!(tracers
+            .iter()
+            .all(|tracer| tracer.start("test").is_recording()));
+        // the same libraries as the other ways of getting a tracer
+        assert!(std::ptr::eq(
+            tracers[0].instrumentation_library(),
+            tracer_provider
+                .versioned_tracer("http", Some("1.0.0"), None::<&'static str>, None)
+                .instrumentation_library()
+        ));
+
+        assert!(tracer_provider.shutdown().is_ok());
+        let tracers = tracer_provider.tracers(vec![TracerSpec::new("http"), TracerSpec::new("db")]);
+        assert_eq!(tracers.len(), 2);
+        assert!(tracers
+            .iter()
+            .all(|tracer| !tracer.start("test").is_recording()));
+    }
+
+    #[test]
+    fn test_noop() {
+        let tracer_provider = super::TracerProvider::noop();
+        let tracer = tracer_provider.tracer("test");
+
+        let mut span = tracer.start("test");
+        assert!(!span.is_recording());
+        assert!(!span.span_context().is_sampled());
+        span.end();
+
+        assert!(tracer_provider.span_processors().is_empty());
+        assert!(tracer_provider.force_flush().is_ok());
+        assert!(tracer_provider.shutdown().is_ok());
+    }
+
+    #[test]
+    fn test_on_sampling_drop() {
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let on_sampling_drop = dropped.clone();
+        let tracer_provider = super::TracerProvider::builder()
+            .with_config(Config::default().with_sampler(Sampler::AlwaysOff))
+            .with_on_sampling_drop(move |name, attributes| {
+                on_sampling_drop
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), attributes.to_vec()))
+            })
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let _ = tracer.start("first");
+        let _ = tracer
+            .span_builder("second")
+            .with_attributes([KeyValue::new("key", "value")])
+            .start(&tracer);
+
+        assert_eq!(
+            *dropped.lock().unwrap(),
+            vec![
+                ("first".to_string(), vec![]),
+                ("second".to_string(), vec![KeyValue::new("key", "value")]),
+            ]
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_span_end_callback() {
+        use crate::testing::trace::InMemorySpanExporterBuilder;
+
+        let ended = Arc::new(Mutex::new(Vec::new()));
+        let on_span_end = ended.clone();
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = super::TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(Config::default().with_sampler(RecordOnlySampler))
+            .with_span_end_callback(move |span| {
+                assert!(span.end_time >= span.start_time);
+                on_span_end
+                    .lock()
+                    .unwrap()
+                    .push((span.name.to_string(), span.span_context.span_id()))
+            })
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let mut first = tracer.start("first");
+        let first_id = first.span_context().span_id();
+        first.end();
+        let second = tracer.start("second");
+        let second_id = second.span_context().span_id();
+        drop(second);
+
+        // the spans are recorded but not sampled, so never exported
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+        assert_eq!(
+            *ended.lock().unwrap(),
+            vec![
+                ("first".to_string(), first_id),
+                ("second".to_string(), second_id),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_checked() {
+        let nan_ratio = super::TracerProvider::builder()
+            .with_config(
+                Config::default().with_sampler(Sampler::ParentBased(Box::new(
+                    Sampler::TraceIdRatioBased(f64::NAN),
+                ))),
+            )
+            .build_checked();
+        assert_eq!(nan_ratio.unwrap_err(), BuilderError::NanSamplerRatio);
+
+        let wrapped_nan_ratio = super::TracerProvider::builder()
+            .with_config(
+                Config::default().with_sampler(
+                    crate::trace::CompositeSampler::any()
+                        .with_sampler(Sampler::AlwaysOn)
+                        .with_sampler(Sampler::TraceIdRatioBased(f64::NAN)),
+                ),
+            )
+            .build_checked();
+        assert_eq!(
+            wrapped_nan_ratio.unwrap_err(),
+            BuilderError::NanSamplerRatio
+        );
+
+        let zero_detector_timeout = super::TracerProvider::builder()
+            .with_detectors(vec![FixedDetector::boxed(Vec::new(), Duration::ZERO)])
+            .with_detector_timeout(Duration::ZERO)
+            .build_checked();
+        assert_eq!(
+            zero_detector_timeout.unwrap_err(),
+            BuilderError::ZeroDetectorTimeout
+        );
+
+        let duplicate_tracer_sampler = super::TracerProvider::builder()
+            .with_versioned_tracer_sampler("db", "1.0", Sampler::AlwaysOn)
+            .with_tracer_sampler("db", Sampler::AlwaysOn)
+            .with_versioned_tracer_sampler("db", "1.0", Sampler::AlwaysOff)
+            .build_checked();
+        assert_eq!(
+            duplicate_tracer_sampler.unwrap_err(),
+            BuilderError::DuplicateTracerSampler {
+                name: "db".into(),
+                version: Some("1.0".into()),
+            }
+        );
+
+        assert!(super::TracerProvider::builder()
+            .with_config(Config::default().with_sampler(Sampler::TraceIdRatioBased(0.5)))
+            .build_checked()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_build_falls_back_to_defaults() {
+        let provider = super::TracerProvider::builder()
+            .with_config(Config::default().with_sampler(Sampler::TraceIdRatioBased(f64::NAN)))
+            .build();
+
+        // the NaN ratio sampler was replaced by the default ParentBased(AlwaysOn)
+        let span = provider.tracer("test").start("span");
+        assert!(span.span_context().is_sampled());
+    }
+
+    #[test]
+    fn test_tracer_sampler() {
+        let tracer_provider = super::TracerProvider::builder()
+            .with_config(Config::default().with_sampler(Sampler::AlwaysOff))
+            .with_tracer_sampler("db", Sampler::ParentBased(Box::new(Sampler::AlwaysOn)))
+            .with_versioned_tracer_sampler("cache", "2.0", Sampler::AlwaysOn)
+            .build();
+        let is_sampled = |span: &Span| span.span_context().is_sampled();
+
+        let http = tracer_provider.tracer("http");
+        let db = tracer_provider.tracer("db");
+        assert!(!is_sampled(&http.start("request")));
+        assert!(is_sampled(&db.start("query")));
+
+        // the override still follows the decision of the parent
+        let cx = Context::current_with_span(http.start("request"));
+        assert!(!is_sampled(&db.start_with_context("query", &cx)));
+
+        let cache = |version: &'static str| {
+            tracer_provider
+                .tracer_builder("cache")
+                .with_version(version)
+                .build()
+        };
+        assert!(is_sampled(&cache("2.0").start("get")));
+        assert!(!is_sampled(&cache("1.0").start("get")));
+    }
+
+    #[derive(Debug)]
+    struct ResourceProcessor(Arc<Mutex<Option<Resource>>>);
+
+    impl SpanProcessor for ResourceProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn set_resource(&mut self, resource: &Resource) {
+            *self.0.lock().unwrap() = Some(resource.clone());
+        }
+    }
+
+    #[test]
+    fn test_with_span_processor_resource() {
+        let default_resource = Arc::new(Mutex::new(None));
+        let tenant_resource = Arc::new(Mutex::new(None));
+        let provider = super::TracerProvider::builder()
+            .with_resource(Resource::new(vec![
+                KeyValue::new(SERVICE_NAME, "provider"),
+                KeyValue::new("region", "eu"),
+            ]))
+            .with_span_processor(ResourceProcessor(default_resource.clone()))
+            .with_span_processor_resource(
+                ResourceProcessor(tenant_resource.clone()),
+                Resource::new(vec![KeyValue::new(SERVICE_NAME, "tenant")]),
+            )
+            .build();
+
+        let service_name = |resource: &Mutex<Option<Resource>>| {
+            resource
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|resource| resource.get(Key::from_static_str(SERVICE_NAME)))
+        };
+        assert_eq!(service_name(&default_resource), Some("provider".into()));
+        assert_eq!(service_name(&tenant_resource), Some("tenant".into()));
+        // the rest of the provider resource is kept
+        assert_eq!(
+            tenant_resource
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|resource| resource.get(Key::from_static_str("region"))),
+            Some("eu".into())
+        );
+        // the provider resource itself is unaffected
+        assert_eq!(
+            provider
+                .config()
+                .resource
+                .get(Key::from_static_str(SERVICE_NAME)),
+            Some("provider".into())
+        );
+    }
+
+    #[derive(Debug)]
+    struct DispatchOrderProcessor(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl SpanProcessor for DispatchOrderProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {
+            self.1.lock().unwrap().push(format!("start {}", self.0));
+        }
 
         fn on_end(&self, _span: SpanData) {
-            // ignore
+            self.1.lock().unwrap().push(format!("end {}", self.0));
         }
 
         fn force_flush(&self) -> TraceResult<()> {
-            if self.success {
-                Ok(())
-            } else {
-                Err(TraceError::from("cannot export"))
-            }
+            Ok(())
         }
 
         fn shutdown(&self) -> TraceResult<()> {
-            if self.assert_info.0.is_shutdown.load(Ordering::SeqCst) {
-                Ok(())
-            } else {
-                let _ = self.assert_info.0.is_shutdown.compare_exchange(
-                    false,
-                    true,
-                    Ordering::SeqCst,
-                    Ordering::SeqCst,
-                );
-                self.force_flush()
-            }
+            Ok(())
         }
     }
 
     #[test]
-    fn test_force_flush() {
-        let tracer_provider = super::TracerProvider::new(TracerProviderInner {
-            processors: vec![
-                Box::from(TestSpanProcessor::new(true)),
-                Box::from(TestSpanProcessor::new(false)),
-            ],
-            config: Default::default(),
-        });
+    fn test_with_span_processor_ordered() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let processor = |name| DispatchOrderProcessor(name, calls.clone());
+        let resource = Arc::new(Mutex::new(None));
+        let provider = super::TracerProvider::builder()
+            .with_span_processor(processor("export"))
+            .with_span_processor_ordered(processor("late"), -5)
+            .with_span_processor_ordered(processor("enrich"), 10)
+            .with_span_processor_resource(
+                ResourceProcessor(resource.clone()),
+                Resource::new(vec![KeyValue::new(SERVICE_NAME, "tenant")]),
+            )
+            .with_span_processor_ordered(processor("filter"), 10)
+            .with_span_processor_ordered(processor("default"), 0)
+            .build();
 
-        let results = tracer_provider.force_flush();
-        assert_eq!(results.len(), 2);
+        let _ = provider.tracer("test").start("span");
+
+        let order = ["enrich", "filter", "export", "default", "late"];
+        let expected: Vec<_> = order
+            .iter()
+            .map(|name| format!("start {name}"))
+            .chain(order.iter().map(|name| format!("end {name}")))
+            .collect();
+        assert_eq!(*calls.lock().unwrap(), expected);
+        // resources are given before processors are reordered
+        assert_eq!(
+            resource
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|resource| resource.get(Key::from_static_str(SERVICE_NAME))),
+            Some("tenant".into())
+        );
     }
 
     #[test]
-    fn test_tracer_provider_default_resource() {
-        let assert_resource = |provider: &super::TracerProvider,
-                               resource_key: &'static str,
-                               expect: Option<&'static str>| {
+    fn test_effective_config() {
+        let provider = super::TracerProvider::builder()
+            .with_config(
+                Config::default()
+                    .with_sampler(Sampler::TraceIdRatioBased(0.25))
+                    .with_max_events_per_span(16)
+                    .with_max_links_per_span(4)
+                    .with_resource(Resource::from_schema_url(
+                        vec![KeyValue::new("b", "2"), KeyValue::new("a", "1")],
+                        "http://schema/url",
+                    )),
+            )
+            .build();
+
+        let config = provider.effective_config();
+        assert_eq!(config.sampler, "TraceIdRatioBased(0.25)");
+        assert_eq!(config.span_limits.max_events_per_span, 16);
+        assert_eq!(config.span_limits.max_links_per_span, 4);
+        assert_eq!(
+            config.span_limits.max_attributes_per_span,
+            SpanLimits::default().max_attributes_per_span
+        );
+        assert_eq!(
+            config.resource,
+            vec![KeyValue::new("a", "1"), KeyValue::new("b", "2")]
+        );
+        assert_eq!(
+            config.resource_schema_url.as_deref(),
+            Some("http://schema/url")
+        );
+        assert_eq!(provider.effective_config(), config);
+    }
+
+    #[test]
+    fn test_config_template() {
+        let provider = super::TracerProvider::builder()
+            .with_config(
+                Config::default()
+                    .with_sampler(Sampler::TraceIdRatioBased(0.5))
+                    .with_max_events_per_span(16),
+            )
+            .build();
+
+        let copy = super::TracerProvider::builder()
+            .with_config(provider.config_template())
+            .build();
+
+        assert_eq!(copy.effective_config(), provider.effective_config());
+        for trace_id in (0..100u128).map(|i| TraceId::from_u128(i << 57)) {
+            let decision = |provider: &super::TracerProvider| {
+                provider
+                    .config()
+                    .sampler
+                    .should_sample(None, trace_id, "test", &SpanKind::Internal, &[], &[])
+                    .decision
+            };
+            assert_eq!(decision(&copy), decision(&provider));
+        }
+    }
+
+    #[test]
+    fn test_allow_no_processors() {
+        assert!(super::TracerProvider::builder().lacks_processors());
+        assert!(!super::TracerProvider::builder()
+            .with_span_processor(NamedProcessor("processor", true))
+            .lacks_processors());
+
+        let builder = super::TracerProvider::builder().allow_no_processors();
+        assert!(!builder.lacks_processors());
+        let provider = builder.build();
+        assert!(provider.force_flush().is_empty());
+        assert!(provider.shutdown().is_ok());
+    }
+
+    #[test]
+    fn test_without_resource_interning() {
+        let providers: Vec<_> = ["first", "second", "third"]
+            .into_iter()
+            .map(|name| {
+                super::TracerProvider::builder()
+                    .with_resource(Resource::new(vec![KeyValue::new(SERVICE_NAME, name)]))
+                    .without_resource_interning()
+                    .build()
+            })
+            .collect();
+
+        for (provider, name) in providers.iter().zip(["first", "second", "third"]) {
+            assert!(matches!(provider.config().resource, Cow::Owned(_)));
             assert_eq!(
                 provider
                     .config()
                     .resource
-                    .get(Key::from_static_str(resource_key))
-                    .map(|v| v.to_string()),
-                expect.map(|s| s.to_string())
+                    .get(Key::from_static_str(SERVICE_NAME)),
+                Some(Value::from(name))
             );
+        }
+    }
+
+    #[test]
+    fn test_with_resource() {
+        let service_name = |provider: &super::TracerProvider| {
+            provider
+                .config()
+                .resource
+                .get(Key::from_static_str(SERVICE_NAME))
+                .map(|v| v.to_string())
         };
-        let assert_telemetry_resource = |provider: &super::TracerProvider| {
+
+        // explicit only, detected sdk attributes are kept
+        temp_env::with_var_unset("OTEL_RESOURCE_ATTRIBUTES", || {
+            let provider = super::TracerProvider::builder()
+                .with_resource(Resource::new(vec![KeyValue::new(SERVICE_NAME, "explicit")]))
+                .build();
+            assert_eq!(service_name(&provider), Some("explicit".to_string()));
             assert_eq!(
                 provider
                     .config()
@@ -419,149 +4771,315 @@ mod tests {
                     .get(TELEMETRY_SDK_LANGUAGE.into()),
                 Some(Value::from("rust"))
             );
-            assert_eq!(
-                provider.config().resource.get(TELEMETRY_SDK_NAME.into()),
-                Some(Value::from("opentelemetry"))
-            );
-            assert_eq!(
-                provider.config().resource.get(TELEMETRY_SDK_VERSION.into()),
-                Some(Value::from(env!("CARGO_PKG_VERSION")))
-            );
-        };
+        });
 
-        // If users didn't provide a resource and there isn't a env var set. Use default one.
-        temp_env::with_var_unset("OTEL_RESOURCE_ATTRIBUTES", || {
-            let default_config_provider = super::TracerProvider::builder().build();
-            assert_resource(
-                &default_config_provider,
-                SERVICE_NAME,
-                Some("unknown_service"),
+        // env only
+        temp_env::with_var("OTEL_RESOURCE_ATTRIBUTES", Some("env-key=env-val"), || {
+            let provider = super::TracerProvider::builder()
+                .with_resource(Resource::empty())
+                .build();
+            assert_eq!(
+                provider.config().resource.get(Key::from("env-key")),
+                Some(Value::from("env-val"))
             );
-            assert_telemetry_resource(&default_config_provider);
+            assert_eq!(service_name(&provider), Some("unknown_service".to_string()));
         });
 
-        // If user provided a resource, use that.
-        let custom_config_provider = super::TracerProvider::builder()
-            .with_config(Config {
-                resource: Cow::Owned(Resource::new(vec![KeyValue::new(
-                    SERVICE_NAME,
-                    "test_service",
-                )])),
-                ..Default::default()
-            })
-            .build();
-        assert_resource(&custom_config_provider, SERVICE_NAME, Some("test_service"));
-        assert_eq!(custom_config_provider.config().resource.len(), 1);
-
-        // If `OTEL_RESOURCE_ATTRIBUTES` is set, read them automatically
-        temp_env::with_var(
-            "OTEL_RESOURCE_ATTRIBUTES",
-            Some("key1=value1, k2, k3=value2"),
-            || {
-                let env_resource_provider = super::TracerProvider::builder().build();
-                assert_resource(
-                    &env_resource_provider,
-                    SERVICE_NAME,
-                    Some("unknown_service"),
-                );
-                assert_resource(&env_resource_provider, "key1", Some("value1"));
-                assert_resource(&env_resource_provider, "k3", Some("value2"));
-                assert_telemetry_resource(&env_resource_provider);
-                assert_eq!(env_resource_provider.config().resource.len(), 6);
-            },
-        );
-
-        // When `OTEL_RESOURCE_ATTRIBUTES` is set and also user provided config
+        // overlapping keys, explicit wins
         temp_env::with_var(
             "OTEL_RESOURCE_ATTRIBUTES",
-            Some("my-custom-key=env-val,k2=value2"),
+            Some("shared=env-val,env-key=env-val"),
             || {
-                let user_provided_resource_config_provider = super::TracerProvider::builder()
-                    .with_config(Config {
-                        resource: Cow::Owned(Resource::default().merge(&mut Resource::new(vec![
-                            KeyValue::new("my-custom-key", "my-custom-value"),
-                            KeyValue::new("my-custom-key2", "my-custom-value2"),
-                        ]))),
-                        ..Default::default()
-                    })
+                let provider = super::TracerProvider::builder()
+                    .with_resource(Resource::new(vec![
+                        KeyValue::new("shared", "explicit-val"),
+                        KeyValue::new("explicit-key", "explicit-val"),
+                    ]))
                     .build();
-                assert_resource(
-                    &user_provided_resource_config_provider,
-                    SERVICE_NAME,
-                    Some("unknown_service"),
-                );
-                assert_resource(
-                    &user_provided_resource_config_provider,
-                    "my-custom-key",
-                    Some("my-custom-value"),
-                );
-                assert_resource(
-                    &user_provided_resource_config_provider,
-                    "my-custom-key2",
-                    Some("my-custom-value2"),
+                let resource = &provider.config().resource;
+                assert_eq!(
+                    resource.get(Key::from("shared")),
+                    Some(Value::from("explicit-val"))
                 );
-                assert_resource(
-                    &user_provided_resource_config_provider,
-                    "k2",
-                    Some("value2"),
+                assert_eq!(
+                    resource.get(Key::from("env-key")),
+                    Some(Value::from("env-val"))
                 );
-                assert_telemetry_resource(&user_provided_resource_config_provider);
                 assert_eq!(
-                    user_provided_resource_config_provider
-                        .config()
-                        .resource
-                        .len(),
-                    7
+                    resource.get(Key::from("explicit-key")),
+                    Some(Value::from("explicit-val"))
                 );
             },
         );
+    }
 
-        // If user provided a resource, it takes priority during collision.
-        let no_service_name = super::TracerProvider::builder()
-            .with_config(Config {
-                resource: Cow::Owned(Resource::empty()),
-                ..Default::default()
+    #[derive(Debug)]
+    struct FixedDetector {
+        resource: Resource,
+        delay: Duration,
+    }
+
+    impl FixedDetector {
+        fn boxed(kvs: Vec<KeyValue>, delay: Duration) -> Box<dyn ResourceDetector + Send> {
+            Box::new(FixedDetector {
+                resource: Resource::new(kvs),
+                delay,
             })
+        }
+    }
+
+    impl ResourceDetector for FixedDetector {
+        fn detect(&self, _timeout: Duration) -> Resource {
+            std::thread::sleep(self.delay);
+            self.resource.clone()
+        }
+    }
+
+    #[test]
+    fn test_with_detectors_merge_order() {
+        let provider = super::TracerProvider::builder()
+            .with_detectors(vec![
+                FixedDetector::boxed(
+                    vec![KeyValue::new("shared", "first"), KeyValue::new("a", "1")],
+                    Duration::ZERO,
+                ),
+                FixedDetector::boxed(
+                    vec![KeyValue::new("shared", "second"), KeyValue::new("b", "2")],
+                    Duration::ZERO,
+                ),
+            ])
+            .with_resource(Resource::new(vec![KeyValue::new("b", "explicit")]))
             .build();
 
-        assert_eq!(no_service_name.config().resource.len(), 0)
+        let resource = &provider.config().resource;
+        assert_eq!(
+            resource.get(Key::from("shared")),
+            Some(Value::from("first"))
+        );
+        assert_eq!(resource.get(Key::from("a")), Some(Value::from("1")));
+        assert_eq!(resource.get(Key::from("b")), Some(Value::from("explicit")));
+        // detected on top of the default resource
+        assert!(resource.get(TELEMETRY_SDK_NAME.into()).is_some());
     }
 
     #[test]
-    fn test_shutdown_noops() {
-        let processor = TestSpanProcessor::new(false);
-        let assert_handle = processor.assert_info();
-        let tracer_provider = super::TracerProvider::new(TracerProviderInner {
-            processors: vec![Box::from(processor)],
-            config: Default::default(),
-        });
+    fn test_with_detectors_timeout() {
+        let start = Instant::now();
+        let provider = super::TracerProvider::builder()
+            .with_detectors(vec![
+                FixedDetector::boxed(vec![KeyValue::new("slow", "1")], Duration::from_secs(5)),
+                FixedDetector::boxed(vec![KeyValue::new("fast", "1")], Duration::ZERO),
+            ])
+            .with_detector_timeout(Duration::from_millis(100))
+            .build();
+        assert!(start.elapsed() < Duration::from_secs(2));
 
-        let test_tracer_1 = tracer_provider.tracer("test1");
-        let _ = test_tracer_1.start("test");
+        let resource = &provider.config().resource;
+        assert_eq!(resource.get(Key::from("slow")), None);
+        assert_eq!(resource.get(Key::from("fast")), Some(Value::from("1")));
+    }
 
-        assert!(assert_handle.started_span_count(1));
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_build_async_detectors_timeout() {
+        use crate::resource::AsyncResourceDetector;
+        use futures_util::future::BoxFuture;
 
-        let _ = test_tracer_1.start("test");
+        #[derive(Debug)]
+        struct AsyncFixedDetector {
+            resource: Resource,
+            delay: Duration,
+        }
 
-        assert!(assert_handle.started_span_count(2));
+        impl AsyncFixedDetector {
+            fn boxed(kvs: Vec<KeyValue>, delay: Duration) -> Box<dyn AsyncResourceDetector> {
+                Box::new(AsyncFixedDetector {
+                    resource: Resource::new(kvs),
+                    delay,
+                })
+            }
+        }
 
-        let shutdown = |tracer_provider: super::TracerProvider| {
-            let _ = tracer_provider.shutdown(); // shutdown once
-        };
+        impl AsyncResourceDetector for AsyncFixedDetector {
+            fn detect(&self) -> BoxFuture<'_, Resource> {
+                Box::pin(async move {
+                    tokio::time::sleep(self.delay).await;
+                    self.resource.clone()
+                })
+            }
+        }
 
-        // assert tracer provider can be shutdown using on a cloned version
-        shutdown(tracer_provider.clone());
+        let start = Instant::now();
+        let provider = super::TracerProvider::builder()
+            .with_async_detectors(vec![
+                AsyncFixedDetector::boxed(
+                    vec![KeyValue::new("shared", "slow"), KeyValue::new("slow", "1")],
+                    Duration::from_secs(5),
+                ),
+                AsyncFixedDetector::boxed(
+                    vec![KeyValue::new("shared", "fast"), KeyValue::new("fast", "1")],
+                    Duration::from_millis(10),
+                ),
+            ])
+            .with_detectors(vec![FixedDetector::boxed(
+                vec![KeyValue::new("sync", "1")],
+                Duration::ZERO,
+            )])
+            .with_detector_timeout(Duration::from_millis(100))
+            .build_async(crate::runtime::Tokio)
+            .await;
+        assert!(start.elapsed() < Duration::from_secs(2));
 
-        // after shutdown we should get noop tracer
-        let noop_tracer = tracer_provider.tracer("noop");
-        // noop tracer cannot start anything
-        let _ = noop_tracer.start("test");
-        assert!(assert_handle.started_span_count(2));
-        // noop tracer's tracer provider should be shutdown
-        assert!(noop_tracer.provider().is_shutdown.load(Ordering::SeqCst));
+        let resource = &provider.config().resource;
+        assert_eq!(resource.get(Key::from("slow")), None);
+        assert_eq!(resource.get(Key::from("fast")), Some(Value::from("1")));
+        assert_eq!(resource.get(Key::from("shared")), Some(Value::from("fast")));
+        assert_eq!(resource.get(Key::from("sync")), Some(Value::from("1")));
+    }
 
-        // existing tracer becomes noops after shutdown
-        let _ = test_tracer_1.start("test");
-        assert!(assert_handle.started_span_count(2));
+    #[test]
+    fn test_warmup_before_export() {
+        use crate::export::trace::{ExportResult, SpanExporter};
+        use crate::trace::SimpleSpanProcessor;
+        use futures_util::future::BoxFuture;
+
+        // Exporter recording its warmups and exports, in order.
+        #[derive(Debug)]
+        struct RecordingExporter {
+            events: Arc<Mutex<Vec<&'static str>>>,
+            fail_warmup: bool,
+        }
+
+        impl SpanExporter for RecordingExporter {
+            fn export(&mut self, _batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+                self.events.lock().unwrap().push("export");
+                Box::pin(async { Ok(()) })
+            }
+
+            fn warmup(&mut self) -> BoxFuture<'static, ExportResult> {
+                self.events.lock().unwrap().push("warmup");
+                let result = if self.fail_warmup {
+                    Err(TraceError::from("connection refused"))
+                } else {
+                    Ok(())
+                };
+                Box::pin(async move { result })
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let provider = super::TracerProvider::builder()
+            .with_span_processor(SimpleSpanProcessor::new(Box::new(RecordingExporter {
+                events: events.clone(),
+                fail_warmup: false,
+            })))
+            .build();
+        assert!(futures_executor::block_on(provider.warmup()).is_ok());
+        provider.tracer("test").start("span").end();
+        assert_eq!(*events.lock().unwrap(), vec!["warmup", "export"]);
+
+        let provider = super::TracerProvider::builder()
+            .with_span_processor(SimpleSpanProcessor::new(Box::new(RecordingExporter {
+                events: Arc::new(Mutex::new(Vec::new())),
+                fail_warmup: true,
+            })))
+            .build();
+        let err = futures_executor::block_on(provider.warmup()).unwrap_err();
+        assert!(err.to_string().contains("SimpleSpanProcessor"));
+        assert!(err.to_string().contains("connection refused"));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_with_batch_exporters() {
+        use crate::testing::trace::NoopSpanExporter;
+
+        let exporters: Vec<Box<dyn crate::export::trace::SpanExporter>> = vec![
+            Box::new(NoopSpanExporter::new()),
+            Box::new(NoopSpanExporter::new()),
+            Box::new(NoopSpanExporter::new()),
+        ];
+        let provider = super::TracerProvider::builder()
+            .with_batch_exporters(exporters, crate::runtime::TokioCurrentThread)
+            .build();
+        assert_eq!(provider.span_processors().len(), 3);
+
+        let provider = super::TracerProvider::builder()
+            .with_batch_exporters(Vec::new(), crate::runtime::TokioCurrentThread)
+            .allow_no_processors()
+            .build();
+        assert!(provider.span_processors().is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_build_checked_batch_sizes() {
+        use crate::testing::trace::NoopSpanExporter;
+
+        temp_env::with_vars(
+            [
+                ("OTEL_BSP_MAX_EXPORT_BATCH_SIZE", Some("1024")),
+                ("OTEL_BSP_MAX_QUEUE_SIZE", Some("256")),
+            ],
+            || {
+                let batch = super::TracerProvider::builder()
+                    .with_batch_exporter(
+                        NoopSpanExporter::new(),
+                        crate::runtime::TokioCurrentThread,
+                    )
+                    .build_checked();
+                assert_eq!(
+                    batch.unwrap_err(),
+                    BuilderError::ExportBatchLargerThanQueue {
+                        max_export_batch_size: 1024,
+                        max_queue_size: 256,
+                    }
+                );
+
+                // the sizes only matter to the processors the builder creates
+                assert!(super::TracerProvider::builder()
+                    .allow_no_processors()
+                    .build_checked()
+                    .is_ok());
+            },
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_flush_and_reset_stats() {
+        use crate::testing::trace::InMemorySpanExporterBuilder;
+
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = super::TracerProvider::builder()
+            .with_batch_exporter(exporter.clone(), crate::runtime::TokioCurrentThread)
+            .with_config(Config::default().with_max_attributes_per_span(1))
+            .build();
+        let tracer = provider.tracer("test");
+
+        for _ in 0..3 {
+            tracer
+                .span_builder("span")
+                .with_attributes(vec![KeyValue::new("a", 1), KeyValue::new("b", 2)])
+                .start(&tracer)
+                .end();
+        }
+        let results = provider.flush_and_reset_stats();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 3);
+
+        let telemetry = provider.telemetry_snapshot();
+        assert_eq!(telemetry.total.exported_spans, 0);
+        assert_eq!(telemetry.total.exports, 0);
+        assert_eq!(telemetry.total.last_export_latency, None);
+        assert_eq!(provider.truncation_stats(), Default::default());
+        assert_eq!(provider.status_counts().unset, 0);
+
+        tracer.start("after reset").end();
+        assert!(provider.force_flush().is_ok());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 4);
+        assert_eq!(provider.telemetry_snapshot().total.exported_spans, 1);
+        assert_eq!(provider.status_counts().unset, 1);
     }
 }
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Holds an [`Arc`] readers load without locking, while writers swap it for
+/// a new one.
+///
+/// Loading is an atomic pointer load and a reference count increment, so it
+/// can sit on hot paths like span start. Swapped out values are kept alive by
+/// the cell until no load that may have read their pointer is in progress,
+/// after which they only live as long as the snapshots loaded before.
+pub(crate) struct SnapshotCell<T> {
+    current: AtomicPtr<T>,
+    // values published by `store`, the current one included, which readers
+    // may still be incrementing the reference count of
+    published: Mutex<Vec<Arc<T>>>,
+    // number of loads in progress
+    loading: AtomicUsize,
+}
+
+impl<T> SnapshotCell<T> {
+    pub(crate) fn new(value: T) -> Self {
+        let value = Arc::new(value);
+        SnapshotCell {
+            current: AtomicPtr::new(Arc::as_ptr(&value) as *mut T),
+            published: Mutex::new(vec![value]),
+            loading: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the current value.
+    pub(crate) fn load(&self) -> Arc<T> {
+        self.loading.fetch_add(1, Ordering::SeqCst);
+        let ptr = self.current.load(Ordering::SeqCst);
+        // SAFETY: `ptr` points to a value owned by an `Arc` of `published`,
+        // and `store` doesn't release those while `loading` is not zero
+        let value = unsafe {
+            Arc::increment_strong_count(ptr);
+            Arc::from_raw(ptr)
+        };
+        self.loading.fetch_sub(1, Ordering::SeqCst);
+        value
+    }
+
+    /// Locks out other writers, e.g. to compute the next value from the
+    /// current one. Readers are not blocked.
+    pub(crate) fn lock(&self) -> SnapshotWriter<'_, T> {
+        SnapshotWriter {
+            cell: self,
+            published: self
+                .published
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        }
+    }
+}
+
+impl<T: Default> Default for SnapshotCell<T> {
+    fn default() -> Self {
+        SnapshotCell::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SnapshotCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SnapshotCell").field(&self.load()).finish()
+    }
+}
+
+/// Exclusive write access to a [`SnapshotCell`], see [`SnapshotCell::lock`].
+pub(crate) struct SnapshotWriter<'a, T> {
+    cell: &'a SnapshotCell<T>,
+    published: MutexGuard<'a, Vec<Arc<T>>>,
+}
+
+impl<T> SnapshotWriter<'_, T> {
+    /// Returns the current value.
+    pub(crate) fn current(&self) -> &Arc<T> {
+        let current: *const T = self.cell.current.load(Ordering::SeqCst);
+        self.published
+            .iter()
+            .find(|value| Arc::as_ptr(value) == current)
+            .expect("the current value is published")
+    }
+
+    /// Replaces the current value by `value` and returns the old one.
+    pub(crate) fn store(&mut self, value: T) -> Arc<T> {
+        let old = self.current().clone();
+        let value = Arc::new(value);
+        self.cell
+            .current
+            .store(Arc::as_ptr(&value) as *mut T, Ordering::SeqCst);
+        self.published.push(value);
+        // loads starting from now read the new pointer, so once none is in
+        // progress the cell can release every other value, the snapshots
+        // loaded before own theirs
+        if self.cell.loading.load(Ordering::SeqCst) == 0 {
+            let current: *const T = self.cell.current.load(Ordering::SeqCst);
+            self.published.retain(|value| Arc::as_ptr(value) == current);
+        }
+        old
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotCell;
+    use std::sync::Arc;
+
+    #[test]
+    fn load_returns_the_stored_value() {
+        let cell = SnapshotCell::new(1);
+        let before = cell.load();
+        let old = cell.lock().store(2);
+        assert_eq!(*old, 1);
+        assert_eq!(*before, 1);
+        assert_eq!(*cell.load(), 2);
+    }
+
+    #[test]
+    fn store_releases_old_values() {
+        let cell = SnapshotCell::new(vec![0]);
+        let first = Arc::downgrade(&cell.load());
+        drop(cell.lock().store(vec![1]));
+        assert!(first.upgrade().is_none());
+
+        let second = cell.load();
+        drop(cell.lock().store(vec![2]));
+        assert_eq!(*second, vec![1]);
+        let second_weak = Arc::downgrade(&second);
+        drop(second);
+        assert!(second_weak.upgrade().is_none());
+        assert_eq!(*cell.load(), vec![2]);
+    }
+
+    #[test]
+    fn concurrent_loads_and_stores() {
+        let cell = Arc::new(SnapshotCell::new(0usize));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                std::thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..10_000 {
+                        let value = *cell.load();
+                        assert!(value >= last);
+                        last = value;
+                    }
+                })
+            })
+            .collect();
+        for value in 1..=1_000 {
+            cell.lock().store(value);
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(*cell.load(), 1_000);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::trace::BuilderError;
 use opentelemetry::{
     trace::{
         Link, SamplingDecision, SamplingResult, SpanKind, TraceContextExt, TraceId, TraceState,
@@ -13,6 +14,40 @@ pub use jaeger_remote::{JaegerRemoteSampler, JaegerRemoteSamplerBuilder};
 #[cfg(feature = "jaeger_remote_sampler")]
 use opentelemetry_http::HttpClient;
 
+#[cfg(feature = "watched_file_sampler")]
+mod watched_file;
+
+#[cfg(feature = "watched_file_sampler")]
+pub use watched_file::WatchedFileSampler;
+
+mod adaptive;
+mod attribute;
+mod baggage;
+mod caching;
+mod composite;
+mod link_aware;
+mod operation_ratio;
+mod parent_based;
+mod probability;
+mod rate_limiting;
+mod record_only;
+mod scheduled;
+mod shadow;
+
+pub use adaptive::AdaptiveSampler;
+pub use attribute::AttributeSampler;
+pub use baggage::BaggageSampler;
+pub use caching::CachingSampler;
+pub use composite::{CompositeMode, CompositeSampler};
+pub use link_aware::LinkAwareSampler;
+pub use operation_ratio::OperationRatioSampler;
+pub use parent_based::ParentBasedSampler;
+pub use probability::ProbabilityRecordingSampler;
+pub use rate_limiting::RateLimitingSampler;
+pub use record_only::RecordOnlySampler;
+pub use scheduled::{SamplingSchedule, ScheduledRatioSampler};
+pub use shadow::{ShadowReport, ShadowSampler};
+
 /// The [`ShouldSample`] interface allows implementations to provide samplers
 /// which will return a sampling [`SamplingResult`] based on information that
 /// is typically available just before the [`Span`] was created.
@@ -80,6 +115,43 @@ pub trait ShouldSample: CloneShouldSample + Send + Sync + std::fmt::Debug {
         attributes: &[KeyValue],
         links: &[Link],
     ) -> SamplingResult;
+
+    /// Returns the probability the span described by the arguments is
+    /// sampled at, if [`should_sample`] decides it with a ratio, e.g. as a
+    /// [`Sampler::TraceIdRatioBased`] does. The default returns `None`, for
+    /// samplers deciding otherwise.
+    ///
+    /// Called with the arguments of a span [`should_sample`] sampled, e.g. by
+    /// [`ProbabilityRecordingSampler`] to record the probability on the span.
+    /// Samplers deferring to other samplers should defer to the same ones
+    /// here.
+    ///
+    /// [`should_sample`]: ShouldSample::should_sample
+    #[allow(clippy::too_many_arguments)]
+    fn sampling_probability(
+        &self,
+        _parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> Option<f64> {
+        None
+    }
+
+    /// Checks the configuration of the sampler, returning the first invariant
+    /// it breaks. The default accepts any configuration.
+    ///
+    /// Called by [`Builder::build_checked`], and by [`Builder::build`] which
+    /// replaces invalid samplers by the default one. Samplers deferring to
+    /// other samplers should check them too.
+    ///
+    /// [`Builder::build_checked`]: crate::trace::Builder::build_checked
+    /// [`Builder::build`]: crate::trace::Builder::build
+    fn validate(&self) -> Result<(), BuilderError> {
+        Ok(())
+    }
 }
 
 /// This trait should not be used directly instead users should use [`ShouldSample`].
@@ -162,6 +234,12 @@ impl Sampler {
     }
 }
 
+/// The probability spans sampled with `ratio` are sampled at, `None` for a
+/// NaN ratio, which never samples.
+pub(crate) fn ratio_probability(ratio: f64) -> Option<f64> {
+    (!ratio.is_nan()).then(|| ratio.clamp(0.0, 1.0))
+}
+
 impl ShouldSample for Sampler {
     fn should_sample(
         &self,
@@ -217,12 +295,136 @@ impl ShouldSample for Sampler {
             // No extra attributes ever set by the SDK samplers.
             attributes: Vec::new(),
             // all sampler in SDK will not modify trace state.
-            trace_state: match parent_context {
-                Some(ctx) => ctx.span().span_context().trace_state().clone(),
-                None => TraceState::default(),
-            },
+            trace_state: parent_trace_state(parent_context),
         }
     }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        match self {
+            Sampler::TraceIdRatioBased(ratio) => ratio_probability(*ratio),
+            // the decision of the parent is followed
+            Sampler::ParentBased(_) if parent_context.map_or(false, |cx| cx.has_active_span()) => {
+                None
+            }
+            Sampler::ParentBased(root) => root.sampling_probability(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            ),
+            #[cfg(feature = "jaeger_remote_sampler")]
+            Sampler::JaegerRemote(remote_sampler) => remote_sampler.sampling_probability(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            ),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        match self {
+            Sampler::TraceIdRatioBased(ratio) if ratio.is_nan() => {
+                Err(BuilderError::NanSamplerRatio)
+            }
+            Sampler::ParentBased(root) => root.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Maximum number of entries of a trace state, see the [W3C specification].
+///
+/// [W3C specification]: https://www.w3.org/TR/trace-context/#tracestate-header-field-values
+const MAX_TRACE_STATE_ENTRIES: usize = 32;
+
+/// How the trace state returned by the sampler is combined with the trace
+/// state of the parent span to get the trace state of a new span.
+///
+/// Whatever the policy, the trace state is capped at 32 entries, as required
+/// by the [W3C specification], dropping the rightmost entries.
+///
+/// See [`Config::with_trace_state_merge_policy`].
+///
+/// [W3C specification]: https://www.w3.org/TR/trace-context/#tracestate-header-field-values
+/// [`Config::with_trace_state_merge_policy`]: crate::trace::Config::with_trace_state_merge_policy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TraceStateMergePolicy {
+    /// Use the trace state returned by the sampler as is, the default.
+    /// Samplers are expected to start from the parent trace state.
+    #[default]
+    Replace,
+    /// Put the entries of the sampler first, overriding the parent entries
+    /// with the same key, followed by the other parent entries. This is how
+    /// the W3C specification expects updated entries to be ordered.
+    Override,
+    /// Keep the parent entries first, with their values, followed by the
+    /// entries of the sampler whose key isn't in the parent trace state.
+    Append,
+}
+
+impl TraceStateMergePolicy {
+    /// Merges the trace state `sampled` returned by the sampler into the
+    /// trace state `parent` of the parent span.
+    pub(crate) fn merge(&self, parent: &TraceState, sampled: TraceState) -> TraceState {
+        let (first, second) = match self {
+            TraceStateMergePolicy::Replace => {
+                let entries = trace_state_entries(&sampled);
+                if entries.len() <= MAX_TRACE_STATE_ENTRIES {
+                    return sampled;
+                }
+                (entries, Vec::new())
+            }
+            TraceStateMergePolicy::Override => {
+                (trace_state_entries(&sampled), trace_state_entries(parent))
+            }
+            TraceStateMergePolicy::Append => {
+                (trace_state_entries(parent), trace_state_entries(&sampled))
+            }
+        };
+
+        let mut entries: Vec<(String, String)> = Vec::with_capacity(first.len() + second.len());
+        for (key, value) in first.into_iter().chain(second) {
+            if !entries.iter().any(|(existing, _)| *existing == key) {
+                entries.push((key, value));
+            }
+        }
+        entries.truncate(MAX_TRACE_STATE_ENTRIES);
+        // the entries come from valid trace states
+        TraceState::from_key_value(entries).unwrap_or_default()
+    }
+}
+
+fn trace_state_entries(trace_state: &TraceState) -> Vec<(String, String)> {
+    trace_state
+        .header()
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Trace state of the parent span, returned by the samplers that don't
+/// change it.
+pub(crate) fn parent_trace_state(parent_context: Option<&Context>) -> TraceState {
+    match parent_context {
+        Some(ctx) => ctx.span().span_context().trace_state().clone(),
+        None => TraceState::default(),
+    }
 }
 
 pub(crate) fn sample_based_on_probability(prob: &f64, trace_id: TraceId) -> SamplingDecision {
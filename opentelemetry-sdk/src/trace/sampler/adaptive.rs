@@ -0,0 +1,179 @@
+use crate::trace::sampler::{parent_trace_state, ratio_probability, sample_based_on_probability};
+use crate::trace::{QueueDepthGauge, ShouldSample};
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::fmt;
+use std::sync::Arc;
+
+/// Function mapping the fill fraction of the queue to a sampling ratio.
+#[derive(Clone)]
+struct RatioFn(Arc<dyn Fn(f64) -> f64 + Send + Sync>);
+
+impl fmt::Debug for RatioFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RatioFn")
+    }
+}
+
+/// Sampler lowering the sampling ratio as the queue of a
+/// [`BatchSpanProcessor`] fills up, so that the export pipeline sheds load
+/// before spans get dropped, and raising it back once the queue drains.
+///
+/// The ratio is computed for each span by calling the mapping function with
+/// the fill fraction of the queue, from `0.0` when empty to `1.0` when full,
+/// see [`QueueDepthGauge::fill_fraction`].
+///
+/// Like [`Sampler::TraceIdRatioBased`], the decision is derived from the
+/// trace id, so a trace gets the same decision at a given ratio. Since the
+/// ratio changes with the queue depth, wrap the sampler in
+/// [`Sampler::ParentBased`] so that only root spans are sampled by it and
+/// the other spans of the trace follow their parent.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::runtime::RuntimeChannel;
+/// use opentelemetry_sdk::trace::{AdaptiveSampler, BatchSpanProcessor, Config, Sampler, TracerProvider};
+///
+/// fn init_tracing<R: RuntimeChannel>(processor: BatchSpanProcessor<R>) -> TracerProvider {
+///     // sample everything until the queue is half full, then less and less
+///     let sampler = AdaptiveSampler::new(processor.queue_depth_gauge(), |fill| {
+///         (2.0 * (1.0 - fill)).min(1.0)
+///     });
+///     TracerProvider::builder()
+///         .with_span_processor(processor)
+///         .with_config(Config::default().with_sampler(Sampler::ParentBased(Box::new(sampler))))
+///         .build()
+/// }
+/// ```
+///
+/// [`BatchSpanProcessor`]: crate::trace::BatchSpanProcessor
+/// [`Sampler::TraceIdRatioBased`]: crate::trace::Sampler::TraceIdRatioBased
+/// [`Sampler::ParentBased`]: crate::trace::Sampler::ParentBased
+#[derive(Clone, Debug)]
+pub struct AdaptiveSampler {
+    gauge: QueueDepthGauge,
+    ratio_fn: RatioFn,
+}
+
+impl AdaptiveSampler {
+    /// Create a sampler sampling spans at the ratio `ratio_fn` returns for
+    /// the fill fraction of the queue read by `gauge`.
+    pub fn new<F>(gauge: QueueDepthGauge, ratio_fn: F) -> Self
+    where
+        F: Fn(f64) -> f64 + Send + Sync + 'static,
+    {
+        AdaptiveSampler {
+            gauge,
+            ratio_fn: RatioFn(Arc::new(ratio_fn)),
+        }
+    }
+
+    /// Create a sampler whose ratio decreases linearly from `1.0` when the
+    /// queue is empty to `0.0` when it is full.
+    pub fn linear(gauge: QueueDepthGauge) -> Self {
+        Self::new(gauge, |fill| 1.0 - fill)
+    }
+
+    /// Returns the ratio spans are currently sampled at.
+    pub fn ratio(&self) -> f64 {
+        (self.ratio_fn.0)(self.gauge.fill_fraction()).clamp(0.0, 1.0)
+    }
+}
+
+impl ShouldSample for AdaptiveSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        SamplingResult {
+            decision: sample_based_on_probability(&self.ratio(), trace_id),
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        _parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> Option<f64> {
+        ratio_probability(self.ratio())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptiveSampler;
+    use crate::trace::span_processor::BatchStats;
+    use crate::trace::{QueueDepthGauge, ShouldSample};
+    use opentelemetry::trace::{SamplingDecision, SpanKind, TraceId};
+    use std::sync::Arc;
+
+    fn sampled(sampler: &AdaptiveSampler) -> usize {
+        (0..1000u128)
+            .filter(|i| {
+                let trace_id =
+                    TraceId::from_u128(i.wrapping_mul(0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835));
+                sampler
+                    .should_sample(None, trace_id, "test", &SpanKind::Internal, &[], &[])
+                    .decision
+                    == SamplingDecision::RecordAndSample
+            })
+            .count()
+    }
+
+    #[test]
+    fn samples_less_as_the_queue_fills() {
+        let stats = Arc::new(BatchStats::default());
+        let sampler = AdaptiveSampler::linear(QueueDepthGauge::new(stats.clone(), 100));
+        assert_eq!(sampler.ratio(), 1.0);
+        assert_eq!(sampled(&sampler), 1000);
+
+        for _ in 0..50 {
+            stats.record_enqueued();
+        }
+        assert_eq!(sampler.ratio(), 0.5);
+        let half = sampled(&sampler);
+        assert!(half > 400 && half < 600, "{half}");
+
+        // full queue
+        for _ in 0..50 {
+            stats.record_enqueued();
+        }
+        assert_eq!(sampler.ratio(), 0.0);
+        assert_eq!(sampled(&sampler), 0);
+
+        // drained queue
+        stats.record_dequeued(100);
+        assert_eq!(sampler.ratio(), 1.0);
+    }
+
+    #[test]
+    fn decisions_are_stable_at_a_given_ratio() {
+        let stats = Arc::new(BatchStats::default());
+        for _ in 0..30 {
+            stats.record_enqueued();
+        }
+        let sampler = AdaptiveSampler::linear(QueueDepthGauge::new(stats, 100));
+        let decision = |trace_id| {
+            sampler
+                .should_sample(None, trace_id, "test", &SpanKind::Internal, &[], &[])
+                .decision
+        };
+
+        for i in 0..100u128 {
+            let trace_id = TraceId::from_u128((i << 64) | i.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+            assert_eq!(decision(trace_id), decision(trace_id));
+        }
+    }
+}
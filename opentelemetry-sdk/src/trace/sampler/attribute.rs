@@ -0,0 +1,214 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::fmt;
+use std::sync::Arc;
+
+type Rule = dyn Fn(&str, &[KeyValue]) -> Option<SamplingDecision> + Send + Sync;
+
+/// Sampler deciding from the span name and the attributes available when the
+/// span is started, e.g. to always sample requests to a given route.
+///
+/// The rule receives the span name and the attributes passed to
+/// [`ShouldSample::should_sample`], i.e. the attributes set on the span
+/// builder before the span was started. Attributes added to the span later
+/// are not visible. When the rule returns `None`, the decision is made by the
+/// fallback sampler instead.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::trace::SamplingDecision;
+/// use opentelemetry_sdk::trace::{AttributeSampler, Sampler};
+///
+/// let sampler = AttributeSampler::new(
+///     |name, attributes| {
+///         if name == "SELECT" {
+///             return Some(SamplingDecision::Drop);
+///         }
+///         attributes
+///             .iter()
+///             .any(|kv| kv.key.as_str() == "http.route" && kv.value.as_str() == "/checkout")
+///             .then_some(SamplingDecision::RecordAndSample)
+///     },
+///     Sampler::TraceIdRatioBased(0.1),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct AttributeSampler {
+    rule: Arc<Rule>,
+    fallback: Box<dyn ShouldSample>,
+}
+
+impl fmt::Debug for AttributeSampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttributeSampler")
+            .field("fallback", &self.fallback)
+            .finish()
+    }
+}
+
+impl AttributeSampler {
+    /// Create a sampler applying `rule`, and deferring to `fallback` when the
+    /// rule returns `None`.
+    pub fn new<F, S>(rule: F, fallback: S) -> Self
+    where
+        F: Fn(&str, &[KeyValue]) -> Option<SamplingDecision> + Send + Sync + 'static,
+        S: ShouldSample + 'static,
+    {
+        AttributeSampler {
+            rule: Arc::new(rule),
+            fallback: Box::new(fallback),
+        }
+    }
+}
+
+impl ShouldSample for AttributeSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        match (self.rule)(name, attributes) {
+            Some(decision) => SamplingResult {
+                decision,
+                attributes: Vec::new(),
+                trace_state: parent_trace_state(parent_context),
+            },
+            None => self.fallback.should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            ),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        match (self.rule)(name, attributes) {
+            // decided by the rule, not by a ratio
+            Some(_) => None,
+            None => self.fallback.sampling_probability(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            ),
+        }
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.fallback.validate()
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::AttributeSampler;
+    use crate::testing::trace::InMemorySpanExporterBuilder;
+    use crate::trace::{config, Sampler, TracerProvider};
+    use opentelemetry::trace::{SamplingDecision, Span, Tracer, TracerProvider as _};
+    use opentelemetry::KeyValue;
+    use std::sync::{Arc, Mutex};
+
+    fn checkout_sampler(fallback: Sampler) -> AttributeSampler {
+        AttributeSampler::new(
+            |name, attributes| {
+                if name == "SELECT" {
+                    return Some(SamplingDecision::Drop);
+                }
+                attributes
+                    .iter()
+                    .any(|kv| kv.key.as_str() == "http.route" && kv.value.as_str() == "/checkout")
+                    .then_some(SamplingDecision::RecordAndSample)
+            },
+            fallback,
+        )
+    }
+
+    #[test]
+    fn rule_decides_on_match() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(config().with_sampler(checkout_sampler(Sampler::AlwaysOn)))
+            .build();
+        let tracer = provider.tracer("test");
+
+        tracer
+            .span_builder("GET")
+            .with_attributes(vec![KeyValue::new("http.route", "/checkout")])
+            .start(&tracer)
+            .end();
+        tracer.start("SELECT").end();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "GET");
+    }
+
+    #[test]
+    fn falls_through_to_fallback() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(config().with_sampler(checkout_sampler(Sampler::AlwaysOff)))
+            .build();
+        let tracer = provider.tracer("test");
+
+        let span = tracer
+            .span_builder("GET")
+            .with_attributes(vec![KeyValue::new("http.route", "/users")])
+            .start(&tracer);
+        assert!(!span.is_recording());
+
+        tracer
+            .span_builder("GET")
+            .with_attributes(vec![KeyValue::new("http.route", "/checkout")])
+            .start(&tracer)
+            .end();
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rule_sees_start_attributes() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let rule_seen = seen.clone();
+        let provider = TracerProvider::builder()
+            .with_config(config().with_sampler(AttributeSampler::new(
+                move |_, attributes| {
+                    rule_seen.lock().unwrap().extend_from_slice(attributes);
+                    None
+                },
+                Sampler::AlwaysOn,
+            )))
+            .build();
+        let tracer = provider.tracer("test");
+
+        let mut span = tracer
+            .span_builder("span")
+            .with_attributes(vec![KeyValue::new("at.start", 1)])
+            .start(&tracer);
+        span.set_attribute(KeyValue::new("after.start", 2));
+        span.end();
+
+        assert_eq!(*seen.lock().unwrap(), vec![KeyValue::new("at.start", 1)]);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::baggage::BaggageExt;
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, Key, KeyValue};
+use std::borrow::Cow;
+
+/// Sampler forcing the sampling of spans whose context carries a given
+/// baggage entry, e.g. to trace a single request end to end when an upstream
+/// service sets `force_sample=true` in the baggage header.
+///
+/// When the baggage of the parent context has the entry `key` with the value
+/// `value`, the span is recorded and sampled whatever the fallback sampler
+/// would decide. Otherwise the decision is made by the fallback sampler.
+///
+/// The baggage must be in the context the span is started with, e.g. after
+/// extracting it with a [`BaggagePropagator`].
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{BaggageSampler, Sampler};
+///
+/// let sampler = BaggageSampler::new("force_sample", "true", Sampler::TraceIdRatioBased(0.01));
+/// ```
+///
+/// [`BaggagePropagator`]: crate::propagation::BaggagePropagator
+#[derive(Clone, Debug)]
+pub struct BaggageSampler {
+    key: Key,
+    value: Cow<'static, str>,
+    fallback: Box<dyn ShouldSample>,
+}
+
+impl BaggageSampler {
+    /// Create a sampler sampling the spans whose context has the baggage
+    /// entry `key=value`, and deferring to `fallback` for the others.
+    pub fn new<K, V, S>(key: K, value: V, fallback: S) -> Self
+    where
+        K: Into<Key>,
+        V: Into<Cow<'static, str>>,
+        S: ShouldSample + 'static,
+    {
+        BaggageSampler {
+            key: key.into(),
+            value: value.into(),
+            fallback: Box::new(fallback),
+        }
+    }
+}
+
+impl ShouldSample for BaggageSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let forced = parent_context
+            .and_then(|cx| cx.baggage().get(self.key.as_str()))
+            .map_or(false, |value| value.as_str() == self.value);
+        if !forced {
+            return self.fallback.should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            );
+        }
+
+        SamplingResult {
+            decision: SamplingDecision::RecordAndSample,
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        let forced = parent_context
+            .and_then(|cx| cx.baggage().get(self.key.as_str()))
+            .map_or(false, |value| value.as_str() == self.value);
+        if forced {
+            return None;
+        }
+        self.fallback.sampling_probability(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.fallback.validate()
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::BaggageSampler;
+    use crate::testing::trace::InMemorySpanExporterBuilder;
+    use crate::trace::{Config, Sampler, TracerProvider};
+    use opentelemetry::baggage::BaggageExt;
+    use opentelemetry::trace::{Span, Tracer, TracerProvider as _};
+    use opentelemetry::{Context, KeyValue};
+
+    fn provider(exporter: crate::testing::trace::InMemorySpanExporter) -> TracerProvider {
+        TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .with_config(Config::default().with_sampler(BaggageSampler::new(
+                "force_sample",
+                "true",
+                Sampler::AlwaysOff,
+            )))
+            .build()
+    }
+
+    #[test]
+    fn baggage_forces_sampling() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = provider(exporter.clone());
+        let tracer = provider.tracer("test");
+
+        let cx = Context::new().with_baggage(vec![KeyValue::new("force_sample", "true")]);
+        let span = tracer.start_with_context("forced", &cx);
+        assert!(span.span_context().is_sampled());
+        drop(span);
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "forced");
+    }
+
+    #[test]
+    fn falls_through_to_fallback() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = provider(exporter.clone());
+        let tracer = provider.tracer("test");
+
+        let span = tracer.start_with_context("no baggage", &Context::new());
+        assert!(!span.is_recording());
+
+        let cx = Context::new().with_baggage(vec![KeyValue::new("force_sample", "false")]);
+        let span = tracer.start_with_context("other value", &cx);
+        assert!(!span.is_recording());
+
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Default number of trace ids a [`CachingSampler`] remembers decisions for.
+const DEFAULT_CACHE_SIZE: usize = 1024;
+
+/// Sampler remembering the decision of the sampler it wraps for the most
+/// recently seen trace ids, so that spans of a trace already sampled don't
+/// consult it again.
+///
+/// Decisions are keyed on the trace id only, all the spans of a trace cached
+/// here therefore get the same decision. Only the first span of each trace
+/// gets the attributes of the wrapped sampler's result, the others reuse the
+/// decision alone, with the trace state of their parent.
+///
+/// The cache is bounded, evicting the least recently used trace ids, and is
+/// shared by all clones of the sampler.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{CachingSampler, Sampler};
+///
+/// let sampler = CachingSampler::new(Sampler::TraceIdRatioBased(0.1)).with_cache_size(4096);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachingSampler {
+    inner: Box<dyn ShouldSample>,
+    cache: Arc<Mutex<DecisionCache>>,
+}
+
+impl CachingSampler {
+    /// Create a sampler caching the decisions of `inner`.
+    pub fn new<S: ShouldSample + 'static>(inner: S) -> Self {
+        CachingSampler {
+            inner: Box::new(inner),
+            cache: Arc::new(Mutex::new(DecisionCache::new(DEFAULT_CACHE_SIZE))),
+        }
+    }
+
+    /// Set the number of trace ids decisions are remembered for, `1024` by
+    /// default. A size of `0` disables caching.
+    pub fn with_cache_size(self, cache_size: usize) -> Self {
+        CachingSampler {
+            cache: Arc::new(Mutex::new(DecisionCache::new(cache_size))),
+            ..self
+        }
+    }
+}
+
+impl ShouldSample for CachingSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let cached = self
+            .cache
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.get(trace_id));
+        if let Some(decision) = cached {
+            return SamplingResult {
+                decision,
+                attributes: Vec::new(),
+                trace_state: parent_trace_state(parent_context),
+            };
+        }
+
+        let mut result =
+            self.inner
+                .should_sample(parent_context, trace_id, name, span_kind, attributes, links);
+        if let Ok(mut cache) = self.cache.lock() {
+            // another span of the trace may have been sampled meanwhile, its
+            // decision wins so the whole trace shares it
+            result.decision = cache.get_or_insert(trace_id, result.decision);
+        }
+        result
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        self.inner.sampling_probability(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.inner.validate()
+    }
+}
+
+/// Least recently used decisions, by trace id.
+///
+/// Each access is recorded in `order` with an increasing tick, entries whose
+/// tick doesn't match the one in `decisions` are stale and skipped on
+/// eviction.
+#[derive(Debug)]
+struct DecisionCache {
+    capacity: usize,
+    tick: u64,
+    decisions: HashMap<TraceId, (SamplingDecision, u64)>,
+    order: VecDeque<(TraceId, u64)>,
+}
+
+impl DecisionCache {
+    fn new(capacity: usize) -> Self {
+        DecisionCache {
+            capacity,
+            tick: 0,
+            decisions: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, trace_id: TraceId) -> Option<SamplingDecision> {
+        let tick = self.tick.wrapping_add(1);
+        let (decision, last_used) = self.decisions.get_mut(&trace_id)?;
+        *last_used = tick;
+        let decision = decision.clone();
+        self.tick = tick;
+        self.order.push_back((trace_id, tick));
+        self.compact();
+        Some(decision)
+    }
+
+    fn get_or_insert(&mut self, trace_id: TraceId, decision: SamplingDecision) -> SamplingDecision {
+        if let Some(cached) = self.get(trace_id) {
+            return cached;
+        }
+        if self.capacity == 0 {
+            return decision;
+        }
+
+        self.tick = self.tick.wrapping_add(1);
+        self.decisions
+            .insert(trace_id, (decision.clone(), self.tick));
+        self.order.push_back((trace_id, self.tick));
+        while self.decisions.len() > self.capacity {
+            match self.order.pop_front() {
+                Some((oldest, tick)) if is_last_use(&self.decisions, &oldest, tick) => {
+                    self.decisions.remove(&oldest);
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+        decision
+    }
+
+    // drops stale accesses so hits don't grow `order` without bound
+    fn compact(&mut self) {
+        if self.order.len() > self.capacity.saturating_mul(2) {
+            let decisions = &self.decisions;
+            self.order
+                .retain(|(trace_id, tick)| is_last_use(decisions, trace_id, *tick));
+        }
+    }
+}
+
+fn is_last_use(
+    decisions: &HashMap<TraceId, (SamplingDecision, u64)>,
+    trace_id: &TraceId,
+    tick: u64,
+) -> bool {
+    decisions
+        .get(trace_id)
+        .map_or(false, |(_, last_used)| *last_used == tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachingSampler;
+    use crate::trace::{Sampler, ShouldSample};
+    use opentelemetry::trace::{SamplingDecision, SamplingResult, SpanKind, TraceId};
+    use opentelemetry::{Context, KeyValue};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Sampler counting how many times it was consulted.
+    #[derive(Clone, Debug, Default)]
+    struct CountingSampler {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ShouldSample for CountingSampler {
+        fn should_sample(
+            &self,
+            parent_context: Option<&Context>,
+            trace_id: TraceId,
+            name: &str,
+            span_kind: &SpanKind,
+            attributes: &[KeyValue],
+            links: &[opentelemetry::trace::Link],
+        ) -> SamplingResult {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Sampler::TraceIdRatioBased(0.5).should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            )
+        }
+    }
+
+    fn decision(sampler: &CachingSampler, trace_id: u128) -> SamplingDecision {
+        sampler
+            .should_sample(
+                None,
+                TraceId::from(trace_id),
+                "test",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn consults_inner_once_per_trace_id() {
+        let inner = CountingSampler::default();
+        let sampler = CachingSampler::new(inner.clone()).with_cache_size(100);
+
+        let trace_ids: Vec<u128> = (1..=100).map(|i| i * 0x0123_4567_89ab_cdef).collect();
+        let first: Vec<_> = trace_ids.iter().map(|id| decision(&sampler, *id)).collect();
+        for _ in 0..1000 {
+            for (id, expected) in trace_ids.iter().zip(&first) {
+                assert_eq!(&decision(&sampler, *id), expected);
+            }
+        }
+
+        assert_eq!(inner.calls.load(Ordering::Relaxed), trace_ids.len());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let inner = CountingSampler::default();
+        let sampler = CachingSampler::new(inner.clone()).with_cache_size(2);
+
+        decision(&sampler, 1);
+        decision(&sampler, 2);
+        // 1 is now more recently used than 2
+        decision(&sampler, 1);
+        decision(&sampler, 3);
+        assert_eq!(inner.calls.load(Ordering::Relaxed), 3);
+
+        decision(&sampler, 1);
+        decision(&sampler, 3);
+        assert_eq!(inner.calls.load(Ordering::Relaxed), 3);
+        decision(&sampler, 2);
+        assert_eq!(inner.calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn zero_cache_size_disables_caching() {
+        let inner = CountingSampler::default();
+        let sampler = CachingSampler::new(inner.clone()).with_cache_size(0);
+
+        decision(&sampler, 1);
+        decision(&sampler, 1);
+        assert_eq!(inner.calls.load(Ordering::Relaxed), 2);
+    }
+}
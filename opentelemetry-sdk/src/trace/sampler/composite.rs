@@ -0,0 +1,326 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId, TraceState};
+use opentelemetry::{Context, KeyValue};
+
+/// How a [`CompositeSampler`] combines the decisions of its samplers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompositeMode {
+    /// Sample only if every sampler samples. The span is dropped if any
+    /// sampler drops it, and only recorded otherwise.
+    All,
+    /// Sample if any sampler samples. The span is recorded if any sampler
+    /// records it, and dropped otherwise.
+    Any,
+}
+
+/// Sampler combining the decisions of several samplers, e.g. to sample if an
+/// error attribute is set *or* for 10% of the traffic.
+///
+/// Every sampler is asked for a decision with the same arguments, in the
+/// order they were added, and their decisions are combined according to the
+/// [`CompositeMode`]. A composite sampler without samplers samples every span
+/// in [`CompositeMode::All`] and drops every span in [`CompositeMode::Any`].
+///
+/// # Merging attributes and trace state
+///
+/// Unless the span is dropped, it gets the attributes of every sampler's
+/// result. Its trace state contains the entries of every sampler's trace
+/// state. In both cases, when several samplers return the same key, the value
+/// of the first sampler wins, and entries are ordered by sampler, then as
+/// each sampler returned them.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{CompositeSampler, Sampler};
+///
+/// let sampler = CompositeSampler::any()
+///     .with_sampler(Sampler::TraceIdRatioBased(0.1))
+///     .with_sampler(my_error_sampler());
+/// # fn my_error_sampler() -> Sampler { Sampler::AlwaysOff }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompositeSampler {
+    mode: CompositeMode,
+    samplers: Vec<Box<dyn ShouldSample>>,
+}
+
+impl CompositeSampler {
+    /// Create a composite sampler without samplers, combining decisions
+    /// according to `mode`.
+    pub fn new(mode: CompositeMode) -> Self {
+        CompositeSampler {
+            mode,
+            samplers: Vec::new(),
+        }
+    }
+
+    /// Create a composite sampler sampling only if all its samplers sample.
+    pub fn all() -> Self {
+        Self::new(CompositeMode::All)
+    }
+
+    /// Create a composite sampler sampling if any of its samplers samples.
+    pub fn any() -> Self {
+        Self::new(CompositeMode::Any)
+    }
+
+    /// Add a sampler to ask for a decision.
+    pub fn with_sampler<T: ShouldSample + 'static>(mut self, sampler: T) -> Self {
+        self.samplers.push(Box::new(sampler));
+        self
+    }
+
+    fn combine(&self, decisions: impl Iterator<Item = SamplingDecision>) -> SamplingDecision {
+        let mut recorded = false;
+        let mut sampled = false;
+        let mut dropped = false;
+        for decision in decisions {
+            match decision {
+                SamplingDecision::RecordAndSample => sampled = true,
+                SamplingDecision::RecordOnly => recorded = true,
+                SamplingDecision::Drop => dropped = true,
+            }
+        }
+
+        match self.mode {
+            CompositeMode::All if dropped => SamplingDecision::Drop,
+            CompositeMode::All if recorded => SamplingDecision::RecordOnly,
+            CompositeMode::All => SamplingDecision::RecordAndSample,
+            CompositeMode::Any if sampled => SamplingDecision::RecordAndSample,
+            CompositeMode::Any if recorded => SamplingDecision::RecordOnly,
+            CompositeMode::Any => SamplingDecision::Drop,
+        }
+    }
+}
+
+impl ShouldSample for CompositeSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let results: Vec<_> = self
+            .samplers
+            .iter()
+            .map(|sampler| {
+                sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+            })
+            .collect();
+
+        let decision = self.combine(results.iter().map(|result| result.decision.clone()));
+        if results.is_empty() {
+            return SamplingResult {
+                decision,
+                attributes: Vec::new(),
+                trace_state: parent_trace_state(parent_context),
+            };
+        }
+
+        let mut merged_attributes: Vec<KeyValue> = Vec::new();
+        if decision != SamplingDecision::Drop {
+            for attribute in results.iter().flat_map(|result| &result.attributes) {
+                if !merged_attributes.iter().any(|kv| kv.key == attribute.key) {
+                    merged_attributes.push(attribute.clone());
+                }
+            }
+        }
+
+        SamplingResult {
+            decision,
+            attributes: merged_attributes,
+            trace_state: merge_trace_states(&results),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        let probabilities = self.samplers.iter().map(|sampler| {
+            sampler.sampling_probability(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            )
+        });
+        match self.mode {
+            // every sampler sampled the span, and a trace id passing the
+            // lowest ratio passes the higher ones
+            CompositeMode::All => probabilities.flatten().reduce(f64::min),
+            // the span may have been sampled without a ratio unless every
+            // sampler decides with one
+            CompositeMode::Any => probabilities
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .reduce(f64::max),
+        }
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.samplers
+            .iter()
+            .try_for_each(|sampler| sampler.validate())
+    }
+}
+
+fn merge_trace_states(results: &[SamplingResult]) -> TraceState {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for result in results {
+        let header = result.trace_state.header();
+        for entry in header.split_terminator(',') {
+            if let Some((key, value)) = entry.split_once('=') {
+                if !entries.iter().any(|(existing, _)| existing == key) {
+                    entries.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+
+    // entries come from valid trace states, so this only fails on invariants
+    // we don't control, in which case the first sampler's state is kept
+    TraceState::from_key_value(entries).unwrap_or_else(|_| results[0].trace_state.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompositeMode, CompositeSampler};
+    use crate::trace::{Sampler, ShouldSample};
+    use opentelemetry::trace::{
+        Link, SamplingDecision, SamplingResult, SpanKind, TraceId, TraceState,
+    };
+    use opentelemetry::{Context, KeyValue};
+
+    /// Samples spans with an `error` attribute and tags them.
+    #[derive(Clone, Debug)]
+    struct ErrorSampler;
+
+    impl ShouldSample for ErrorSampler {
+        fn should_sample(
+            &self,
+            _parent_context: Option<&Context>,
+            _trace_id: TraceId,
+            _name: &str,
+            _span_kind: &SpanKind,
+            attributes: &[KeyValue],
+            _links: &[Link],
+        ) -> SamplingResult {
+            let error = attributes.iter().any(|kv| kv.key.as_str() == "error");
+            SamplingResult {
+                decision: if error {
+                    SamplingDecision::RecordAndSample
+                } else {
+                    SamplingDecision::Drop
+                },
+                attributes: vec![KeyValue::new("sampled.by", "error")],
+                trace_state: TraceState::from_key_value([("rule", "error"), ("a", "1")]).unwrap(),
+            }
+        }
+    }
+
+    /// Returns a fixed result.
+    #[derive(Clone, Debug)]
+    struct FixedSampler(SamplingDecision, &'static str);
+
+    impl ShouldSample for FixedSampler {
+        fn should_sample(
+            &self,
+            _parent_context: Option<&Context>,
+            _trace_id: TraceId,
+            _name: &str,
+            _span_kind: &SpanKind,
+            _attributes: &[KeyValue],
+            _links: &[Link],
+        ) -> SamplingResult {
+            SamplingResult {
+                decision: self.0.clone(),
+                attributes: vec![KeyValue::new("sampled.by", self.1)],
+                trace_state: TraceState::from_key_value([("rule", self.1), ("b", "2")]).unwrap(),
+            }
+        }
+    }
+
+    fn sample(sampler: &CompositeSampler, attributes: &[KeyValue]) -> SamplingResult {
+        sampler.should_sample(
+            None,
+            // above the 0.1 ratio
+            TraceId::from(u128::MAX),
+            "test",
+            &SpanKind::Internal,
+            attributes,
+            &[],
+        )
+    }
+
+    #[test]
+    fn any_combines_ratio_and_attribute_samplers() {
+        let sampler = CompositeSampler::any()
+            .with_sampler(Sampler::TraceIdRatioBased(0.1))
+            .with_sampler(ErrorSampler);
+
+        let error = sample(&sampler, &[KeyValue::new("error", true)]);
+        assert_eq!(error.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(error.attributes, vec![KeyValue::new("sampled.by", "error")]);
+
+        let ok = sample(&sampler, &[]);
+        assert_eq!(ok.decision, SamplingDecision::Drop);
+        assert!(ok.attributes.is_empty());
+    }
+
+    #[test]
+    fn all_requires_every_sampler() {
+        let sampler = CompositeSampler::all()
+            .with_sampler(Sampler::AlwaysOn)
+            .with_sampler(ErrorSampler);
+        assert_eq!(
+            sample(&sampler, &[KeyValue::new("error", true)]).decision,
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(sample(&sampler, &[]).decision, SamplingDecision::Drop);
+
+        let sampler = CompositeSampler::all()
+            .with_sampler(Sampler::AlwaysOn)
+            .with_sampler(FixedSampler(SamplingDecision::RecordOnly, "fixed"));
+        assert_eq!(sample(&sampler, &[]).decision, SamplingDecision::RecordOnly);
+    }
+
+    #[test]
+    fn first_sampler_wins_on_collisions() {
+        let sampler = CompositeSampler::new(CompositeMode::Any)
+            .with_sampler(ErrorSampler)
+            .with_sampler(FixedSampler(SamplingDecision::RecordAndSample, "fixed"));
+
+        let result = sample(&sampler, &[]);
+        assert_eq!(result.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(
+            result.attributes,
+            vec![KeyValue::new("sampled.by", "error")]
+        );
+        assert_eq!(result.trace_state.header(), "rule=error,a=1,b=2");
+    }
+
+    #[test]
+    fn empty_composite() {
+        assert_eq!(
+            sample(&CompositeSampler::all(), &[]).decision,
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(
+            sample(&CompositeSampler::any(), &[]).decision,
+            SamplingDecision::Drop
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+
+/// Sampler forcing the sampling of spans linked to a sampled span, e.g. to
+/// keep the batch job processing messages whose producing request was
+/// sampled.
+///
+/// When any of the links the span is started with points to a sampled span
+/// context, the span is recorded and sampled whatever the fallback sampler
+/// would decide. Otherwise the decision is made by the fallback sampler.
+///
+/// Only the links known when the span starts are inspected, links added
+/// afterwards with [`Span::add_link`] don't affect sampling.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{LinkAwareSampler, Sampler};
+///
+/// let sampler = LinkAwareSampler::new(Sampler::TraceIdRatioBased(0.01));
+/// ```
+///
+/// [`Span::add_link`]: opentelemetry::trace::Span::add_link
+#[derive(Clone, Debug)]
+pub struct LinkAwareSampler {
+    fallback: Box<dyn ShouldSample>,
+}
+
+impl LinkAwareSampler {
+    /// Create a sampler sampling the spans linked to a sampled span, and
+    /// deferring to `fallback` for the others.
+    pub fn new<S: ShouldSample + 'static>(fallback: S) -> Self {
+        LinkAwareSampler {
+            fallback: Box::new(fallback),
+        }
+    }
+}
+
+impl ShouldSample for LinkAwareSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        if !links.iter().any(|link| link.span_context.is_sampled()) {
+            return self.fallback.should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            );
+        }
+
+        SamplingResult {
+            decision: SamplingDecision::RecordAndSample,
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        if links.iter().any(|link| link.span_context.is_sampled()) {
+            return None;
+        }
+        self.fallback.sampling_probability(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.fallback.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinkAwareSampler;
+    use crate::trace::{Sampler, ShouldSample};
+    use opentelemetry::trace::{
+        Link, SamplingDecision, SpanContext, SpanId, SpanKind, TraceFlags, TraceId, TraceState,
+    };
+
+    fn link(trace_flags: TraceFlags) -> Link {
+        Link::with_context(SpanContext::new(
+            TraceId::from_u128(2),
+            SpanId::from_u64(2),
+            trace_flags,
+            true,
+            TraceState::default(),
+        ))
+    }
+
+    fn decision(links: &[Link]) -> SamplingDecision {
+        LinkAwareSampler::new(Sampler::AlwaysOff)
+            .should_sample(
+                None,
+                TraceId::from_u128(1),
+                "test",
+                &SpanKind::Consumer,
+                &[],
+                links,
+            )
+            .decision
+    }
+
+    #[test]
+    fn sampled_link_forces_sampling() {
+        let links = [link(TraceFlags::default()), link(TraceFlags::SAMPLED)];
+        assert_eq!(decision(&links), SamplingDecision::RecordAndSample);
+    }
+
+    #[test]
+    fn unsampled_link_falls_through_to_fallback() {
+        assert_eq!(
+            decision(&[link(TraceFlags::default())]),
+            SamplingDecision::Drop
+        );
+        assert_eq!(decision(&[]), SamplingDecision::Drop);
+    }
+}
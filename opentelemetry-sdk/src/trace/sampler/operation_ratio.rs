@@ -0,0 +1,179 @@
+use crate::trace::sampler::{parent_trace_state, ratio_probability, sample_based_on_probability};
+use crate::trace::ShouldSample;
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Sampler sampling each operation, i.e. span name, at its own ratio, e.g.
+/// 1% of `list` spans but all `payment` spans.
+///
+/// Keys of the ratio map are either exact span names, or prefixes when they
+/// end with `*`, e.g. `db.*` matches every span whose name starts with
+/// `db.`. An exact match takes precedence over prefixes, and the longest
+/// matching prefix takes precedence over shorter ones. Spans matching no key
+/// are sampled at the default ratio.
+///
+/// Like [`Sampler::TraceIdRatioBased`], the decision is derived from the
+/// trace id, so spans of the same operation in a trace get the same
+/// decision.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::OperationRatioSampler;
+/// use std::collections::HashMap;
+///
+/// let sampler = OperationRatioSampler::new(
+///     HashMap::from([
+///         ("list".to_string(), 0.01),
+///         ("checkout".to_string(), 0.5),
+///         ("payment.*".to_string(), 1.0),
+///     ]),
+///     0.1,
+/// );
+/// ```
+///
+/// [`Sampler::TraceIdRatioBased`]: crate::trace::Sampler::TraceIdRatioBased
+#[derive(Clone, Debug)]
+pub struct OperationRatioSampler {
+    exact: HashMap<String, f64>,
+    // longest first, so the first matching prefix is the longest one
+    prefixes: Vec<(String, f64)>,
+    default_ratio: f64,
+}
+
+impl OperationRatioSampler {
+    /// Create a sampler sampling the spans whose name matches a key of
+    /// `ratios` at the associated ratio, and the others at `default_ratio`.
+    pub fn new(ratios: HashMap<String, f64>, default_ratio: f64) -> Self {
+        let mut exact = HashMap::new();
+        let mut prefixes = Vec::new();
+        for (operation, ratio) in ratios {
+            match operation.strip_suffix('*') {
+                Some(prefix) => prefixes.push((prefix.to_string(), ratio)),
+                None => {
+                    exact.insert(operation, ratio);
+                }
+            }
+        }
+        prefixes.sort_by_key(|(prefix, _)| Reverse(prefix.len()));
+        OperationRatioSampler {
+            exact,
+            prefixes,
+            default_ratio,
+        }
+    }
+
+    /// The ratio spans named `name` are sampled at.
+    fn ratio(&self, name: &str) -> f64 {
+        if let Some(ratio) = self.exact.get(name) {
+            return *ratio;
+        }
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix.as_str()))
+            .map_or(self.default_ratio, |(_, ratio)| *ratio)
+    }
+}
+
+impl ShouldSample for OperationRatioSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        SamplingResult {
+            decision: sample_based_on_probability(&self.ratio(name), trace_id),
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        _parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> Option<f64> {
+        ratio_probability(self.ratio(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OperationRatioSampler;
+    use crate::trace::ShouldSample;
+    use opentelemetry::trace::{SamplingDecision, SpanKind, TraceId};
+    use std::collections::HashMap;
+
+    fn sampler() -> OperationRatioSampler {
+        OperationRatioSampler::new(
+            HashMap::from([
+                ("checkout".to_string(), 1.0),
+                ("db.*".to_string(), 1.0),
+                ("db.query.*".to_string(), 0.0),
+                ("db.query.slow".to_string(), 1.0),
+            ]),
+            0.0,
+        )
+    }
+
+    fn decision(sampler: &OperationRatioSampler, name: &str) -> SamplingDecision {
+        sampler
+            .should_sample(
+                None,
+                TraceId::from_u128(1),
+                name,
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn exact_match() {
+        let sampler = sampler();
+        assert_eq!(
+            decision(&sampler, "checkout"),
+            SamplingDecision::RecordAndSample
+        );
+        // exact keys aren't prefixes
+        assert_eq!(decision(&sampler, "checkout.step"), SamplingDecision::Drop);
+        // and take precedence over them
+        assert_eq!(
+            decision(&sampler, "db.query.slow"),
+            SamplingDecision::RecordAndSample
+        );
+    }
+
+    #[test]
+    fn longest_prefix_match() {
+        let sampler = sampler();
+        assert_eq!(
+            decision(&sampler, "db.connect"),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(decision(&sampler, "db.query.fast"), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn falls_through_to_default_ratio() {
+        let sampler = sampler();
+        assert_eq!(decision(&sampler, "list"), SamplingDecision::Drop);
+
+        let sampler = OperationRatioSampler::new(HashMap::new(), 1.0);
+        assert_eq!(
+            decision(&sampler, "list"),
+            SamplingDecision::RecordAndSample
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use crate::trace::{BuilderError, Sampler, ShouldSample};
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceContextExt, TraceId};
+use opentelemetry::{Context, KeyValue};
+
+/// Sampler deferring to a different sampler depending on whether the parent
+/// span is remote or local, and sampled or not, as described by the full
+/// `ParentBased` configuration of the [specification].
+///
+/// Unlike [`Sampler::ParentBased`], which always follows the decision of the
+/// parent, each of the four kinds of parent gets its own delegate. By default
+/// sampled parents are followed, with [`Sampler::AlwaysOn`], and unsampled
+/// ones too, with [`Sampler::AlwaysOff`], which makes this sampler behave like
+/// [`Sampler::ParentBased`] until delegates are set. Spans without a parent
+/// are sampled by the root sampler.
+///
+/// A parent is remote if its span context was propagated from another
+/// process, see [`SpanContext::is_remote`].
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{ParentBasedSampler, Sampler};
+///
+/// // keep following local parents, but resample traces coming from other
+/// // services at 10%, whatever they decided
+/// let sampler = ParentBasedSampler::new(Sampler::TraceIdRatioBased(0.1))
+///     .with_remote_parent_sampled(Sampler::TraceIdRatioBased(0.1))
+///     .with_remote_parent_not_sampled(Sampler::TraceIdRatioBased(0.1));
+/// ```
+///
+/// [specification]: https://github.com/open-telemetry/opentelemetry-specification/blob/main/specification/trace/sdk.md#parentbased
+/// [`SpanContext::is_remote`]: opentelemetry::trace::SpanContext::is_remote
+#[derive(Clone, Debug)]
+pub struct ParentBasedSampler {
+    root: Box<dyn ShouldSample>,
+    remote_parent_sampled: Box<dyn ShouldSample>,
+    remote_parent_not_sampled: Box<dyn ShouldSample>,
+    local_parent_sampled: Box<dyn ShouldSample>,
+    local_parent_not_sampled: Box<dyn ShouldSample>,
+}
+
+impl ParentBasedSampler {
+    /// Create a sampler deferring to `root` for spans without a parent, and
+    /// following the decision of the parent for the others.
+    pub fn new<S: ShouldSample + 'static>(root: S) -> Self {
+        ParentBasedSampler {
+            root: Box::new(root),
+            remote_parent_sampled: Box::new(Sampler::AlwaysOn),
+            remote_parent_not_sampled: Box::new(Sampler::AlwaysOff),
+            local_parent_sampled: Box::new(Sampler::AlwaysOn),
+            local_parent_not_sampled: Box::new(Sampler::AlwaysOff),
+        }
+    }
+
+    /// Set the sampler for spans whose parent is remote and sampled.
+    pub fn with_remote_parent_sampled<S: ShouldSample + 'static>(self, sampler: S) -> Self {
+        ParentBasedSampler {
+            remote_parent_sampled: Box::new(sampler),
+            ..self
+        }
+    }
+
+    /// Set the sampler for spans whose parent is remote and not sampled.
+    pub fn with_remote_parent_not_sampled<S: ShouldSample + 'static>(self, sampler: S) -> Self {
+        ParentBasedSampler {
+            remote_parent_not_sampled: Box::new(sampler),
+            ..self
+        }
+    }
+
+    /// Set the sampler for spans whose parent is local and sampled.
+    pub fn with_local_parent_sampled<S: ShouldSample + 'static>(self, sampler: S) -> Self {
+        ParentBasedSampler {
+            local_parent_sampled: Box::new(sampler),
+            ..self
+        }
+    }
+
+    /// Set the sampler for spans whose parent is local and not sampled.
+    pub fn with_local_parent_not_sampled<S: ShouldSample + 'static>(self, sampler: S) -> Self {
+        ParentBasedSampler {
+            local_parent_not_sampled: Box::new(sampler),
+            ..self
+        }
+    }
+
+    /// The sampler deciding for spans with the parent of `parent_context`.
+    pub(crate) fn delegate(&self, parent_context: Option<&Context>) -> &dyn ShouldSample {
+        let parent = match parent_context.filter(|cx| cx.has_active_span()) {
+            Some(cx) => cx.span(),
+            None => return self.root.as_ref(),
+        };
+        let parent_span_context = parent.span_context();
+        let delegate = match (
+            parent_span_context.is_remote(),
+            parent_span_context.is_sampled(),
+        ) {
+            (true, true) => &self.remote_parent_sampled,
+            (true, false) => &self.remote_parent_not_sampled,
+            (false, true) => &self.local_parent_sampled,
+            (false, false) => &self.local_parent_not_sampled,
+        };
+        delegate.as_ref()
+    }
+}
+
+impl ShouldSample for ParentBasedSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        self.delegate(parent_context).should_sample(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        self.delegate(parent_context).sampling_probability(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.root.validate()?;
+        self.remote_parent_sampled.validate()?;
+        self.remote_parent_not_sampled.validate()?;
+        self.local_parent_sampled.validate()?;
+        self.local_parent_not_sampled.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParentBasedSampler;
+    use crate::trace::{Sampler, ShouldSample};
+    use opentelemetry::trace::{
+        Link, SamplingDecision, SamplingResult, SpanContext, SpanId, SpanKind, TraceContextExt,
+        TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::{Context, KeyValue};
+
+    // Sampler recording its name in the attributes of its result.
+    #[derive(Clone, Debug)]
+    struct NamedSampler(&'static str);
+
+    impl ShouldSample for NamedSampler {
+        fn should_sample(
+            &self,
+            _parent_context: Option<&Context>,
+            _trace_id: TraceId,
+            _name: &str,
+            _span_kind: &SpanKind,
+            _attributes: &[KeyValue],
+            _links: &[Link],
+        ) -> SamplingResult {
+            SamplingResult {
+                decision: SamplingDecision::RecordOnly,
+                attributes: vec![KeyValue::new("delegate", self.0)],
+                trace_state: TraceState::default(),
+            }
+        }
+    }
+
+    fn parent(is_remote: bool, is_sampled: bool) -> Context {
+        let trace_flags = if is_sampled {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::default()
+        };
+        Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_u128(1),
+            SpanId::from_u64(1),
+            trace_flags,
+            is_remote,
+            TraceState::default(),
+        ))
+    }
+
+    fn delegate(sampler: &ParentBasedSampler, parent_context: Option<&Context>) -> KeyValue {
+        sampler
+            .should_sample(
+                parent_context,
+                TraceId::from_u128(1),
+                "test",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .attributes
+            .remove(0)
+    }
+
+    #[test]
+    fn defers_to_the_delegate_of_each_kind_of_parent() {
+        let sampler = ParentBasedSampler::new(NamedSampler("root"))
+            .with_remote_parent_sampled(NamedSampler("remote sampled"))
+            .with_remote_parent_not_sampled(NamedSampler("remote not sampled"))
+            .with_local_parent_sampled(NamedSampler("local sampled"))
+            .with_local_parent_not_sampled(NamedSampler("local not sampled"));
+
+        let cases = [
+            (None, "root"),
+            (Some(parent(true, true)), "remote sampled"),
+            (Some(parent(true, false)), "remote not sampled"),
+            (Some(parent(false, true)), "local sampled"),
+            (Some(parent(false, false)), "local not sampled"),
+        ];
+        for (parent_context, expected) in cases {
+            assert_eq!(
+                delegate(&sampler, parent_context.as_ref()),
+                KeyValue::new("delegate", expected)
+            );
+        }
+    }
+
+    #[test]
+    fn follows_the_parent_by_default() {
+        let sampler = ParentBasedSampler::new(Sampler::AlwaysOff);
+
+        for (is_remote, is_sampled) in [(true, true), (true, false), (false, true), (false, false)]
+        {
+            let decision = sampler
+                .should_sample(
+                    Some(&parent(is_remote, is_sampled)),
+                    TraceId::from_u128(1),
+                    "test",
+                    &SpanKind::Internal,
+                    &[],
+                    &[],
+                )
+                .decision;
+            let expected = if is_sampled {
+                SamplingDecision::RecordAndSample
+            } else {
+                SamplingDecision::Drop
+            };
+            assert_eq!(decision, expected);
+        }
+    }
+}
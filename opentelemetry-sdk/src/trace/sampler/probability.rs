@@ -0,0 +1,245 @@
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+
+/// Attribute holding the probability a span was sampled at.
+const SAMPLING_PROBABILITY: &str = "sampling.probability";
+
+/// Sampler recording the probability spans are sampled at in their
+/// `sampling.probability` attribute, so that backends can count each span as
+/// `1 / probability` spans when computing rates.
+///
+/// The attribute is added to the spans the wrapped sampler samples with a
+/// ratio, as reported by [`ShouldSample::sampling_probability`], e.g. by a
+/// [`Sampler::TraceIdRatioBased`], possibly as the delegate of a
+/// [`Sampler::ParentBased`] or [`ParentBasedSampler`], in which case it
+/// holds the ratio of the delegate actually used. Spans following the
+/// decision of their parent, or sampled without a ratio, e.g. by
+/// [`Sampler::AlwaysOn`], don't get the attribute.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{ProbabilityRecordingSampler, Sampler};
+///
+/// let sampler = ProbabilityRecordingSampler::new(Sampler::ParentBased(Box::new(
+///     Sampler::TraceIdRatioBased(0.25),
+/// )));
+/// ```
+///
+/// [`Sampler::TraceIdRatioBased`]: crate::trace::Sampler::TraceIdRatioBased
+/// [`Sampler::ParentBased`]: crate::trace::Sampler::ParentBased
+/// [`Sampler::AlwaysOn`]: crate::trace::Sampler::AlwaysOn
+/// [`ParentBasedSampler`]: crate::trace::ParentBasedSampler
+#[derive(Clone, Debug)]
+pub struct ProbabilityRecordingSampler {
+    inner: Box<dyn ShouldSample>,
+}
+
+impl ProbabilityRecordingSampler {
+    /// Create a sampler deciding like `inner`, and recording the probability
+    /// it samples spans at.
+    pub fn new<S: ShouldSample + 'static>(inner: S) -> Self {
+        ProbabilityRecordingSampler {
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl ShouldSample for ProbabilityRecordingSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let mut result =
+            self.inner
+                .should_sample(parent_context, trace_id, name, span_kind, attributes, links);
+        if result.decision == SamplingDecision::RecordAndSample {
+            if let Some(probability) = self.inner.sampling_probability(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            ) {
+                result
+                    .attributes
+                    .push(KeyValue::new(SAMPLING_PROBABILITY, probability));
+            }
+        }
+        result
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        self.inner.sampling_probability(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.inner.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProbabilityRecordingSampler;
+    use crate::trace::{
+        CachingSampler, CompositeSampler, Config, OperationRatioSampler, ParentBasedSampler,
+        Sampler, ShouldSample, TracerProvider,
+    };
+    use opentelemetry::trace::{
+        SamplingDecision, SamplingResult, Span, SpanContext, SpanId, SpanKind, TraceContextExt,
+        TraceFlags, TraceId, TraceState, Tracer, TracerProvider as _,
+    };
+    use opentelemetry::{Context, KeyValue};
+
+    fn sample(
+        sampler: &ProbabilityRecordingSampler,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+    ) -> SamplingResult {
+        sampler.should_sample(
+            parent_context,
+            trace_id,
+            "test",
+            &SpanKind::Internal,
+            &[],
+            &[],
+        )
+    }
+
+    fn parent(is_remote: bool) -> Context {
+        Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_u128(1),
+            SpanId::from_u64(1),
+            TraceFlags::SAMPLED,
+            is_remote,
+            TraceState::default(),
+        ))
+    }
+
+    #[test]
+    fn records_the_ratio_on_sampled_spans() {
+        let sampler = ProbabilityRecordingSampler::new(Sampler::TraceIdRatioBased(0.25));
+
+        let mut sampled = 0;
+        for i in 0..100u128 {
+            let trace_id =
+                TraceId::from_u128(i.wrapping_mul(0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835));
+            let result = sample(&sampler, None, trace_id);
+            if result.decision == SamplingDecision::RecordAndSample {
+                sampled += 1;
+                assert_eq!(
+                    result.attributes,
+                    vec![KeyValue::new("sampling.probability", 0.25)]
+                );
+            } else {
+                assert!(result.attributes.is_empty());
+            }
+        }
+        assert!(sampled > 0);
+    }
+
+    #[test]
+    fn records_the_ratio_of_the_delegate_used() {
+        let parent_based = ProbabilityRecordingSampler::new(Sampler::ParentBased(Box::new(
+            Sampler::TraceIdRatioBased(1.0),
+        )));
+        assert_eq!(
+            sample(&parent_based, None, TraceId::from_u128(1)).attributes,
+            vec![KeyValue::new("sampling.probability", 1.0)]
+        );
+        // the decision of the parent is followed
+        assert!(
+            sample(&parent_based, Some(&parent(false)), TraceId::from_u128(1))
+                .attributes
+                .is_empty()
+        );
+
+        let resampling = ProbabilityRecordingSampler::new(
+            ParentBasedSampler::new(Sampler::AlwaysOn)
+                .with_remote_parent_sampled(Sampler::TraceIdRatioBased(1.0)),
+        );
+        assert!(sample(&resampling, None, TraceId::from_u128(1))
+            .attributes
+            .is_empty());
+        assert_eq!(
+            sample(&resampling, Some(&parent(true)), TraceId::from_u128(1)).attributes,
+            vec![KeyValue::new("sampling.probability", 1.0)]
+        );
+    }
+
+    #[test]
+    fn records_the_ratio_through_wrapping_samplers() {
+        let probability = |sampler: ProbabilityRecordingSampler| {
+            sample(&sampler, None, TraceId::from_u128(1)).attributes
+        };
+
+        assert_eq!(
+            probability(ProbabilityRecordingSampler::new(CachingSampler::new(
+                Sampler::TraceIdRatioBased(0.5)
+            ))),
+            vec![KeyValue::new("sampling.probability", 0.5)]
+        );
+        assert_eq!(
+            probability(ProbabilityRecordingSampler::new(
+                OperationRatioSampler::new([("test".to_string(), 0.5)].into(), 0.0,)
+            )),
+            vec![KeyValue::new("sampling.probability", 0.5)]
+        );
+        assert_eq!(
+            probability(ProbabilityRecordingSampler::new(
+                CompositeSampler::all()
+                    .with_sampler(Sampler::AlwaysOn)
+                    .with_sampler(Sampler::TraceIdRatioBased(0.5))
+                    .with_sampler(Sampler::TraceIdRatioBased(0.25)),
+            )),
+            vec![KeyValue::new("sampling.probability", 0.25)]
+        );
+        // sampled by `AlwaysOn` regardless of the ratio
+        assert!(probability(ProbabilityRecordingSampler::new(
+            CompositeSampler::any()
+                .with_sampler(Sampler::AlwaysOn)
+                .with_sampler(Sampler::TraceIdRatioBased(0.5)),
+        ))
+        .is_empty());
+    }
+
+    #[test]
+    fn attribute_flows_onto_spans() {
+        let provider = TracerProvider::builder()
+            .with_config(
+                Config::default().with_sampler(ProbabilityRecordingSampler::new(
+                    Sampler::TraceIdRatioBased(1.0),
+                )),
+            )
+            .build();
+        let span = provider.tracer("test").start("test");
+
+        let data = span.exported_data().unwrap();
+        assert!(data
+            .attributes
+            .contains(&KeyValue::new("sampling.probability", 1.0)));
+        assert!(span.is_recording());
+    }
+}
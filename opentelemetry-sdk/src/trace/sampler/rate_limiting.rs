@@ -0,0 +1,253 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::{Clock, ShouldSample, SystemClock};
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Sampler that samples at most a fixed number of spans per second.
+///
+/// Decisions are taken from a token bucket refilled continuously at
+/// `max_per_second` tokens per second, holding at most one second worth of
+/// tokens, or a single token for rates below one span per second. Each
+/// sampled span takes a token, and spans are dropped while the bucket is
+/// empty. The bucket starts full, so a burst of up to `max_per_second` spans
+/// can be sampled at once, after which throughput is capped at
+/// `max_per_second`.
+///
+/// The bucket is shared by all clones of the sampler, and therefore by all
+/// tracers of a provider configured with it.
+///
+/// The sampler makes a decision for every span it is asked about. Wrap it in
+/// [`Sampler::ParentBased`] to only rate limit root spans, child spans then
+/// follow the decision of their parent:
+///
+/// ```
+/// use opentelemetry_sdk::trace::{RateLimitingSampler, Sampler};
+///
+/// let sampler = Sampler::ParentBased(Box::new(RateLimitingSampler::new(100.0)));
+/// ```
+///
+/// [`Sampler::ParentBased`]: crate::trace::Sampler::ParentBased
+#[derive(Clone, Debug)]
+pub struct RateLimitingSampler {
+    max_per_second: f64,
+    bucket: Arc<Mutex<TokenBucket>>,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: SystemTime,
+}
+
+impl TokenBucket {
+    fn full(max_per_second: f64, now: SystemTime) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(TokenBucket {
+            tokens: Self::capacity(max_per_second),
+            last_refill: now,
+        }))
+    }
+
+    /// One second worth of tokens, but at least the one a span takes so
+    /// that rates below one per second still sample.
+    fn capacity(max_per_second: f64) -> f64 {
+        if max_per_second > 0.0 {
+            max_per_second.max(1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl RateLimitingSampler {
+    /// Create a sampler sampling at most `max_per_second` spans per second,
+    /// using the system clock.
+    ///
+    /// Negative rates are treated as `0`, which drops every span.
+    pub fn new(max_per_second: f64) -> Self {
+        let max_per_second = max_per_second.max(0.0);
+        RateLimitingSampler {
+            max_per_second,
+            bucket: TokenBucket::full(max_per_second, SystemClock.now()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read the current time from `clock` instead of the system clock.
+    ///
+    /// The returned sampler starts with a full bucket of its own, not shared
+    /// with the clones of `self`.
+    pub fn with_clock<C: Clock + 'static>(self, clock: C) -> Self {
+        RateLimitingSampler {
+            bucket: TokenBucket::full(self.max_per_second, clock.now()),
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    fn try_acquire(&self) -> bool {
+        let Ok(mut bucket) = self.bucket.lock() else {
+            return false;
+        };
+        let now = self.clock.now();
+        // a clock going backwards refills nothing
+        let elapsed = now
+            .duration_since(bucket.last_refill)
+            .unwrap_or_default()
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.max_per_second)
+            .min(TokenBucket::capacity(self.max_per_second));
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl ShouldSample for RateLimitingSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        SamplingResult {
+            decision: if self.try_acquire() {
+                SamplingDecision::RecordAndSample
+            } else {
+                SamplingDecision::Drop
+            },
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::RateLimitingSampler;
+    use crate::testing::trace::TestSpan;
+    use crate::trace::{Clock, Sampler, ShouldSample};
+    use opentelemetry::trace::{
+        SamplingDecision, SpanContext, SpanId, SpanKind, TraceContextExt, TraceFlags, TraceId,
+        TraceState,
+    };
+    use opentelemetry::Context;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// Clock only moving forward when advanced by the test.
+    #[derive(Clone, Debug)]
+    struct ManualClock(Arc<Mutex<SystemTime>>);
+
+    impl ManualClock {
+        fn new() -> Self {
+            ManualClock(Arc::new(Mutex::new(
+                UNIX_EPOCH + Duration::from_secs(1_704_067_200),
+            )))
+        }
+
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn is_sampled(sampler: &dyn ShouldSample, parent: Option<&Context>) -> bool {
+        sampler
+            .should_sample(
+                parent,
+                TraceId::from(1),
+                "test",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+            == SamplingDecision::RecordAndSample
+    }
+
+    #[test]
+    fn caps_spans_per_second() {
+        let clock = ManualClock::new();
+        let sampler = RateLimitingSampler::new(100.0).with_clock(clock.clone());
+
+        // the bucket starts full
+        let burst = (0..1000).filter(|_| is_sampled(&sampler, None)).count();
+        assert_eq!(burst, 100);
+        assert!(!is_sampled(&sampler, None));
+
+        // refilled continuously, 10ms is worth one span
+        for _ in 0..100 {
+            clock.advance(Duration::from_millis(10));
+            assert!(is_sampled(&sampler, None));
+            assert!(!is_sampled(&sampler, None));
+        }
+
+        // at most one second worth of tokens is kept
+        clock.advance(Duration::from_secs(10));
+        let burst = (0..1000).filter(|_| is_sampled(&sampler, None)).count();
+        assert_eq!(burst, 100);
+    }
+
+    #[test]
+    fn samples_below_one_per_second() {
+        let clock = ManualClock::new();
+        let sampler = RateLimitingSampler::new(0.5).with_clock(clock.clone());
+
+        assert!(is_sampled(&sampler, None));
+        assert!(!is_sampled(&sampler, None));
+
+        // a token every two seconds
+        for _ in 0..10 {
+            clock.advance(Duration::from_secs(1));
+            assert!(!is_sampled(&sampler, None));
+            clock.advance(Duration::from_secs(1));
+            assert!(is_sampled(&sampler, None));
+        }
+
+        // a single token is kept
+        clock.advance(Duration::from_secs(60));
+        assert!(is_sampled(&sampler, None));
+        assert!(!is_sampled(&sampler, None));
+    }
+
+    #[test]
+    fn shared_between_clones() {
+        let sampler = RateLimitingSampler::new(2.0);
+        let clone = sampler.clone();
+        assert!(is_sampled(&sampler, None));
+        assert!(is_sampled(&clone, None));
+        assert!(!is_sampled(&sampler, None));
+        assert!(!is_sampled(&clone, None));
+    }
+
+    #[test]
+    fn children_follow_parent_under_parent_based() {
+        let sampler = Sampler::ParentBased(Box::new(RateLimitingSampler::new(0.0)));
+        let parent = Context::new().with_span(TestSpan(SpanContext::new(
+            TraceId::from(1),
+            SpanId::from(1),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        )));
+
+        assert!(!is_sampled(&sampler, None));
+        assert!(is_sampled(&sampler, Some(&parent)));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::trace::sampler::parent_trace_state;
+use crate::trace::ShouldSample;
+use opentelemetry::trace::{Link, SamplingDecision, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+
+/// Sampler recording spans without sampling them, e.g. for debugging.
+///
+/// Spans are recording, so they reach the span processors and in-process
+/// tooling such as span end callbacks, but the built-in processors don't
+/// export them.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{Config, RecordOnlySampler};
+///
+/// let config = Config::default().with_sampler(RecordOnlySampler);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecordOnlySampler;
+
+impl ShouldSample for RecordOnlySampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        SamplingResult {
+            decision: SamplingDecision::RecordOnly,
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::trace::sampler::{parent_trace_state, ratio_probability, sample_based_on_probability};
+use crate::trace::{Clock, ShouldSample, SystemClock};
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Time of day windows and the sampling ratio to use in each of them.
+///
+/// Windows are expressed as offsets from midnight. A window whose end is
+/// before its start wraps around midnight, e.g. `22:00` to `06:00`. When
+/// windows overlap, the one added first wins. Outside of every window the
+/// default ratio is used.
+///
+/// Times of day are computed in UTC. Use [`with_utc_offset`] to express the
+/// windows in another timezone, e.g. `with_utc_offset(2 * 3600)` for UTC+2.
+/// The offset is fixed: daylight saving time changes are not applied.
+///
+/// [`with_utc_offset`]: SamplingSchedule::with_utc_offset
+#[derive(Clone, Debug)]
+pub struct SamplingSchedule {
+    windows: Vec<(Duration, Duration, f64)>,
+    default_ratio: f64,
+    utc_offset_secs: i64,
+}
+
+impl SamplingSchedule {
+    /// Create a schedule using `default_ratio` outside of any window.
+    pub fn new(default_ratio: f64) -> Self {
+        SamplingSchedule {
+            windows: Vec::new(),
+            default_ratio,
+            utc_offset_secs: 0,
+        }
+    }
+
+    /// Sample with `ratio` between `start` and `end`, both offsets from
+    /// midnight. `start` is inclusive and `end` exclusive.
+    pub fn with_window(mut self, start: Duration, end: Duration, ratio: f64) -> Self {
+        self.windows.push((start, end, ratio));
+        self
+    }
+
+    /// Offset in seconds from UTC of the timezone the windows are expressed
+    /// in. Defaults to `0`.
+    pub fn with_utc_offset(mut self, offset_secs: i32) -> Self {
+        self.utc_offset_secs = offset_secs.into();
+        self
+    }
+
+    fn ratio_at(&self, time_of_day: Duration) -> f64 {
+        self.windows
+            .iter()
+            .find(|(start, end, _)| {
+                if start <= end {
+                    *start <= time_of_day && time_of_day < *end
+                } else {
+                    *start <= time_of_day || time_of_day < *end
+                }
+            })
+            .map_or(self.default_ratio, |(_, _, ratio)| *ratio)
+    }
+}
+
+/// Sampler that samples a ratio of traces depending on the time of day.
+///
+/// The ratio in effect is looked up in a [`SamplingSchedule`] using the time
+/// given by a [`Clock`], and is then applied like
+/// [`Sampler::TraceIdRatioBased`]. This allows e.g. heavier sampling during
+/// business hours and lighter sampling overnight.
+///
+/// The sampler makes a decision for every span it is asked about. Wrap it in
+/// [`Sampler::ParentBased`] to only apply it to root spans:
+///
+/// ```
+/// use opentelemetry_sdk::trace::{Sampler, SamplingSchedule, ScheduledRatioSampler};
+/// use std::time::Duration;
+///
+/// let hour = Duration::from_secs(60 * 60);
+/// let schedule = SamplingSchedule::new(0.01)
+///     // 09:00 to 18:00 in UTC+1
+///     .with_window(9 * hour, 18 * hour, 0.5)
+///     .with_utc_offset(60 * 60);
+///
+/// let sampler = Sampler::ParentBased(Box::new(ScheduledRatioSampler::new(schedule)));
+/// ```
+///
+/// [`Sampler::TraceIdRatioBased`]: crate::trace::Sampler::TraceIdRatioBased
+/// [`Sampler::ParentBased`]: crate::trace::Sampler::ParentBased
+#[derive(Clone, Debug)]
+pub struct ScheduledRatioSampler {
+    schedule: SamplingSchedule,
+    clock: Arc<dyn Clock>,
+}
+
+impl ScheduledRatioSampler {
+    /// Create a sampler following `schedule`, using the system clock.
+    pub fn new(schedule: SamplingSchedule) -> Self {
+        ScheduledRatioSampler {
+            schedule,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read the current time from `clock` instead of the system clock.
+    pub fn with_clock<C: Clock + 'static>(self, clock: C) -> Self {
+        ScheduledRatioSampler {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// The ratio in effect at the current time.
+    pub fn current_ratio(&self) -> f64 {
+        let secs_since_epoch = match self.clock.now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(before_epoch) => -(before_epoch.duration().as_secs() as i64),
+        };
+        let time_of_day =
+            (secs_since_epoch + self.schedule.utc_offset_secs).rem_euclid(SECONDS_PER_DAY);
+        self.schedule
+            .ratio_at(Duration::from_secs(time_of_day as u64))
+    }
+}
+
+impl ShouldSample for ScheduledRatioSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        SamplingResult {
+            decision: sample_based_on_probability(&self.current_ratio(), trace_id),
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        _parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> Option<f64> {
+        ratio_probability(self.current_ratio())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SamplingSchedule, ScheduledRatioSampler};
+    use crate::trace::{Clock, ShouldSample};
+    use opentelemetry::trace::{SamplingDecision, SpanKind, TraceId};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[derive(Clone, Debug)]
+    struct FixedClock(SystemTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+    }
+
+    // 2024-01-01 was a Monday, midnight UTC
+    fn at(time_of_day: Duration) -> FixedClock {
+        FixedClock(UNIX_EPOCH + Duration::from_secs(1_704_067_200) + time_of_day)
+    }
+
+    fn schedule() -> SamplingSchedule {
+        SamplingSchedule::new(0.0)
+            .with_window(9 * HOUR, 18 * HOUR, 1.0)
+            .with_window(22 * HOUR, 6 * HOUR, 0.1)
+    }
+
+    #[test]
+    fn picks_ratio_of_current_window() {
+        let cases = [
+            (10 * HOUR, 1.0),
+            (9 * HOUR, 1.0),
+            (18 * HOUR, 0.0),
+            (23 * HOUR, 0.1),
+            (2 * HOUR, 0.1),
+            (7 * HOUR, 0.0),
+        ];
+        for (time_of_day, expected) in cases {
+            let sampler = ScheduledRatioSampler::new(schedule()).with_clock(at(time_of_day));
+            assert_eq!(sampler.current_ratio(), expected, "at {time_of_day:?}");
+        }
+    }
+
+    #[test]
+    fn applies_utc_offset() {
+        // 08:00 UTC is 10:00 in UTC+2
+        let sampler = ScheduledRatioSampler::new(schedule().with_utc_offset(2 * 60 * 60))
+            .with_clock(at(8 * HOUR));
+        assert_eq!(sampler.current_ratio(), 1.0);
+
+        // 01:00 UTC is 20:00 the day before in UTC-5
+        let sampler = ScheduledRatioSampler::new(schedule().with_utc_offset(-5 * 60 * 60))
+            .with_clock(at(HOUR));
+        assert_eq!(sampler.current_ratio(), 0.0);
+    }
+
+    #[test]
+    fn samples_with_current_ratio() {
+        let decision = |time_of_day| {
+            ScheduledRatioSampler::new(schedule())
+                .with_clock(at(time_of_day))
+                .should_sample(
+                    None,
+                    TraceId::from(1),
+                    "test",
+                    &SpanKind::Internal,
+                    &[],
+                    &[],
+                )
+                .decision
+        };
+        assert_eq!(decision(12 * HOUR), SamplingDecision::RecordAndSample);
+        assert_eq!(decision(20 * HOUR), SamplingDecision::Drop);
+    }
+}
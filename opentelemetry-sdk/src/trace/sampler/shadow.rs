@@ -0,0 +1,176 @@
+use crate::trace::{BuilderError, ShouldSample};
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Sampler comparing the decisions of a candidate sampler with the ones of
+/// the sampler in use, to validate a new sampling configuration against
+/// production traffic without changing what is recorded and exported.
+///
+/// Every span is sampled by both the live and the shadow sampler. The result
+/// of the live sampler is used as is, the decision of the shadow sampler is
+/// only compared with it and counted in the [`ShadowReport`] returned by
+/// [`report`].
+///
+/// The counts are shared by all clones of the sampler, so keep a clone to
+/// read the report of the sampler given to the provider.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{Config, Sampler, ShadowSampler, TracerProvider};
+///
+/// let sampler = ShadowSampler::new(Sampler::AlwaysOn, Sampler::TraceIdRatioBased(0.1));
+/// let provider = TracerProvider::builder()
+///     .with_config(Config::default().with_sampler(sampler.clone()))
+///     .build();
+///
+/// // later on
+/// let report = sampler.report();
+/// println!("{} decisions would change", report.disagreements);
+/// ```
+///
+/// [`report`]: ShadowSampler::report
+#[derive(Clone, Debug)]
+pub struct ShadowSampler {
+    live: Box<dyn ShouldSample>,
+    shadow: Box<dyn ShouldSample>,
+    counts: Arc<ShadowCounts>,
+}
+
+#[derive(Debug, Default)]
+struct ShadowCounts {
+    agreements: AtomicU64,
+    disagreements: AtomicU64,
+}
+
+/// Number of spans for which the shadow sampler of a [`ShadowSampler`]
+/// agreed or disagreed with the live one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShadowReport {
+    /// Spans for which both samplers made the same decision.
+    pub agreements: u64,
+    /// Spans for which the samplers made different decisions.
+    pub disagreements: u64,
+}
+
+impl ShadowSampler {
+    /// Create a sampler using the decisions of `live`, and comparing them
+    /// with the ones of `shadow`.
+    pub fn new<L, S>(live: L, shadow: S) -> Self
+    where
+        L: ShouldSample + 'static,
+        S: ShouldSample + 'static,
+    {
+        ShadowSampler {
+            live: Box::new(live),
+            shadow: Box::new(shadow),
+            counts: Arc::default(),
+        }
+    }
+
+    /// Returns the number of decisions the samplers agreed and disagreed on
+    /// so far.
+    pub fn report(&self) -> ShadowReport {
+        ShadowReport {
+            agreements: self.counts.agreements.load(Ordering::Relaxed),
+            disagreements: self.counts.disagreements.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl ShouldSample for ShadowSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let result =
+            self.live
+                .should_sample(parent_context, trace_id, name, span_kind, attributes, links);
+        let shadow =
+            self.shadow
+                .should_sample(parent_context, trace_id, name, span_kind, attributes, links);
+        let counter = if shadow.decision == result.decision {
+            &self.counts.agreements
+        } else {
+            &self.counts.disagreements
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    fn sampling_probability(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> Option<f64> {
+        self.live
+            .sampling_probability(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+
+    fn validate(&self) -> Result<(), BuilderError> {
+        self.live.validate()?;
+        self.shadow.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ShadowReport, ShadowSampler};
+    use crate::trace::{Sampler, ShouldSample};
+    use opentelemetry::trace::{SamplingDecision, SpanKind, TraceId};
+
+    fn decision(sampler: &ShadowSampler) -> SamplingDecision {
+        sampler
+            .should_sample(
+                None,
+                TraceId::from_u128(1),
+                "test",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn counts_disagreements_without_changing_decisions() {
+        let sampler = ShadowSampler::new(Sampler::AlwaysOn, Sampler::AlwaysOff);
+        let clone = sampler.clone();
+
+        for spans in 1..=3 {
+            assert_eq!(decision(&clone), SamplingDecision::RecordAndSample);
+            assert_eq!(
+                sampler.report(),
+                ShadowReport {
+                    agreements: 0,
+                    disagreements: spans,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn counts_agreements() {
+        let sampler = ShadowSampler::new(Sampler::AlwaysOff, Sampler::AlwaysOff);
+
+        assert_eq!(decision(&sampler), SamplingDecision::Drop);
+        assert_eq!(
+            sampler.report(),
+            ShadowReport {
+                agreements: 1,
+                disagreements: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use crate::runtime::RuntimeChannel;
+use crate::trace::sampler::{parent_trace_state, ratio_probability, sample_based_on_probability};
+use crate::trace::ShouldSample;
+use futures_util::StreamExt as _;
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceError, TraceId};
+use opentelemetry::{global, Context, KeyValue};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// Sampler that samples a ratio of traces read from a file, picking up
+/// changes to the file without restarting the application.
+///
+/// The file must contain a single floating point number, the fraction of
+/// traces to sample, e.g. `0.25`. Surrounding whitespace is ignored. The
+/// ratio is applied exactly like [`Sampler::TraceIdRatioBased`]: values
+/// `>= 1.0` sample every trace and values `<= 0.0` sample none.
+///
+/// The file is read once when the sampler is created and then re-read every
+/// `poll_interval` on a task spawned with the given runtime, so an update is
+/// picked up at most `poll_interval` after the file is written. The ratio is
+/// swapped atomically and never blocks sampling decisions. The polling task
+/// stops once every clone of the sampler has been dropped.
+///
+/// If the file cannot be read or does not contain a valid number, the
+/// previously effective ratio is kept and the error is reported through
+/// [`global::handle_error`]. If that happens on creation, no traces are
+/// sampled until a valid ratio is read.
+///
+/// The sampler makes a decision for every span it is asked about. Wrap it in
+/// [`Sampler::ParentBased`] to only apply it to root spans:
+///
+/// ```no_run
+/// # #[cfg(feature = "rt-tokio")]
+/// # {
+/// use opentelemetry_sdk::runtime;
+/// use opentelemetry_sdk::trace::{Sampler, WatchedFileSampler};
+/// use std::time::Duration;
+///
+/// let sampler = Sampler::ParentBased(Box::new(WatchedFileSampler::new(
+///     "/etc/my-service/sampling-ratio",
+///     Duration::from_secs(30),
+///     runtime::Tokio,
+/// )));
+/// # }
+/// ```
+///
+/// [`Sampler::TraceIdRatioBased`]: crate::trace::Sampler::TraceIdRatioBased
+/// [`Sampler::ParentBased`]: crate::trace::Sampler::ParentBased
+#[derive(Clone, Debug)]
+pub struct WatchedFileSampler {
+    // f64 ratio stored as its bit representation
+    ratio: Arc<AtomicU64>,
+}
+
+impl WatchedFileSampler {
+    /// Create a sampler reading its ratio from `path` and checking it for
+    /// changes every `poll_interval`.
+    pub fn new<P, R>(path: P, poll_interval: Duration, runtime: R) -> Self
+    where
+        P: Into<PathBuf>,
+        R: RuntimeChannel,
+    {
+        let path = path.into();
+        let initial = read_ratio(&path).unwrap_or_else(|err| {
+            global::handle_error(err);
+            0.0
+        });
+        let sampler = WatchedFileSampler {
+            ratio: Arc::new(AtomicU64::new(initial.to_bits())),
+        };
+        Self::run_update_task(runtime, Arc::downgrade(&sampler.ratio), path, poll_interval);
+        sampler
+    }
+
+    /// The ratio currently used to make sampling decisions.
+    pub fn ratio(&self) -> f64 {
+        f64::from_bits(self.ratio.load(Ordering::Relaxed))
+    }
+
+    fn run_update_task<R: RuntimeChannel>(
+        runtime: R,
+        ratio: Weak<AtomicU64>,
+        path: PathBuf,
+        poll_interval: Duration,
+    ) {
+        let inner_runtime = runtime.clone();
+        runtime.spawn(Box::pin(async move {
+            // skip the first tick, the file has been read on creation
+            let mut interval = Box::pin(inner_runtime.interval(poll_interval).skip(1));
+            while interval.next().await.is_some() {
+                let Some(ratio) = ratio.upgrade() else {
+                    // every sampler has been dropped
+                    break;
+                };
+                match read_ratio(&path) {
+                    Ok(new_ratio) => ratio.store(new_ratio.to_bits(), Ordering::Relaxed),
+                    Err(err) => global::handle_error(err),
+                }
+            }
+        }));
+    }
+}
+
+fn read_ratio(path: &Path) -> Result<f64, TraceError> {
+    let content = std::fs::read_to_string(path).map_err(|err| {
+        TraceError::Other(
+            format!(
+                "cannot read sampling ratio from {}: {}",
+                path.display(),
+                err
+            )
+            .into(),
+        )
+    })?;
+    content
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|ratio| !ratio.is_nan())
+        .ok_or_else(|| {
+            TraceError::Other(
+                format!(
+                    "invalid sampling ratio {:?} in {}",
+                    content.trim(),
+                    path.display()
+                )
+                .into(),
+            )
+        })
+}
+
+impl ShouldSample for WatchedFileSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        SamplingResult {
+            decision: sample_based_on_probability(&self.ratio(), trace_id),
+            attributes: Vec::new(),
+            trace_state: parent_trace_state(parent_context),
+        }
+    }
+
+    fn sampling_probability(
+        &self,
+        _parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> Option<f64> {
+        ratio_probability(self.ratio())
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::WatchedFileSampler;
+    use crate::runtime;
+    use crate::trace::ShouldSample;
+    use opentelemetry::trace::{SamplingDecision, SpanKind, TraceId};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn ratio_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "otel-watched-file-sampler-{}-{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn decision(sampler: &WatchedFileSampler) -> SamplingDecision {
+        sampler
+            .should_sample(
+                None,
+                TraceId::from(u128::MAX >> 1),
+                "test",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[tokio::test]
+    async fn picks_up_file_changes() {
+        let path = ratio_file("changes", "1.0\n");
+        let sampler = WatchedFileSampler::new(&path, Duration::from_millis(10), runtime::Tokio);
+        assert_eq!(sampler.ratio(), 1.0);
+        assert_eq!(decision(&sampler), SamplingDecision::RecordAndSample);
+
+        std::fs::write(&path, "0").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(sampler.ratio(), 0.0);
+        assert_eq!(decision(&sampler), SamplingDecision::Drop);
+
+        // malformed content keeps the previous ratio
+        std::fs::write(&path, "not a number").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(sampler.ratio(), 0.0);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn malformed_file_on_creation_samples_nothing() {
+        let path = ratio_file("malformed", "NaN");
+        let sampler = WatchedFileSampler::new(&path, Duration::from_secs(60), runtime::Tokio);
+        assert_eq!(sampler.ratio(), 0.0);
+        assert_eq!(decision(&sampler), SamplingDecision::Drop);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
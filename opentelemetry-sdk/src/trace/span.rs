@@ -8,10 +8,11 @@
 //! start time is set to the current time on span creation. After the `Span` is created, it
 //! is possible to change its name, set its `Attributes`, and add `Links` and `Events`.
 //! These cannot be changed after the `Span`'s end time has been set.
-use crate::trace::SpanLimits;
+use crate::trace::{OnEndStatus, SpanLimits, SpanProcessor};
 use opentelemetry::trace::{Event, Link, SpanContext, SpanId, SpanKind, Status};
 use opentelemetry::KeyValue;
 use std::borrow::Cow;
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// Single operation within a trace.
@@ -21,6 +22,9 @@ pub struct Span {
     data: Option<SpanData>,
     tracer: crate::trace::Tracer,
     span_limits: SpanLimits,
+    suppressed: bool,
+    // processors the span started on, and ends on
+    processors: Option<Arc<Vec<Arc<dyn SpanProcessor>>>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -60,9 +64,16 @@ impl Span {
             data,
             tracer,
             span_limits: span_limit,
+            suppressed: false,
+            processors: None,
         }
     }
 
+    /// Sets the processors the span is ended on, those it was started on.
+    pub(crate) fn set_processors(&mut self, processors: Arc<Vec<Arc<dyn SpanProcessor>>>) {
+        self.processors = Some(processors);
+    }
+
     /// Operate on a mutable reference to span data
     fn with_data<T, F>(&mut self, f: F) -> Option<T>
     where
@@ -81,9 +92,50 @@ impl Span {
             .as_ref()
             .map(|data| build_export_data(data.clone(), span_context, tracer))
     }
+
+    /// Requests the span not to be recorded, for processors which can tell
+    /// from [`SpanProcessor::on_start`] that a span shouldn't be kept when
+    /// the sampler can't, e.g. because it lacks some of their context.
+    ///
+    /// Once every processor has seen the span start, a suppressed span stops
+    /// recording and loses its sampled flag, so it is not exported and is
+    /// not handed to [`SpanProcessor::on_end`]. A single processor
+    /// suppressing the span is enough, whatever the other processors do.
+    /// Suppressing the span has no effect after it started.
+    pub fn suppress(&mut self) {
+        self.suppressed = true;
+    }
+
+    /// Returns `true` if a processor suppressed the span while it started,
+    /// see [`Span::suppress`].
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
+    /// Drops the data of a suppressed span and clears its sampled flag.
+    pub(crate) fn discard(&mut self) {
+        self.data = None;
+        self.span_context = SpanContext::new(
+            self.span_context.trace_id(),
+            self.span_context.span_id(),
+            self.span_context.trace_flags().with_sampled(false),
+            self.span_context.is_remote(),
+            self.span_context.trace_state().clone(),
+        );
+    }
 }
 
 impl opentelemetry::trace::Span for Span {
+    /// Records events in the context of a given `Span`, at the current time
+    /// of the provider's [`Clock`](crate::trace::Clock).
+    fn add_event<T>(&mut self, name: T, attributes: Vec<KeyValue>)
+    where
+        T: Into<Cow<'static, str>>,
+    {
+        let timestamp = self.tracer.provider().config().clock.now();
+        self.add_event_with_timestamp(name, timestamp, attributes)
+    }
+
     /// Records events at a specific time in the context of a given `Span`.
     ///
     /// Note that the OpenTelemetry project documents certain ["standard event names and
@@ -99,11 +151,17 @@ impl opentelemetry::trace::Span for Span {
     {
         let span_events_limit = self.span_limits.max_events_per_span as usize;
         let event_attributes_limit = self.span_limits.max_attributes_per_event as usize;
-        self.with_data(|data| {
+        let provider = self.tracer.provider();
+        let truncation = provider.truncation_counter();
+        let marker = &provider.config().attribute_value_truncation_marker;
+        if let Some(data) = self.data.as_mut() {
             if data.events.len() < span_events_limit {
                 let dropped_attributes_count =
                     attributes.len().saturating_sub(event_attributes_limit);
                 attributes.truncate(event_attributes_limit);
+                self.span_limits
+                    .truncate_attribute_values(&mut attributes, marker);
+                truncation.record_event_attributes(dropped_attributes_count);
 
                 data.events.add_event(Event::new(
                     name,
@@ -113,8 +171,9 @@ impl opentelemetry::trace::Span for Span {
                 ));
             } else {
                 data.events.dropped_count += 1;
+                truncation.record_events(1);
             }
-        });
+        }
     }
 
     /// Returns the `SpanContext` for the given `Span`.
@@ -134,15 +193,21 @@ impl opentelemetry::trace::Span for Span {
     /// Note that the OpenTelemetry project documents certain ["standard
     /// attributes"](https://github.com/open-telemetry/opentelemetry-specification/tree/v0.5.0/specification/trace/semantic_conventions/README.md)
     /// that have prescribed semantic meanings.
-    fn set_attribute(&mut self, attribute: KeyValue) {
+    fn set_attribute(&mut self, mut attribute: KeyValue) {
         let span_attribute_limit = self.span_limits.max_attributes_per_span as usize;
-        self.with_data(|data| {
+        let provider = self.tracer.provider();
+        let truncation = provider.truncation_counter();
+        let marker = &provider.config().attribute_value_truncation_marker;
+        if let Some(data) = self.data.as_mut() {
             if data.attributes.len() < span_attribute_limit {
+                self.span_limits
+                    .truncate_attribute_values(std::slice::from_mut(&mut attribute), marker);
                 data.attributes.push(attribute);
             } else {
                 data.dropped_attributes_count += 1;
+                truncation.record_attributes(1);
             }
-        });
+        }
     }
 
     /// Sets the status of this `Span`.
@@ -173,12 +238,18 @@ impl opentelemetry::trace::Span for Span {
     fn add_link(&mut self, span_context: SpanContext, attributes: Vec<KeyValue>) {
         let span_links_limit = self.span_limits.max_links_per_span as usize;
         let link_attributes_limit = self.span_limits.max_attributes_per_link as usize;
-        self.with_data(|data| {
+        let provider = self.tracer.provider();
+        let truncation = provider.truncation_counter();
+        let marker = &provider.config().attribute_value_truncation_marker;
+        if let Some(data) = self.data.as_mut() {
             if data.links.links.len() < span_links_limit {
                 let dropped_attributes_count =
                     attributes.len().saturating_sub(link_attributes_limit);
                 let mut attributes = attributes;
                 attributes.truncate(link_attributes_limit);
+                self.span_limits
+                    .truncate_attribute_values(&mut attributes, marker);
+                truncation.record_link_attributes(dropped_attributes_count);
                 data.links.add_link(Link::new(
                     span_context,
                     attributes,
@@ -186,8 +257,14 @@ impl opentelemetry::trace::Span for Span {
                 ));
             } else {
                 data.links.dropped_count += 1;
+                truncation.record_links(1);
             }
-        });
+        }
+    }
+
+    /// Finishes the span at the time read from the provider's clock.
+    fn end(&mut self) {
+        self.ensure_ended_and_exported(None);
     }
 
     /// Finishes the span with given timestamp.
@@ -205,6 +282,7 @@ impl Span {
         };
 
         let provider = self.tracer.provider();
+        provider.span_counter().record_end();
         // skip if provider has been shut down
         if provider.is_shutdown() {
             return;
@@ -214,27 +292,40 @@ impl Span {
         if let Some(timestamp) = timestamp {
             data.end_time = timestamp;
         } else if data.end_time == data.start_time {
-            data.end_time = opentelemetry::time::now();
+            data.end_time = provider.config().clock.now();
         }
 
-        match provider.span_processors() {
-            [] => {}
-            [processor] => {
-                processor.on_end(build_export_data(
-                    data,
-                    self.span_context.clone(),
-                    &self.tracer,
-                ));
-            }
-            processors => {
-                for processor in processors {
-                    processor.on_end(build_export_data(
-                        data.clone(),
-                        self.span_context.clone(),
-                        &self.tracer,
-                    ));
-                }
+        provider.status_counter().record(&data.status);
+
+        let processors = self.processors.take().unwrap_or_default();
+        let on_span_end = provider.on_span_end();
+        if processors.is_empty() && on_span_end.is_none() {
+            return;
+        }
+
+        let span = build_export_data(data, self.span_context.clone(), &self.tracer);
+        if let Some(on_span_end) = on_span_end {
+            on_span_end.call(&span);
+        }
+        if let Some((last, others)) = processors.split_last() {
+            for processor in others {
+                let status = processor.try_on_end(span.clone());
+                report_drop(provider, processor.as_ref(), status);
             }
+            let status = last.try_on_end(span);
+            report_drop(provider, last.as_ref(), status);
+        }
+    }
+}
+
+fn report_drop(
+    provider: &crate::trace::TracerProvider,
+    processor: &dyn SpanProcessor,
+    status: OnEndStatus,
+) {
+    if status != OnEndStatus::Accepted {
+        if let Some(on_processor_drop) = provider.on_processor_drop() {
+            on_processor_drop.call(processor.name(), status);
         }
     }
 }
@@ -566,6 +657,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn exceed_span_attributes_limit_truncation_stats() {
+        let provider = crate::trace::TracerProvider::builder()
+            .with_simple_exporter(NoopSpanExporter::new())
+            .build();
+        let tracer = provider.tracer("opentelemetry-test");
+
+        let overflow = 3;
+        let initial_attributes = (0..DEFAULT_MAX_ATTRIBUTES_PER_SPAN + overflow)
+            .map(|i| KeyValue::new(format!("key {}", i), i.to_string()))
+            .collect::<Vec<_>>();
+        let mut span =
+            tracer.build(SpanBuilder::from_name("test_span").with_attributes(initial_attributes));
+        assert_eq!(provider.truncation_stats().attributes, overflow as u64);
+
+        span.set_attribute(KeyValue::new("key", "value"));
+        span.add_link(
+            SpanContext::empty_context(),
+            vec![KeyValue::new("key", "value"); DEFAULT_MAX_ATTRIBUTES_PER_LINK as usize + 2],
+        );
+        let stats = provider.truncation_stats();
+        assert_eq!(stats.attributes, overflow as u64 + 1);
+        assert_eq!(stats.link_attributes, 2);
+        assert_eq!(stats.events, 0);
+    }
+
+    #[test]
+    fn truncate_attribute_values() {
+        use crate::testing::trace::InMemorySpanExporterBuilder;
+        use opentelemetry::{Array, Value};
+
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = crate::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(
+                crate::trace::Config::default()
+                    .with_max_attribute_value_length(8)
+                    .with_max_attributes_per_span(3),
+            )
+            .build();
+        let tracer = provider.tracer("opentelemetry-test");
+
+        let mut span = tracer.build(
+            SpanBuilder::from_name("test_span")
+                .with_attributes(vec![KeyValue::new("builder", "x".repeat(1024))]),
+        );
+        span.set_attribute(KeyValue::new("short", "fits"));
+        span.set_attribute(KeyValue::new(
+            "array",
+            Value::Array(Array::String(vec!["é".repeat(10).into(), "a".into()])),
+        ));
+        span.set_attribute(KeyValue::new("dropped", "y".repeat(1024)));
+        span.add_event("event", vec![KeyValue::new("event", "z".repeat(1024))]);
+        span.end();
+
+        let span = exporter.get_finished_spans().unwrap().remove(0);
+        assert_eq!(
+            span.attributes,
+            vec![
+                KeyValue::new("builder", "x".repeat(8)),
+                KeyValue::new("short", "fits"),
+                KeyValue::new(
+                    "array",
+                    Value::Array(Array::String(vec!["é".repeat(8).into(), "a".into()]))
+                ),
+            ]
+        );
+        assert_eq!(span.dropped_attributes_count, 1);
+        assert_eq!(
+            span.events.events[0].attributes,
+            vec![KeyValue::new("event", "z".repeat(8))]
+        );
+    }
+
+    #[test]
+    fn truncate_attribute_values_with_marker() {
+        let marker = ".".repeat(3);
+        let provider = crate::trace::TracerProvider::builder()
+            .with_config(
+                crate::trace::Config::default()
+                    .with_max_attribute_value_length(8)
+                    .with_attribute_value_truncation_marker(marker),
+            )
+            .build();
+        let tracer = provider.tracer("opentelemetry-test");
+
+        let mut span = tracer.start("test_span");
+        span.set_attribute(KeyValue::new("long", "0123456789"));
+        span.set_attribute(KeyValue::new("exact", "01234567"));
+
+        let data = span.data.clone().unwrap();
+        assert_eq!(
+            data.attributes,
+            vec![
+                KeyValue::new("long", "01234..."),
+                KeyValue::new("exact", "01234567"),
+            ]
+        );
+    }
+
     #[test]
     fn exceed_event_attributes_limit() {
         let exporter = NoopSpanExporter::new();
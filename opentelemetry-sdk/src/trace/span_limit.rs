@@ -1,3 +1,5 @@
+use opentelemetry::{Array, KeyValue, StringValue, Value};
+
 /// # Span limit
 /// Erroneous code can add unintended attributes, events, and links to a span. If these collections
 /// are unbounded, they can quickly exhaust available memory, resulting in crashes that are
@@ -9,9 +11,11 @@
 ///  - Maximum allowed span link count
 ///  - Maximum allowed attribute per span event count
 ///  - Maximum allowed attribute per span link count
+///  - Maximum allowed attribute value length
 ///
 /// If the limit has been breached. The attributes, events or links will be dropped based on their
-/// index in the collection. The one added to collections later will be dropped first.
+/// index in the collection. The one added to collections later will be dropped first. Attribute
+/// values longer than the limit are truncated instead.
 
 pub(crate) const DEFAULT_MAX_EVENT_PER_SPAN: u32 = 128;
 pub(crate) const DEFAULT_MAX_ATTRIBUTES_PER_SPAN: u32 = 128;
@@ -20,7 +24,11 @@ pub(crate) const DEFAULT_MAX_ATTRIBUTES_PER_EVENT: u32 = 128;
 pub(crate) const DEFAULT_MAX_ATTRIBUTES_PER_LINK: u32 = 128;
 
 /// Span limit configuration to keep attributes, events and links to a span in a reasonable number.
-#[derive(Copy, Clone, Debug)]
+///
+/// Start from [`SpanLimits::default`] and change the limits with the `with_*`
+/// methods, or the fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct SpanLimits {
     /// The max events that can be added to a `Span`.
     pub max_events_per_span: u32,
@@ -32,6 +40,15 @@ pub struct SpanLimits {
     pub max_attributes_per_event: u32,
     /// The max attributes that can be added into a `Link`
     pub max_attributes_per_link: u32,
+    /// The max length, in characters, of string attribute values and of the
+    /// string elements of array values, of spans, events and links. Longer
+    /// values are truncated. `None`, the default, doesn't limit the length.
+    ///
+    /// See [`Config::with_attribute_value_truncation_marker`] to mark the
+    /// truncated values.
+    ///
+    /// [`Config::with_attribute_value_truncation_marker`]: crate::trace::Config::with_attribute_value_truncation_marker
+    pub max_attribute_value_length: Option<u32>,
 }
 
 impl Default for SpanLimits {
@@ -42,6 +59,103 @@ impl Default for SpanLimits {
             max_links_per_span: DEFAULT_MAX_LINKS_PER_SPAN,
             max_attributes_per_link: DEFAULT_MAX_ATTRIBUTES_PER_LINK,
             max_attributes_per_event: DEFAULT_MAX_ATTRIBUTES_PER_EVENT,
+            max_attribute_value_length: None,
         }
     }
 }
+
+impl SpanLimits {
+    /// Set the max events that can be added to a `Span`.
+    pub fn with_max_events_per_span(self, max_events: u32) -> Self {
+        SpanLimits {
+            max_events_per_span: max_events,
+            ..self
+        }
+    }
+
+    /// Set the max attributes that can be added to a `Span`.
+    pub fn with_max_attributes_per_span(self, max_attributes: u32) -> Self {
+        SpanLimits {
+            max_attributes_per_span: max_attributes,
+            ..self
+        }
+    }
+
+    /// Set the max links that can be added to a `Span`.
+    pub fn with_max_links_per_span(self, max_links: u32) -> Self {
+        SpanLimits {
+            max_links_per_span: max_links,
+            ..self
+        }
+    }
+
+    /// Set the max attributes that can be added into an `Event`.
+    pub fn with_max_attributes_per_event(self, max_attributes: u32) -> Self {
+        SpanLimits {
+            max_attributes_per_event: max_attributes,
+            ..self
+        }
+    }
+
+    /// Set the max attributes that can be added into a `Link`.
+    pub fn with_max_attributes_per_link(self, max_attributes: u32) -> Self {
+        SpanLimits {
+            max_attributes_per_link: max_attributes,
+            ..self
+        }
+    }
+
+    /// Set the max length, in characters, of string attribute values.
+    pub fn with_max_attribute_value_length(self, max_length: u32) -> Self {
+        SpanLimits {
+            max_attribute_value_length: Some(max_length),
+            ..self
+        }
+    }
+
+    /// Truncates the string values of `attributes` longer than
+    /// `max_attribute_value_length`, appending `marker` to them.
+    pub(crate) fn truncate_attribute_values(&self, attributes: &mut [KeyValue], marker: &str) {
+        let max_length = match self.max_attribute_value_length {
+            Some(max_length) => max_length as usize,
+            None => return,
+        };
+        for attribute in attributes {
+            match &mut attribute.value {
+                Value::String(value) => {
+                    if let Some(truncated) = truncate(value, max_length, marker) {
+                        *value = truncated;
+                    }
+                }
+                Value::Array(Array::String(values)) => {
+                    for value in values {
+                        if let Some(truncated) = truncate(value, max_length, marker) {
+                            *value = truncated;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns `value` truncated to `max_length` characters, marker included, or
+/// `None` if it already fits.
+fn truncate(value: &StringValue, max_length: usize, marker: &str) -> Option<StringValue> {
+    let value = value.as_str();
+    value.char_indices().nth(max_length)?;
+
+    let marker_length = marker.chars().count();
+    let marker = if marker_length <= max_length {
+        marker
+    } else {
+        ""
+    };
+    let keep = max_length - marker.chars().count();
+    let end = value
+        .char_indices()
+        .nth(keep)
+        .map_or(value.len(), |(index, _)| index);
+    Some(format!("{}{}", &value[..end], marker).into())
+}
@@ -34,9 +34,33 @@
 //! [`is_recording`]: opentelemetry::trace::Span::is_recording()
 //! [`TracerProvider`]: opentelemetry::trace::TracerProvider
 
+mod cohort;
+mod enriching;
+mod event_sampling;
+mod filtering;
+mod latency;
+mod multi;
+mod name_rewriting;
+mod stats;
+mod thread_local;
+
+pub use cohort::CohortSplittingProcessor;
+pub use enriching::AttributeEnrichingSpanProcessor;
+pub use event_sampling::EventSamplingSpanProcessor;
+pub use filtering::FilteringSpanProcessor;
+pub use latency::LatencySpanProcessor;
+pub use multi::MultiSpanProcessor;
+pub use name_rewriting::SpanNameRewritingProcessor;
+pub(crate) use stats::BatchStats;
+pub use stats::{
+    DroppedSpanCounts, ExportMetrics, ProcessorStats, ProcessorStatus, ProviderTelemetry,
+    QueueDepthGauge,
+};
+pub use thread_local::ThreadLocalBufferingProcessor;
+
 use crate::export::trace::{ExportResult, SpanData, SpanExporter};
 use crate::resource::Resource;
-use crate::runtime::{RuntimeChannel, TrySend};
+use crate::runtime::{RuntimeChannel, TrySend, TrySendError};
 use crate::trace::Span;
 use futures_channel::oneshot;
 use futures_util::{
@@ -47,12 +71,18 @@ use futures_util::{
 };
 use opentelemetry::global;
 use opentelemetry::{
-    trace::{TraceError, TraceResult},
-    Context,
+    trace::{Status, TraceError, TraceResult},
+    Array, Context, KeyValue, Value,
 };
+use rand::Rng;
 use std::cmp::min;
-use std::sync::{Arc, Mutex};
-use std::{env, fmt, str::FromStr, time::Duration};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard, TryLockError};
+use std::task::{self, Poll, Wake, Waker};
+use std::time::Instant;
+use std::{env, fmt, str::FromStr, thread, time::Duration};
 
 /// Delay interval between two consecutive exports.
 const OTEL_BSP_SCHEDULE_DELAY: &str = "OTEL_BSP_SCHEDULE_DELAY";
@@ -83,36 +113,306 @@ pub trait SpanProcessor: Send + Sync + std::fmt::Debug {
     /// `on_start` is called when a `Span` is started.  This method is called
     /// synchronously on the thread that started the span, therefore it should
     /// not block or throw exceptions.
+    ///
+    /// Processors are called in the order they were registered in, with the
+    /// same span, so they observe the changes made to it by the processors
+    /// registered before them, e.g. attributes they added.
+    ///
+    /// A processor can keep the span from being recorded with
+    /// [`Span::suppress`], which takes effect once every processor has been
+    /// called.
     fn on_start(&self, span: &mut Span, cx: &Context);
     /// `on_end` is called after a `Span` is ended (i.e., the end timestamp is
     /// already set). This method is called synchronously within the `Span::end`
     /// API, therefore it should not block or throw an exception.
     fn on_end(&self, span: SpanData);
+    /// Like [`on_end`](SpanProcessor::on_end), but tells the caller whether
+    /// the span was accepted or dropped, e.g. because the processor is
+    /// overloaded.
+    ///
+    /// Providers call this instead of `on_end` and report dropped spans to the
+    /// callback set with
+    /// [`Builder::with_on_processor_drop`](crate::trace::Builder::with_on_processor_drop).
+    /// The default calls `on_end` and returns [`OnEndStatus::Accepted`], so
+    /// processors that never drop spans only need to implement `on_end`.
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        self.on_end(span);
+        OnEndStatus::Accepted
+    }
     /// Force the spans lying in the cache to be exported.
     fn force_flush(&self) -> TraceResult<()>;
+    /// Like [`force_flush`](SpanProcessor::force_flush), but gives up waiting
+    /// for the flush after `timeout`, returning an error.
+    ///
+    /// Called by
+    /// [`TracerProvider::force_flush_blocking`](crate::trace::TracerProvider::force_flush_blocking).
+    /// The default calls `force_flush`, which suits processors flushing
+    /// inline. Processors waiting for another thread or task to flush should
+    /// stop waiting once `timeout` elapses.
+    fn force_flush_with_timeout(&self, _timeout: Duration) -> TraceResult<()> {
+        self.force_flush()
+    }
     /// Shuts down the processor. Called when SDK is shut down. This is an
     /// opportunity for processors to do any cleanup required.
     ///
     /// Implementation should make sure shutdown can be called multiple times.
     fn shutdown(&self) -> TraceResult<()>;
+    /// Like [`shutdown`](SpanProcessor::shutdown), but gives up waiting for
+    /// the shutdown after `timeout`, returning an error.
+    ///
+    /// Called by
+    /// [`TracerProvider::shutdown_with_timeout`](crate::trace::TracerProvider::shutdown_with_timeout).
+    /// The default calls `shutdown`, processors waiting for another thread or
+    /// task to shut down should stop waiting once `timeout` elapses.
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> TraceResult<()> {
+        self.shutdown()
+    }
+    /// Warms up the exporters of the processor, see
+    /// [`SpanExporter::warmup`]. Spans ending after the returned future
+    /// completes are exported after the warmup.
+    ///
+    /// Called by [`TracerProvider::warmup`](crate::trace::TracerProvider::warmup).
+    /// The default does nothing.
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        Box::pin(future::ready(Ok(())))
+    }
     /// Set the resource for the log processor.
+    ///
+    /// Providers call this before the processor is reachable by any tracer,
+    /// so it always happens before the first call to `on_start` or `on_end`.
     fn set_resource(&mut self, _resource: &Resource) {}
+    /// Name identifying the processor in reports such as
+    /// [`FlushReport`](crate::trace::FlushReport). Defaults to the type name.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+    /// Returns the processor's self-telemetry, e.g. queue depth and export
+    /// counts, or `None` if the processor does not track any.
+    fn stats(&self) -> Option<ProcessorStats> {
+        None
+    }
+    /// Resets the counters reported by [`stats`](SpanProcessor::stats) to
+    /// zero, e.g. between the measurement cycles of a benchmark. The queue
+    /// depth and shutdown state are kept. The default does nothing.
+    fn reset_stats(&self) {}
+    /// Returns the processor's health, e.g. whether it is shut down and how
+    /// many spans are queued, or `None` if the processor does not track it.
+    ///
+    /// Called from readiness checks, so it should be cheap and must not
+    /// block.
+    fn status(&self) -> Option<ProcessorStatus> {
+        None
+    }
+}
+
+/// Whether a [`SpanProcessor`] accepted an ended span, see
+/// [`SpanProcessor::try_on_end`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OnEndStatus {
+    /// The span was accepted, or deliberately ignored, e.g. because it isn't
+    /// sampled.
+    Accepted,
+    /// The span was dropped because the processor's queue is full.
+    QueueFull,
+    /// The span was dropped because the processor is shut down.
+    Shutdown,
+    /// The span was dropped because of another error.
+    Failed,
 }
 
 /// A [SpanProcessor] that passes finished spans to the configured
 /// `SpanExporter`, as soon as they are finished, without any batching. This is
 /// typically useful for debugging and testing. For scenarios requiring higher
 /// performance/throughput, consider using [BatchSpanProcessor].
+///
+/// By default the thread ending a span waits for the exporter to be done with
+/// it, however long that takes. Use [`SimpleSpanProcessor::builder`] to bound
+/// that wait with [`SimpleSpanProcessorBuilder::with_export_timeout`].
+///
+/// Exports are driven to completion on the thread ending the span, so this
+/// processor doesn't need an async runtime and fits synchronous programs
+/// such as CLI tools. [`shutdown`](SpanProcessor::shutdown) waits for an
+/// export in progress on another thread, flushes the exporter and then shuts
+/// it down. Spans ending afterwards are dropped, and later calls to
+/// `shutdown` return `Ok` without touching the exporter again.
+///
+/// The export is also bounded by the [`ExportDeadline`] of the current
+/// context when the span ends, if any, so that ending a span doesn't make a
+/// request overrun its own deadline.
 #[derive(Debug)]
 pub struct SimpleSpanProcessor {
     exporter: Mutex<Box<dyn SpanExporter>>,
+    export_timeout: Option<Duration>,
+    timed_out_spans: AtomicU64,
+    // only set while holding the exporter lock
+    is_shutdown: AtomicBool,
 }
 
 impl SimpleSpanProcessor {
     pub(crate) fn new(exporter: Box<dyn SpanExporter>) -> Self {
         Self {
             exporter: Mutex::new(exporter),
+            export_timeout: None,
+            timed_out_spans: AtomicU64::new(0),
+            is_shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// Create a new simple processor builder
+    pub fn builder<E>(exporter: E) -> SimpleSpanProcessorBuilder<E>
+    where
+        E: SpanExporter,
+    {
+        SimpleSpanProcessorBuilder {
+            exporter,
+            export_timeout: None,
+        }
+    }
+
+    /// Returns the number of spans dropped so far because their export took
+    /// longer than the export timeout, or than what was left before the
+    /// [`ExportDeadline`] of their context.
+    ///
+    /// Always `0` unless an export timeout was set with
+    /// [`SimpleSpanProcessorBuilder::with_export_timeout`] or spans were
+    /// ended with a deadline.
+    pub fn dropped_spans_count(&self) -> u64 {
+        self.timed_out_spans.load(Ordering::Relaxed)
+    }
+
+    /// Counts a span whose export didn't complete within `timeout`.
+    fn timed_out(&self, timeout: Duration) -> TraceError {
+        self.timed_out_spans.fetch_add(1, Ordering::Relaxed);
+        TraceError::ExportTimedOut(timeout)
+    }
+}
+
+/// Point in time after which a [`SimpleSpanProcessor`] gives up on exporting
+/// the spans ending in a context, e.g. the deadline of the request the spans
+/// belong to.
+///
+/// The deadline is read from the [current context](Context::current) when a
+/// span ends. The export is abandoned when the deadline passes, including
+/// while waiting for the export of another thread, and skipped if it already
+/// has, the span being counted in
+/// [`SimpleSpanProcessor::dropped_spans_count`].
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::Context;
+/// use opentelemetry_sdk::trace::ExportDeadline;
+/// use std::time::{Duration, Instant};
+///
+/// fn handle_request() {
+///     let deadline = ExportDeadline::new(Instant::now() + Duration::from_millis(200));
+///     let _guard = Context::current().with_value(deadline).attach();
+///     // spans ended here are exported within the request deadline
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExportDeadline(Instant);
+
+impl ExportDeadline {
+    /// Create a deadline at `deadline`.
+    pub fn new(deadline: Instant) -> Self {
+        ExportDeadline(deadline)
+    }
+
+    /// The point in time after which exports are abandoned.
+    pub fn deadline(&self) -> Instant {
+        self.0
+    }
+
+    /// Time left before the deadline, zero once it passed.
+    fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+}
+
+/// A builder for creating [`SimpleSpanProcessor`] instances.
+#[derive(Debug)]
+pub struct SimpleSpanProcessorBuilder<E> {
+    exporter: E,
+    export_timeout: Option<Duration>,
+}
+
+impl<E> SimpleSpanProcessorBuilder<E>
+where
+    E: SpanExporter + 'static,
+{
+    /// Give up on exporting a span after `timeout`.
+    ///
+    /// The export of a span that isn't done within `timeout`, including the
+    /// time spent waiting for the exports of other threads, is cancelled,
+    /// the span is dropped and counted in
+    /// [`SimpleSpanProcessor::dropped_spans_count`], and the thread ending the
+    /// span moves on. Without a timeout, which is the default, that thread
+    /// waits for the exporter however long it takes, e.g. during a backend
+    /// outage.
+    pub fn with_export_timeout(self, timeout: Duration) -> Self {
+        SimpleSpanProcessorBuilder {
+            export_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Build a simple processor
+    pub fn build(self) -> SimpleSpanProcessor {
+        let mut processor = SimpleSpanProcessor::new(Box::new(self.exporter));
+        processor.export_timeout = self.export_timeout;
+        processor
+    }
+}
+
+/// Polls `future` on the current thread until it completes or `timeout`
+/// elapses, in which case the future is dropped and `None` is returned.
+fn block_on_timeout<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = task::Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Some(output);
         }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        thread::park_timeout(deadline - now);
+    }
+}
+
+/// Interval between two attempts of [`lock_until`] to take the lock.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Locks `mutex`, waiting until `deadline` at most, in which case `None` is
+/// returned.
+fn lock_until<T: ?Sized>(
+    mutex: &Mutex<T>,
+    deadline: Instant,
+) -> Option<LockResult<MutexGuard<'_, T>>> {
+    loop {
+        match mutex.try_lock() {
+            Ok(guard) => return Some(Ok(guard)),
+            Err(TryLockError::Poisoned(poisoned)) => return Some(Err(poisoned)),
+            Err(TryLockError::WouldBlock) => {}
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        thread::sleep(min(deadline - now, LOCK_RETRY_INTERVAL));
     }
 }
 
@@ -126,11 +426,45 @@ impl SpanProcessor for SimpleSpanProcessor {
             return;
         }
 
-        let result = self
-            .exporter
-            .lock()
-            .map_err(|_| TraceError::Other("SimpleSpanProcessor mutex poison".into()))
-            .and_then(|mut exporter| futures_executor::block_on(exporter.export(vec![span])));
+        let remaining =
+            Context::map_current(|cx| cx.get::<ExportDeadline>().map(ExportDeadline::remaining));
+        if remaining == Some(Duration::ZERO) {
+            // the context is out of time already, don't even start exporting
+            global::handle_error(self.timed_out(Duration::ZERO));
+            return;
+        }
+        let export_timeout = match (self.export_timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(min(timeout, remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        };
+
+        // waiting for the export of another thread counts against the timeout
+        let deadline = export_timeout.map(|timeout| (timeout, Instant::now() + timeout));
+        let exporter = match deadline {
+            Some((timeout, deadline)) => match lock_until(&self.exporter, deadline) {
+                Some(exporter) => exporter,
+                None => {
+                    global::handle_error(self.timed_out(timeout));
+                    return;
+                }
+            },
+            None => self.exporter.lock(),
+        };
+        let result = match exporter {
+            Err(_) => Err(TraceError::Other("SimpleSpanProcessor mutex poison".into())),
+            Ok(_) if self.is_shutdown.load(Ordering::Relaxed) => Ok(()),
+            Ok(mut exporter) => {
+                let export = exporter.export(vec![span]);
+                match deadline {
+                    Some((timeout, deadline)) => {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        block_on_timeout(export, remaining)
+                            .unwrap_or_else(|| Err(self.timed_out(timeout)))
+                    }
+                    None => futures_executor::block_on(export),
+                }
+            }
+        };
 
         if let Err(err) = result {
             global::handle_error(err);
@@ -143,14 +477,29 @@ impl SpanProcessor for SimpleSpanProcessor {
     }
 
     fn shutdown(&self) -> TraceResult<()> {
-        if let Ok(mut exporter) = self.exporter.lock() {
-            exporter.shutdown();
-            Ok(())
-        } else {
-            Err(TraceError::Other(
-                "SimpleSpanProcessor mutex poison at shutdown".into(),
-            ))
+        // holding the lock waits for the export in progress, if any
+        let mut exporter = self.exporter.lock().map_err(|_| {
+            TraceError::Other("SimpleSpanProcessor mutex poison at shutdown".into())
+        })?;
+        if self.is_shutdown.swap(true, Ordering::Relaxed) {
+            return Ok(());
         }
+        let result = futures_executor::block_on(exporter.force_flush());
+        exporter.shutdown();
+        result
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        match self.exporter.lock() {
+            Ok(mut exporter) => exporter.warmup(),
+            Err(_) => Box::pin(future::ready(Err(TraceError::Other(
+                "SimpleSpanProcessor mutex poison".into(),
+            )))),
+        }
+    }
+
+    fn reset_stats(&self) {
+        self.timed_out_spans.store(0, Ordering::Relaxed);
     }
 
     fn set_resource(&mut self, resource: &Resource) {
@@ -222,12 +571,41 @@ impl SpanProcessor for SimpleSpanProcessor {
 /// [`async-std`]: https://async.rs
 pub struct BatchSpanProcessor<R: RuntimeChannel> {
     message_sender: R::Sender<BatchMessage>,
+    // set with `OverflowPolicy::DropOldest`, spans are queued here instead of
+    // being sent to the worker
+    overflow_queue: Option<Arc<OverflowQueue>>,
+    stats: Arc<BatchStats>,
+    max_queue_size: usize,
+    on_dropped: Option<OnDropped>,
+}
+
+/// Callback invoked with the number of spans dropped because the queue is full.
+struct OnDropped(Box<dyn Fn(usize) + Send + Sync>);
+
+impl fmt::Debug for OnDropped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnDropped")
+    }
+}
+
+/// Callback invoked by the worker after every call to the exporter.
+#[derive(Clone)]
+struct OnExport(Arc<dyn Fn(ExportMetrics) + Send + Sync>);
+
+impl fmt::Debug for OnExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnExport")
+    }
 }
 
 impl<R: RuntimeChannel> fmt::Debug for BatchSpanProcessor<R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BatchSpanProcessor")
             .field("message_sender", &self.message_sender)
+            .field("overflow_queue", &self.overflow_queue)
+            .field("stats", &self.stats)
+            .field("max_queue_size", &self.max_queue_size)
+            .field("on_dropped", &self.on_dropped)
             .finish()
     }
 }
@@ -238,14 +616,43 @@ impl<R: RuntimeChannel> SpanProcessor for BatchSpanProcessor<R> {
     }
 
     fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
         if !span.span_context.is_sampled() {
-            return;
+            return OnEndStatus::Accepted;
+        }
+        if let Some(queue) = &self.overflow_queue {
+            return self.queue_dropping_oldest(queue, span);
         }
 
+        // counted before sending so the worker never dequeues a span that isn't
+        // accounted for yet
+        self.stats.record_enqueued();
         let result = self.message_sender.try_send(BatchMessage::ExportSpan(span));
 
-        if let Err(err) = result {
-            global::handle_error(TraceError::Other(err.into()));
+        match result {
+            Ok(()) => OnEndStatus::Accepted,
+            Err(err) => {
+                self.stats.record_dequeued(1);
+                let status = match err {
+                    TrySendError::ChannelFull => {
+                        self.stats.record_dropped_queue_full();
+                        if let Some(OnDropped(on_dropped)) = &self.on_dropped {
+                            on_dropped(1);
+                        }
+                        OnEndStatus::QueueFull
+                    }
+                    TrySendError::ChannelClosed => {
+                        self.stats.record_dropped_shutdown();
+                        OnEndStatus::Shutdown
+                    }
+                    _ => OnEndStatus::Failed,
+                };
+                global::handle_error(TraceError::Other(err.into()));
+                status
+            }
         }
     }
 
@@ -260,23 +667,78 @@ impl<R: RuntimeChannel> SpanProcessor for BatchSpanProcessor<R> {
             .and_then(|identity| identity)
     }
 
-    fn shutdown(&self) -> TraceResult<()> {
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
         let (res_sender, res_receiver) = oneshot::channel();
         self.message_sender
-            .try_send(BatchMessage::Shutdown(res_sender))
+            .try_send(BatchMessage::Flush(Some(res_sender)))
             .map_err(|err| TraceError::Other(err.into()))?;
 
+        // the worker keeps flushing if we stop waiting, the result is dropped
+        block_on_timeout(res_receiver, timeout)
+            .ok_or_else(|| TraceError::from(format!("did not flush within {timeout:?}")))?
+            .map_err(|err| TraceError::Other(err.into()))
+            .and_then(|identity| identity)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        let res_receiver = self.send_shutdown()?;
+
         futures_executor::block_on(res_receiver)
             .map_err(|err| TraceError::Other(err.into()))
             .and_then(|identity| identity)
     }
 
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        let res_receiver = self.send_shutdown()?;
+
+        block_on_timeout(res_receiver, timeout)
+            .ok_or_else(|| TraceError::from(format!("did not shut down within {timeout:?}")))?
+            .map_err(|err| TraceError::Other(err.into()))
+            .and_then(|identity| identity)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        let (res_sender, res_receiver) = oneshot::channel();
+        if let Err(err) = self
+            .message_sender
+            .try_send(BatchMessage::Warmup(res_sender))
+        {
+            return Box::pin(future::ready(Err(TraceError::Other(err.into()))));
+        }
+
+        Box::pin(async move {
+            res_receiver
+                .await
+                .map_err(|err| TraceError::Other(err.into()))
+                .and_then(|identity| identity)
+        })
+    }
+
     fn set_resource(&mut self, resource: &Resource) {
         let resource = Arc::new(resource.clone());
+        // the channel is FIFO, so the worker applies the resource before
+        // exporting any span sent after this call
         let _ = self
             .message_sender
             .try_send(BatchMessage::SetResource(resource));
     }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        Some(self.stats.snapshot())
+    }
+
+    fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        let stats = self.stats.snapshot();
+        Some(ProcessorStatus {
+            queue_depth: Some(stats.queue_depth),
+            dropped_spans: Some(stats.dropped_spans),
+            ..ProcessorStatus::new(self.name(), self.stats.is_shutdown())
+        })
+    }
 }
 
 /// Messages sent between application thread and batch span processor's work thread.
@@ -295,14 +757,64 @@ enum BatchMessage {
     Shutdown(oneshot::Sender<ExportResult>),
     /// Set the resource for the exporter.
     SetResource(Arc<Resource>),
+    /// Warm the exporter up, before exporting the spans sent after this.
+    Warmup(oneshot::Sender<ExportResult>),
+    /// Spans were added to the overflow queue, see [`OverflowPolicy::DropOldest`].
+    SpansQueued,
+}
+
+/// Spans ended but not handled by the worker yet, shared with it when the
+/// oldest spans are dropped on overflow.
+#[derive(Debug)]
+struct OverflowQueue {
+    spans: Mutex<VecDeque<SpanData>>,
+    capacity: usize,
+}
+
+impl OverflowQueue {
+    /// Appends `span`, returning the oldest span if it had to be evicted.
+    fn push(&self, span: SpanData) -> Option<SpanData> {
+        let mut spans = self
+            .spans
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        spans.push_back(span);
+        if spans.len() > self.capacity {
+            spans.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Removes the latest span, which won't be handled.
+    fn pop_newest(&self) -> Option<SpanData> {
+        self.spans
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop_back()
+    }
+
+    fn take(&self) -> VecDeque<SpanData> {
+        std::mem::take(
+            &mut *self
+                .spans
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
 }
 
 struct BatchSpanProcessorInternal<R> {
     spans: Vec<SpanData>,
+    overflow_queue: Option<Arc<OverflowQueue>>,
+    // estimated serialized size of `spans`, tracked only with a byte budget
+    spans_bytes: usize,
     export_tasks: FuturesUnordered<BoxFuture<'static, ExportResult>>,
     runtime: R,
     exporter: Box<dyn SpanExporter>,
     config: BatchConfig,
+    stats: Arc<BatchStats>,
+    on_export: Option<OnExport>,
 }
 
 impl<R: RuntimeChannel> BatchSpanProcessorInternal<R> {
@@ -325,7 +837,7 @@ impl<R: RuntimeChannel> BatchSpanProcessorInternal<R> {
             Ok(())
         });
 
-        if self.config.max_concurrent_exports == 1 {
+        if self.exports_inline() {
             let _ = task.await;
         } else {
             self.export_tasks.push(task);
@@ -333,39 +845,68 @@ impl<R: RuntimeChannel> BatchSpanProcessorInternal<R> {
         }
     }
 
+    /// Whether exports should be awaited on the worker task instead of being
+    /// scheduled concurrently.
+    fn exports_inline(&self) -> bool {
+        self.config.strict_fifo || self.config.max_concurrent_exports == 1
+    }
+
+    /// Add an ended span to the current batch, exporting it once full.
+    async fn push_span(&mut self, span: SpanData) {
+        let is_error = matches!(span.status, Status::Error { .. });
+        if self.config.max_export_batch_bytes.is_some() {
+            self.spans_bytes += estimated_size(&span);
+        }
+        self.spans.push(span);
+
+        let over_byte_budget = self
+            .config
+            .max_export_batch_bytes
+            .map_or(false, |budget| self.spans_bytes >= budget);
+        if self.spans.len() == self.config.max_export_batch_size
+            || over_byte_budget
+            || (self.config.flush_on_error && is_error)
+        {
+            // If concurrent exports are saturated, wait for one to complete.
+            if !self.export_tasks.is_empty()
+                && self.export_tasks.len() == self.config.max_concurrent_exports
+            {
+                self.export_tasks.next().await;
+            }
+
+            let export_task = self.export();
+            let task = async move {
+                if let Err(err) = export_task.await {
+                    global::handle_error(err);
+                }
+
+                Ok(())
+            };
+            // Special case when not using concurrent exports
+            if self.exports_inline() {
+                let _ = task.await;
+            } else {
+                self.export_tasks.push(Box::pin(task));
+            }
+        }
+    }
+
     /// Process a single message
     ///
     /// A return value of false indicates shutdown
     async fn process_message(&mut self, message: BatchMessage) -> bool {
+        // spans queued before this message was sent are handled before it
+        if let Some(queue) = self.overflow_queue.clone() {
+            for span in queue.take() {
+                self.push_span(span).await;
+            }
+        }
+
         match message {
             // Span has finished, add to buffer of pending spans.
-            BatchMessage::ExportSpan(span) => {
-                self.spans.push(span);
-
-                if self.spans.len() == self.config.max_export_batch_size {
-                    // If concurrent exports are saturated, wait for one to complete.
-                    if !self.export_tasks.is_empty()
-                        && self.export_tasks.len() == self.config.max_concurrent_exports
-                    {
-                        self.export_tasks.next().await;
-                    }
-
-                    let export_task = self.export();
-                    let task = async move {
-                        if let Err(err) = export_task.await {
-                            global::handle_error(err);
-                        }
-
-                        Ok(())
-                    };
-                    // Special case when not using concurrent exports
-                    if self.config.max_concurrent_exports == 1 {
-                        let _ = task.await;
-                    } else {
-                        self.export_tasks.push(Box::pin(task));
-                    }
-                }
-            }
+            BatchMessage::ExportSpan(span) => self.push_span(span).await,
+            // the spans were added to the overflow queue, drained above
+            BatchMessage::SpansQueued => {}
             // Span batch interval time reached or a force flush has been invoked, export
             // current spans.
             //
@@ -399,6 +940,15 @@ impl<R: RuntimeChannel> BatchSpanProcessorInternal<R> {
             BatchMessage::SetResource(resource) => {
                 self.exporter.set_resource(&resource);
             }
+            BatchMessage::Warmup(ch) => {
+                let result = self.exporter.warmup().await;
+                if let Err(result) = ch.send(result) {
+                    global::handle_error(TraceError::from(format!(
+                        "failed to send warmup result: {:?}",
+                        result
+                    )));
+                }
+            }
         }
         true
     }
@@ -410,15 +960,31 @@ impl<R: RuntimeChannel> BatchSpanProcessorInternal<R> {
             return Box::pin(future::ready(Ok(())));
         }
 
+        let count = self.spans.len();
+        self.spans_bytes = 0;
+        self.stats.record_dequeued(count);
+        let stats = self.stats.clone();
+        let on_export = self.on_export.clone();
+        let start = Instant::now();
         let export = self.exporter.export(self.spans.split_off(0));
         let timeout = self.runtime.delay(self.config.max_export_timeout);
         let time_out = self.config.max_export_timeout;
 
         Box::pin(async move {
-            match future::select(export, timeout).await {
+            let result = match future::select(export, timeout).await {
                 Either::Left((export_res, _)) => export_res,
                 Either::Right((_, _)) => ExportResult::Err(TraceError::ExportTimedOut(time_out)),
+            };
+            let duration = start.elapsed();
+            stats.record_export(count, result.is_ok(), duration);
+            if let Some(OnExport(on_export)) = on_export {
+                on_export(ExportMetrics {
+                    batch_size: count,
+                    duration,
+                    success: result.is_ok(),
+                });
             }
+            result
         })
     }
 
@@ -447,34 +1013,151 @@ impl<R: RuntimeChannel> BatchSpanProcessorInternal<R> {
 
 impl<R: RuntimeChannel> BatchSpanProcessor<R> {
     pub(crate) fn new(exporter: Box<dyn SpanExporter>, config: BatchConfig, runtime: R) -> Self {
-        let (message_sender, message_receiver) =
-            runtime.batch_message_channel(config.max_queue_size);
+        Self::with_on_export(exporter, config, runtime, None)
+    }
+
+    fn with_on_export(
+        exporter: Box<dyn SpanExporter>,
+        config: BatchConfig,
+        runtime: R,
+        on_export: Option<OnExport>,
+    ) -> Self {
+        let max_queue_size = config.max_queue_size;
+        let (message_sender, message_receiver) = runtime.batch_message_channel(max_queue_size);
+        let overflow_queue = match config.overflow_policy {
+            OverflowPolicy::DropNewest => None,
+            OverflowPolicy::DropOldest => Some(Arc::new(OverflowQueue {
+                spans: Mutex::new(VecDeque::new()),
+                capacity: config.max_queue_size,
+            })),
+        };
+        let worker_overflow_queue = overflow_queue.clone();
 
+        let stats = Arc::new(BatchStats::default());
+        let worker_stats = stats.clone();
         let inner_runtime = runtime.clone();
         // Spawn worker process via user-defined spawn function.
         runtime.spawn(Box::pin(async move {
             // Timer will take a reference to the current runtime, so its important we do this within the
             // runtime.spawn()
-            let ticker = inner_runtime
-                .interval(config.scheduled_delay)
-                .skip(1) // The ticker is fired immediately, so we should skip the first one to align with the interval.
-                .map(|_| BatchMessage::Flush(None));
+            let ticker = if config.scheduled_delay_jitter > 0.0 {
+                // a fresh jittered delay for every tick
+                let delay_runtime = inner_runtime.clone();
+                let (scheduled_delay, jitter) =
+                    (config.scheduled_delay, config.scheduled_delay_jitter);
+                Either::Right(stream::unfold((), move |()| {
+                    let delay = delay_runtime.delay(jittered_delay(scheduled_delay, jitter));
+                    async move {
+                        delay.await;
+                        Some((BatchMessage::Flush(None), ()))
+                    }
+                }))
+            } else {
+                Either::Left(
+                    inner_runtime
+                        .interval(config.scheduled_delay)
+                        .skip(1) // The ticker is fired immediately, so we should skip the first one to align with the interval.
+                        .map(|_| BatchMessage::Flush(None)),
+                )
+            };
             let timeout_runtime = inner_runtime.clone();
 
             let messages = Box::pin(stream::select(message_receiver, ticker));
             let processor = BatchSpanProcessorInternal {
                 spans: Vec::new(),
+                overflow_queue: worker_overflow_queue,
+                spans_bytes: 0,
                 export_tasks: FuturesUnordered::new(),
                 runtime: timeout_runtime,
                 config,
                 exporter,
+                stats: worker_stats,
+                on_export,
             };
 
             processor.run(messages).await
         }));
 
         // Return batch processor with link to worker
-        BatchSpanProcessor { message_sender }
+        BatchSpanProcessor {
+            message_sender,
+            overflow_queue,
+            stats,
+            max_queue_size,
+            on_dropped: None,
+        }
+    }
+
+    /// Asks the worker to shut down, returning the receiver of its result.
+    ///
+    /// The processor is marked shut down before sending, so that a concurrent
+    /// call doesn't send a second shutdown nothing would answer, the worker
+    /// being gone, and unmarked if the message couldn't be sent, e.g. because
+    /// the channel is full, so that the shutdown can be retried.
+    fn send_shutdown(&self) -> TraceResult<oneshot::Receiver<ExportResult>> {
+        if self.stats.record_shutdown() {
+            return Err(TraceError::from(
+                "the batch span processor is already shut down",
+            ));
+        }
+        let (res_sender, res_receiver) = oneshot::channel();
+        if let Err(err) = self
+            .message_sender
+            .try_send(BatchMessage::Shutdown(res_sender))
+        {
+            self.stats.cancel_shutdown();
+            return Err(TraceError::Other(err.into()));
+        }
+        Ok(res_receiver)
+    }
+
+    /// Queues `span`, evicting the oldest queued span if the queue is full.
+    fn queue_dropping_oldest(&self, queue: &OverflowQueue, span: SpanData) -> OnEndStatus {
+        if self.stats.is_shutdown() {
+            self.stats.record_dropped_shutdown();
+            return OnEndStatus::Shutdown;
+        }
+
+        self.stats.record_enqueued();
+        if queue.push(span).is_some() {
+            self.stats.record_dequeued(1);
+            self.stats.record_dropped_queue_full();
+            if let Some(OnDropped(on_dropped)) = &self.on_dropped {
+                on_dropped(1);
+            }
+            global::handle_error(TraceError::Other(
+                "the span queue is full, dropped the oldest span".into(),
+            ));
+        }
+
+        // a full channel already holds messages waking the worker up
+        match self.message_sender.try_send(BatchMessage::SpansQueued) {
+            Err(TrySendError::ChannelClosed) => {
+                if queue.pop_newest().is_some() {
+                    self.stats.record_dequeued(1);
+                    self.stats.record_dropped_shutdown();
+                }
+                OnEndStatus::Shutdown
+            }
+            _ => OnEndStatus::Accepted,
+        }
+    }
+
+    /// Returns the number of spans dropped so far because the queue was full.
+    ///
+    /// Spans are dropped when they end faster than the exporter can keep up
+    /// with, see [`BatchConfigBuilder::with_max_queue_size`].
+    pub fn dropped_spans_count(&self) -> u64 {
+        self.stats.snapshot().dropped_spans.queue_full
+    }
+
+    /// Returns a handle reading the number of spans queued by this
+    /// processor, e.g. to sample less when the exporter falls behind with an
+    /// [`AdaptiveSampler`].
+    ///
+    /// [`AdaptiveSampler`]: crate::trace::AdaptiveSampler
+    pub fn queue_depth_gauge(&self) -> QueueDepthGauge {
+        QueueDepthGauge::new(self.stats.clone(), self.max_queue_size)
     }
 
     /// Create a new batch processor builder
@@ -486,10 +1169,80 @@ impl<R: RuntimeChannel> BatchSpanProcessor<R> {
             exporter,
             config: Default::default(),
             runtime,
+            on_dropped: None,
+            on_export: None,
+            max_queue_size: None,
+            max_export_batch_size: None,
+            scheduled_delay: None,
+            max_export_timeout: None,
         }
     }
 }
 
+/// Rough size of `span` once serialized, counting the length of its strings
+/// and a fixed size for its ids, timestamps and numbers.
+///
+/// Meant to be cheap rather than exact, as it is computed for every span
+/// when batching against a byte budget.
+fn estimated_size(span: &SpanData) -> usize {
+    // trace, span and parent ids, start and end times, flags and kind
+    const FIXED_SIZE: usize = 16 + 8 + 8 + 8 + 8 + 2;
+    // event timestamp
+    const EVENT_SIZE: usize = 8;
+    // linked trace and span ids
+    const LINK_SIZE: usize = 16 + 8;
+
+    let status_size = match &span.status {
+        Status::Error { description } => description.len(),
+        _ => 0,
+    };
+    let events_size: usize = span
+        .events
+        .iter()
+        .map(|event| EVENT_SIZE + event.name.len() + attributes_size(&event.attributes))
+        .sum();
+    let links_size: usize = span
+        .links
+        .iter()
+        .map(|link| LINK_SIZE + attributes_size(&link.attributes))
+        .sum();
+
+    FIXED_SIZE
+        + span.name.len()
+        + attributes_size(&span.attributes)
+        + events_size
+        + links_size
+        + status_size
+}
+
+fn attributes_size(attributes: &[KeyValue]) -> usize {
+    attributes
+        .iter()
+        .map(|kv| {
+            let value_size = match &kv.value {
+                Value::Bool(_) => 1,
+                Value::I64(_) | Value::F64(_) => 8,
+                Value::String(s) => s.as_str().len(),
+                Value::Array(Array::Bool(values)) => values.len(),
+                Value::Array(Array::I64(values)) => values.len() * 8,
+                Value::Array(Array::F64(values)) => values.len() * 8,
+                Value::Array(Array::String(values)) => {
+                    values.iter().map(|s| s.as_str().len()).sum()
+                }
+            };
+            kv.key.as_str().len() + value_size
+        })
+        .sum()
+}
+
+/// `delay` randomly shortened or lengthened by up to `jitter` times itself.
+fn jittered_delay(delay: Duration, jitter: f64) -> Duration {
+    if jitter == 0.0 {
+        return delay;
+    }
+    delay.mul_f64(1.0 + rand::thread_rng().gen_range(-jitter..=jitter))
+}
+
 /// Batch span processor configuration.
 /// Use [`BatchConfigBuilder`] to configure your own instance of [`BatchConfig`].
 #[derive(Debug)]
@@ -517,6 +1270,41 @@ pub struct BatchConfig {
     /// by an exporter. A value of 1 will cause exports to be performed
     /// synchronously on the BatchSpanProcessor task.
     max_concurrent_exports: usize,
+
+    /// Serialize every export through the BatchSpanProcessor task
+    ///
+    /// When enabled, exports triggered by the timer, a full batch or a
+    /// force flush complete in the order they were triggered, regardless of
+    /// `max_concurrent_exports`.
+    strict_fifo: bool,
+
+    /// Export the current batch as soon as a span with an error status is
+    /// enqueued, without waiting for the scheduled delay.
+    flush_on_error: bool,
+
+    /// Fraction of the scheduled delay by which each delay is randomly
+    /// shortened or lengthened.
+    scheduled_delay_jitter: f64,
+
+    /// Export the current batch once the estimated serialized size of its
+    /// spans reaches this many bytes, if set.
+    max_export_batch_bytes: Option<usize>,
+
+    /// Which spans are dropped when the queue is full.
+    overflow_policy: OverflowPolicy,
+}
+
+/// Which spans a [`BatchSpanProcessor`] drops when its queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// Drop the spans ending while the queue is full.
+    #[default]
+    DropNewest,
+    /// Evict the oldest queued span to make room for the one ending, keeping
+    /// the most recent spans, which are often the most relevant during an
+    /// incident.
+    DropOldest,
 }
 
 impl Default for BatchConfig {
@@ -533,6 +1321,11 @@ pub struct BatchConfigBuilder {
     max_export_batch_size: usize,
     max_export_timeout: Duration,
     max_concurrent_exports: usize,
+    strict_fifo: bool,
+    flush_on_error: bool,
+    scheduled_delay_jitter: f64,
+    max_export_batch_bytes: Option<usize>,
+    overflow_policy: OverflowPolicy,
 }
 
 impl Default for BatchConfigBuilder {
@@ -551,6 +1344,11 @@ impl Default for BatchConfigBuilder {
             max_export_batch_size: OTEL_BSP_MAX_EXPORT_BATCH_SIZE_DEFAULT,
             max_export_timeout: Duration::from_millis(OTEL_BSP_EXPORT_TIMEOUT_DEFAULT),
             max_concurrent_exports: OTEL_BSP_MAX_CONCURRENT_EXPORTS_DEFAULT,
+            strict_fifo: false,
+            flush_on_error: false,
+            scheduled_delay_jitter: 0.0,
+            max_export_batch_bytes: None,
+            overflow_policy: OverflowPolicy::default(),
         }
         .init_from_env_vars()
     }
@@ -587,23 +1385,96 @@ impl BatchConfigBuilder {
         self
     }
 
-    /// Set scheduled_delay_duration for [`BatchConfigBuilder`].
-    /// It's the delay interval in milliseconds between two consecutive processing of batches.
-    /// The default value is 5000 milliseconds.
-    pub fn with_scheduled_delay(mut self, scheduled_delay: Duration) -> Self {
-        self.scheduled_delay = scheduled_delay;
+    /// Set strict_fifo for [`BatchConfigBuilder`].
+    /// When enabled, all exports, whether triggered by the scheduled delay, a full
+    /// batch or a force flush, are performed one at a time on the BatchSpanProcessor
+    /// task, so batches reach the exporter and complete in the order they were created.
+    /// The default value is false.
+    ///
+    /// This overrides `max_concurrent_exports`. Throughput is bounded by the
+    /// latency of a single export, and while an export is in flight new spans
+    /// accumulate in the queue and may be dropped once it is full. Prefer
+    /// concurrent exports when the backend does not depend on batch ordering.
+    pub fn with_strict_fifo(mut self, strict_fifo: bool) -> Self {
+        self.strict_fifo = strict_fifo;
         self
     }
 
-    /// Set max_export_timeout for [`BatchConfigBuilder`].
-    /// It's the maximum duration to export a batch of data.
-    /// The The default value is 30000 milliseconds.
-    pub fn with_max_export_timeout(mut self, max_export_timeout: Duration) -> Self {
-        self.max_export_timeout = max_export_timeout;
+    /// Set flush_on_error for [`BatchConfigBuilder`].
+    /// When enabled, enqueuing a span whose status is [`Status::Error`] immediately
+    /// exports the current batch, including that span, instead of waiting for the
+    /// scheduled delay or for the batch to fill up.
+    /// The default value is false.
+    ///
+    /// Error spans that end in quick succession each trigger an export, which
+    /// results in more and smaller batches.
+    pub fn with_flush_on_error(mut self, flush_on_error: bool) -> Self {
+        self.flush_on_error = flush_on_error;
         self
     }
 
-    /// Builds a `BatchConfig` enforcing the following invariants:
+    /// Set scheduled_delay_jitter for [`BatchConfigBuilder`].
+    /// Each delay between two scheduled exports is randomly shortened or
+    /// lengthened by up to this fraction of the scheduled delay, e.g. `0.1`
+    /// turns a 5 seconds delay into one between 4.5 and 5.5 seconds, drawn
+    /// again for every delay.
+    /// The default value is 0, exporting at exactly the scheduled delay.
+    ///
+    /// This keeps the exports of many instances started at the same time
+    /// from hitting the collector all at once. The fraction is clamped
+    /// between 0 and 1.
+    pub fn with_scheduled_delay_jitter(mut self, fraction: f64) -> Self {
+        self.scheduled_delay_jitter = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Set max_export_batch_bytes for [`BatchConfigBuilder`].
+    /// The current batch is exported as soon as the estimated serialized size
+    /// of its spans reaches this many bytes, in addition to when it reaches
+    /// `max_export_batch_size` spans or the scheduled delay elapses.
+    /// By default batches are not limited in bytes.
+    ///
+    /// This keeps batches under the payload limits of exporters, e.g. the
+    /// maximum message size of a gRPC server. The size of a span is estimated
+    /// from the length of its name and of the keys and values of its
+    /// attributes, events and links, without serializing it, so leave some
+    /// headroom below the actual limit. A single span larger than the budget
+    /// is exported on its own.
+    pub fn with_max_export_batch_bytes(mut self, max_export_batch_bytes: usize) -> Self {
+        self.max_export_batch_bytes = Some(max_export_batch_bytes);
+        self
+    }
+
+    /// Set overflow_policy for [`BatchConfigBuilder`].
+    /// It decides which spans are dropped when the queue is full, the default
+    /// [`OverflowPolicy::DropNewest`] drops the spans ending while it is full.
+    /// Either way dropped spans are counted in [`BatchSpanProcessor::dropped_spans_count`].
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Set scheduled_delay_duration for [`BatchConfigBuilder`].
+    /// It's the delay interval in milliseconds between two consecutive processing of batches.
+    /// The default value is 5000 milliseconds.
+    pub fn with_scheduled_delay(mut self, scheduled_delay: Duration) -> Self {
+        self.scheduled_delay = scheduled_delay;
+        self
+    }
+
+    /// Set max_export_timeout for [`BatchConfigBuilder`].
+    /// It's the maximum duration to export a batch of data.
+    /// The The default value is 30000 milliseconds.
+    pub fn with_max_export_timeout(mut self, max_export_timeout: Duration) -> Self {
+        self.max_export_timeout = max_export_timeout;
+        self
+    }
+
+    /// Builds a `BatchConfig` enforcing the following invariants:
     /// * `max_export_batch_size` must be less than or equal to `max_queue_size`.
     pub fn build(self) -> BatchConfig {
         // max export batch size must be less or equal to max queue size.
@@ -615,10 +1486,33 @@ impl BatchConfigBuilder {
             scheduled_delay: self.scheduled_delay,
             max_export_timeout: self.max_export_timeout,
             max_concurrent_exports: self.max_concurrent_exports,
+            strict_fifo: self.strict_fifo,
+            flush_on_error: self.flush_on_error,
+            scheduled_delay_jitter: self.scheduled_delay_jitter,
+            max_export_batch_bytes: self.max_export_batch_bytes,
+            overflow_policy: self.overflow_policy,
             max_export_batch_size,
         }
     }
 
+    /// The max export batch size and max queue size set by the environment
+    /// variables, or their defaults, before the batch size is capped.
+    pub(crate) fn env_sizes() -> (usize, usize) {
+        let env_size = |name, default| {
+            env::var(name)
+                .ok()
+                .and_then(|size| usize::from_str(&size).ok())
+                .unwrap_or(default)
+        };
+        (
+            env_size(
+                OTEL_BSP_MAX_EXPORT_BATCH_SIZE,
+                OTEL_BSP_MAX_EXPORT_BATCH_SIZE_DEFAULT,
+            ),
+            env_size(OTEL_BSP_MAX_QUEUE_SIZE, OTEL_BSP_MAX_QUEUE_SIZE_DEFAULT),
+        )
+    }
+
     fn init_from_env_vars(mut self) -> Self {
         if let Some(max_concurrent_exports) = env::var(OTEL_BSP_MAX_CONCURRENT_EXPORTS)
             .ok()
@@ -672,6 +1566,12 @@ pub struct BatchSpanProcessorBuilder<E, R> {
     exporter: E,
     config: BatchConfig,
     runtime: R,
+    on_dropped: Option<OnDropped>,
+    on_export: Option<OnExport>,
+    max_queue_size: Option<usize>,
+    max_export_batch_size: Option<usize>,
+    scheduled_delay: Option<Duration>,
+    max_export_timeout: Option<Duration>,
 }
 
 impl<E, R> BatchSpanProcessorBuilder<E, R>
@@ -684,9 +1584,123 @@ where
         BatchSpanProcessorBuilder { config, ..self }
     }
 
+    /// Register a callback invoked every time spans are dropped because the
+    /// queue is full, with the number of spans dropped.
+    ///
+    /// The callback is called on the thread ending the span, so it should be
+    /// cheap, e.g. increment a metric.
+    pub fn with_on_dropped<F>(self, on_dropped: F) -> Self
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        BatchSpanProcessorBuilder {
+            on_dropped: Some(OnDropped(Box::new(on_dropped))),
+            ..self
+        }
+    }
+
+    /// Register a callback invoked after every call to the exporter, with the
+    /// size of the batch, the duration of the export and whether it
+    /// succeeded, e.g. to record export latency and batch size histograms.
+    ///
+    /// The callback is called on the task exporting the batches, so it should
+    /// be cheap. Totals are also available from [`SpanProcessor::stats`].
+    pub fn with_on_export<F>(self, on_export: F) -> Self
+    where
+        F: Fn(ExportMetrics) + Send + Sync + 'static,
+    {
+        BatchSpanProcessorBuilder {
+            on_export: Some(OnExport(Arc::new(on_export))),
+            ..self
+        }
+    }
+
+    /// Set the maximum queue size to buffer spans for delayed processing.
+    ///
+    /// Overrides both the [`BatchConfig`] set with
+    /// [`BatchSpanProcessorBuilder::with_batch_config`] and the
+    /// `OTEL_BSP_MAX_QUEUE_SIZE` environment variable, regardless of the order
+    /// of the calls.
+    pub fn with_max_queue_size(self, max_queue_size: usize) -> Self {
+        BatchSpanProcessorBuilder {
+            max_queue_size: Some(max_queue_size),
+            ..self
+        }
+    }
+
+    /// Set the maximum number of spans to export in a single batch.
+    ///
+    /// Overrides both the [`BatchConfig`] set with
+    /// [`BatchSpanProcessorBuilder::with_batch_config`] and the
+    /// `OTEL_BSP_MAX_EXPORT_BATCH_SIZE` environment variable, regardless of
+    /// the order of the calls. It is still capped at the maximum queue size.
+    pub fn with_max_export_batch_size(self, max_export_batch_size: usize) -> Self {
+        BatchSpanProcessorBuilder {
+            max_export_batch_size: Some(max_export_batch_size),
+            ..self
+        }
+    }
+
+    /// Set the delay between two consecutive exports of batches.
+    ///
+    /// Overrides both the [`BatchConfig`] set with
+    /// [`BatchSpanProcessorBuilder::with_batch_config`] and the
+    /// `OTEL_BSP_SCHEDULE_DELAY` environment variable, regardless of the order
+    /// of the calls.
+    pub fn with_scheduled_delay(self, scheduled_delay: Duration) -> Self {
+        BatchSpanProcessorBuilder {
+            scheduled_delay: Some(scheduled_delay),
+            ..self
+        }
+    }
+
+    /// Set the maximum duration to export a batch.
+    ///
+    /// Overrides both the [`BatchConfig`] set with
+    /// [`BatchSpanProcessorBuilder::with_batch_config`] and the
+    /// `OTEL_BSP_EXPORT_TIMEOUT` environment variable, regardless of the order
+    /// of the calls.
+    pub fn with_max_export_timeout(self, max_export_timeout: Duration) -> Self {
+        BatchSpanProcessorBuilder {
+            max_export_timeout: Some(max_export_timeout),
+            ..self
+        }
+    }
+
+    /// The batch config with the explicitly set values applied.
+    fn batch_config(&self) -> BatchConfig {
+        let max_queue_size = self.max_queue_size.unwrap_or(self.config.max_queue_size);
+        let max_export_batch_size = self
+            .max_export_batch_size
+            .unwrap_or(self.config.max_export_batch_size);
+
+        BatchConfig {
+            max_queue_size,
+            scheduled_delay: self.scheduled_delay.unwrap_or(self.config.scheduled_delay),
+            max_export_batch_size: min(max_export_batch_size, max_queue_size),
+            max_export_timeout: self
+                .max_export_timeout
+                .unwrap_or(self.config.max_export_timeout),
+            max_concurrent_exports: self.config.max_concurrent_exports,
+            strict_fifo: self.config.strict_fifo,
+            flush_on_error: self.config.flush_on_error,
+            scheduled_delay_jitter: self.config.scheduled_delay_jitter,
+            max_export_batch_bytes: self.config.max_export_batch_bytes,
+            overflow_policy: self.config.overflow_policy,
+        }
+    }
+
     /// Build a batch processor
     pub fn build(self) -> BatchSpanProcessor<R> {
-        BatchSpanProcessor::new(Box::new(self.exporter), self.config, self.runtime)
+        let config = self.batch_config();
+        let mut processor = BatchSpanProcessor::with_on_export(
+            Box::new(self.exporter),
+            config,
+            self.runtime,
+            self.on_export,
+        );
+        processor.on_dropped = self.on_dropped;
+        processor
     }
 }
 
@@ -694,11 +1708,13 @@ where
 mod tests {
     // cargo test trace::span_processor::tests:: --features=testing
     use super::{
-        BatchSpanProcessor, SimpleSpanProcessor, SpanProcessor, OTEL_BSP_EXPORT_TIMEOUT,
+        estimated_size, jittered_delay, BatchSpanProcessor, ExportDeadline, OnEndStatus,
+        OverflowPolicy, SimpleSpanProcessor, SpanProcessor, OTEL_BSP_EXPORT_TIMEOUT,
         OTEL_BSP_MAX_EXPORT_BATCH_SIZE, OTEL_BSP_MAX_QUEUE_SIZE, OTEL_BSP_MAX_QUEUE_SIZE_DEFAULT,
         OTEL_BSP_SCHEDULE_DELAY, OTEL_BSP_SCHEDULE_DELAY_DEFAULT,
     };
     use crate::export::trace::{ExportResult, SpanData, SpanExporter};
+    use crate::resource::SERVICE_NAME;
     use crate::runtime;
     use crate::testing::trace::{
         new_test_export_span_data, new_tokio_test_exporter, InMemorySpanExporterBuilder,
@@ -707,12 +1723,20 @@ mod tests {
         OTEL_BSP_EXPORT_TIMEOUT_DEFAULT, OTEL_BSP_MAX_CONCURRENT_EXPORTS,
         OTEL_BSP_MAX_CONCURRENT_EXPORTS_DEFAULT, OTEL_BSP_MAX_EXPORT_BATCH_SIZE_DEFAULT,
     };
-    use crate::trace::{BatchConfig, BatchConfigBuilder, SpanEvents, SpanLinks};
+    use crate::trace::{
+        BatchConfig, BatchConfigBuilder, DroppedSpanCounts, SpanEvents, SpanLinks, TracerProvider,
+    };
+    use crate::Resource;
     use async_trait::async_trait;
-    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry::trace::{
+        Span as _, SpanContext, SpanId, SpanKind, Status, Tracer as _, TracerProvider as _,
+    };
+    use opentelemetry::{Context, Key, KeyValue, Value};
     use std::fmt::Debug;
     use std::future::Future;
-    use std::time::Duration;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::{Duration, Instant};
 
     #[test]
     fn simple_span_processor_on_end_calls_export() {
@@ -724,6 +1748,27 @@ mod tests {
         let _result = processor.shutdown();
     }
 
+    #[test]
+    fn simple_span_processor_exports_without_runtime() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+
+        // a plain thread, no tokio nor async-std runtime is running
+        let tracer = provider.tracer("test");
+        std::thread::spawn(move || {
+            assert!(tokio::runtime::Handle::try_current().is_err());
+            tracer.in_span("sync", |_cx| {});
+        })
+        .join()
+        .unwrap();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "sync");
+    }
+
     #[test]
     fn simple_span_processor_on_end_skips_export_if_not_sampled() {
         let exporter = InMemorySpanExporterBuilder::new().build();
@@ -747,15 +1792,164 @@ mod tests {
     }
 
     #[test]
-    fn simple_span_processor_shutdown_calls_shutdown() {
+    fn simple_span_processor_export_timeout() {
         let exporter = InMemorySpanExporterBuilder::new().build();
-        let processor = SimpleSpanProcessor::new(Box::new(exporter.clone()));
+        let processor = SimpleSpanProcessor::builder(exporter.clone())
+            .with_export_timeout(Duration::from_millis(100))
+            .build();
         let span_data = new_test_export_span_data();
         processor.on_end(span_data.clone());
-        assert!(!exporter.get_finished_spans().unwrap().is_empty());
-        let _result = processor.shutdown();
-        // Assume shutdown is called by ensuring spans are empty in the exporter
+        assert_eq!(exporter.get_finished_spans().unwrap(), vec![span_data]);
+        assert_eq!(processor.dropped_spans_count(), 0);
+
+        let processor = SimpleSpanProcessor::builder(BlockingExporter {
+            delay_for: Duration::from_secs(60),
+            delay_fn: |_| futures_util::future::pending::<()>(),
+        })
+        .with_export_timeout(Duration::from_millis(100))
+        .build();
+        let start = Instant::now();
+        processor.on_end(new_test_export_span_data());
+        processor.on_end(new_test_export_span_data());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(processor.dropped_spans_count(), 2);
+    }
+
+    #[test]
+    fn simple_span_processor_export_timeout_counts_lock_wait() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = SimpleSpanProcessor::builder(exporter.clone())
+            .with_export_timeout(Duration::from_millis(100))
+            .build();
+
+        // another thread is exporting
+        let exporting = processor.exporter.lock().unwrap();
+        let start = Instant::now();
+        std::thread::scope(|scope| {
+            scope.spawn(|| processor.on_end(new_test_export_span_data()));
+        });
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(exporting);
+
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+        assert_eq!(processor.dropped_spans_count(), 1);
+    }
+
+    #[test]
+    fn simple_span_processor_export_deadline() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = SimpleSpanProcessor::new(Box::new(exporter.clone()));
+        let expired = ExportDeadline::new(Instant::now() - Duration::from_millis(1));
+        {
+            let _guard = Context::current().with_value(expired).attach();
+            processor.on_end(new_test_export_span_data());
+        }
         assert!(exporter.get_finished_spans().unwrap().is_empty());
+        assert_eq!(processor.dropped_spans_count(), 1);
+
+        let processor = SimpleSpanProcessor::new(Box::new(BlockingExporter {
+            delay_for: Duration::from_secs(60),
+            delay_fn: |_| futures_util::future::pending::<()>(),
+        }));
+        let start = Instant::now();
+        let deadline = ExportDeadline::new(start + Duration::from_millis(100));
+        {
+            let _guard = Context::current().with_value(deadline).attach();
+            processor.on_end(new_test_export_span_data());
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(processor.dropped_spans_count(), 1);
+    }
+
+    #[test]
+    fn simple_span_processor_export_deadline_counts_lock_wait() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = SimpleSpanProcessor::new(Box::new(exporter.clone()));
+
+        // another thread is exporting
+        let exporting = processor.exporter.lock().unwrap();
+        let start = Instant::now();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let deadline = ExportDeadline::new(start + Duration::from_millis(100));
+                let _guard = Context::current().with_value(deadline).attach();
+                processor.on_end(new_test_export_span_data());
+            });
+        });
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(exporting);
+
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+        assert_eq!(processor.dropped_spans_count(), 1);
+    }
+
+    #[test]
+    fn simple_span_processor_shutdown_calls_shutdown() {
+        let (exporter, _export_receiver, mut shutdown_receiver) = new_tokio_test_exporter();
+        let processor = SimpleSpanProcessor::new(Box::new(exporter));
+        let _result = processor.shutdown();
+        assert!(shutdown_receiver.try_recv().is_ok());
+    }
+
+    /// Exporter whose exports complete on another thread, after `delay`.
+    #[derive(Debug)]
+    struct DelayedExporter {
+        delay: Duration,
+        exported: Arc<AtomicUsize>,
+        export_started: mpsc::Sender<()>,
+        is_shutdown: Arc<AtomicBool>,
+    }
+
+    impl SpanExporter for DelayedExporter {
+        fn export(
+            &mut self,
+            batch: Vec<SpanData>,
+        ) -> futures_util::future::BoxFuture<'static, ExportResult> {
+            let _ = self.export_started.send(());
+            let (sender, receiver) = futures_channel::oneshot::channel();
+            let delay = self.delay;
+            let exported = self.exported.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                exported.fetch_add(batch.len(), Ordering::SeqCst);
+                let _ = sender.send(());
+            });
+            Box::pin(async move {
+                let _ = receiver.await;
+                Ok(())
+            })
+        }
+
+        fn shutdown(&mut self) {
+            self.is_shutdown.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn simple_span_processor_shutdown_drains_pending_export() {
+        let exported = Arc::new(AtomicUsize::new(0));
+        let is_shutdown = Arc::new(AtomicBool::new(false));
+        let (export_started, started) = mpsc::channel();
+        let processor = Arc::new(SimpleSpanProcessor::new(Box::new(DelayedExporter {
+            delay: Duration::from_millis(100),
+            exported: exported.clone(),
+            export_started,
+            is_shutdown: is_shutdown.clone(),
+        })));
+
+        let ending = processor.clone();
+        let handle = std::thread::spawn(move || ending.on_end(new_test_export_span_data()));
+        started.recv().unwrap();
+
+        assert!(processor.shutdown().is_ok());
+        assert_eq!(exported.load(Ordering::SeqCst), 1);
+        assert!(is_shutdown.load(Ordering::SeqCst));
+        handle.join().unwrap();
+
+        // spans ending after the shutdown are dropped
+        processor.on_end(new_test_export_span_data());
+        assert!(processor.shutdown().is_ok());
+        assert_eq!(exported.load(Ordering::SeqCst), 1);
     }
 
     #[test]
@@ -850,12 +2044,36 @@ mod tests {
             .with_max_export_timeout(Duration::from_millis(10))
             .with_max_concurrent_exports(10)
             .with_max_queue_size(10)
+            .with_strict_fifo(true)
+            .with_flush_on_error(true)
+            .with_scheduled_delay_jitter(0.2)
+            .with_max_export_batch_bytes(4096)
+            .with_overflow_policy(OverflowPolicy::DropOldest)
             .build();
         assert_eq!(batch.max_export_batch_size, 10);
         assert_eq!(batch.scheduled_delay, Duration::from_millis(10));
         assert_eq!(batch.max_export_timeout, Duration::from_millis(10));
         assert_eq!(batch.max_concurrent_exports, 10);
         assert_eq!(batch.max_queue_size, 10);
+        assert!(batch.strict_fifo);
+        assert!(batch.flush_on_error);
+        assert_eq!(batch.scheduled_delay_jitter, 0.2);
+        assert_eq!(batch.max_export_batch_bytes, Some(4096));
+        assert_eq!(batch.overflow_policy, OverflowPolicy::DropOldest);
+    }
+
+    #[test]
+    fn test_jittered_delay() {
+        let delay = Duration::from_secs(5);
+
+        let jittered: Vec<_> = (0..20).map(|_| jittered_delay(delay, 0.1)).collect();
+        assert!(jittered
+            .iter()
+            .all(|d| *d >= Duration::from_millis(4500) && *d <= Duration::from_millis(5500)));
+        // redrawn every time
+        assert!(jittered.iter().any(|d| *d != jittered[0]));
+
+        assert!((0..20).all(|_| jittered_delay(delay, 0.0) == delay));
     }
 
     #[test]
@@ -898,6 +2116,51 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_batch_span_processor_builder_setters_override_env() {
+        let env_vars = vec![
+            (OTEL_BSP_MAX_QUEUE_SIZE, Some("120")),
+            (OTEL_BSP_MAX_EXPORT_BATCH_SIZE, Some("100")),
+            (OTEL_BSP_SCHEDULE_DELAY, Some("2000")),
+            (OTEL_BSP_EXPORT_TIMEOUT, Some("2046")),
+        ];
+        temp_env::with_vars(env_vars, || {
+            let builder = BatchSpanProcessor::builder(
+                InMemorySpanExporterBuilder::new().build(),
+                runtime::Tokio,
+            )
+            .with_max_queue_size(4096)
+            .with_max_export_batch_size(1024)
+            .with_scheduled_delay(Duration::from_millis(10))
+            .with_max_export_timeout(Duration::from_millis(20))
+            // setters win even over a config set afterwards
+            .with_batch_config(
+                BatchConfigBuilder::default()
+                    .with_max_concurrent_exports(3)
+                    .build(),
+            );
+
+            let config = builder.batch_config();
+            assert_eq!(config.max_queue_size, 4096);
+            assert_eq!(config.max_export_batch_size, 1024);
+            assert_eq!(config.scheduled_delay, Duration::from_millis(10));
+            assert_eq!(config.max_export_timeout, Duration::from_millis(20));
+            assert_eq!(config.max_concurrent_exports, 3);
+
+            // unset values still come from the environment
+            let config = BatchSpanProcessor::builder(
+                InMemorySpanExporterBuilder::new().build(),
+                runtime::Tokio,
+            )
+            .with_max_export_batch_size(1024)
+            .batch_config();
+            assert_eq!(config.max_queue_size, 120);
+            // capped at the queue size
+            assert_eq!(config.max_export_batch_size, 120);
+            assert_eq!(config.scheduled_delay, Duration::from_millis(2000));
+        });
+    }
+
     #[tokio::test]
     async fn test_batch_span_processor() {
         let (exporter, mut export_receiver, _shutdown_receiver) = new_tokio_test_exporter();
@@ -992,6 +2255,47 @@ mod tests {
         async_std::task::block_on(timeout_test_std_async(false));
     }
 
+    #[test]
+    #[cfg(feature = "rt-async-std")]
+    fn test_batch_export_async_std() {
+        // the worker runs on the async-std executor, flushes and the shutdown
+        // block the test thread, which must not be one of the executor's
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = BatchSpanProcessor::builder(exporter.clone(), runtime::AsyncStd)
+            .with_scheduled_delay(Duration::from_millis(10))
+            .build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(processor)
+            .build();
+        let tracer = provider.tracer("test");
+
+        // exported on the next scheduled delay tick
+        tracer.in_span("ticked", |_cx| {});
+        async_std::task::block_on(async {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while exporter.get_finished_spans().unwrap().is_empty() && Instant::now() < deadline {
+                async_std::task::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+
+        // exported by a flush
+        tracer.in_span("flushed", |_cx| {});
+        assert!(provider
+            .force_flush()
+            .iter()
+            .all(|(_, result)| result.is_ok()));
+        let names: Vec<_> = exporter
+            .get_finished_spans()
+            .unwrap()
+            .into_iter()
+            .map(|span| span.name)
+            .collect();
+        assert_eq!(names, vec!["ticked", "flushed"]);
+
+        assert!(provider.shutdown().is_ok());
+    }
+
     // If the time_out is true, then the result suppose to ended with timeout.
     // otherwise the exporter should be able to export within time out duration.
     #[cfg(feature = "rt-async-std")]
@@ -1042,4 +2346,557 @@ mod tests {
         let shutdown_res = processor.shutdown();
         assert!(shutdown_res.is_ok());
     }
+
+    #[test]
+    fn test_batch_span_processor_force_flush_with_timeout() {
+        let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let config = BatchConfig {
+            max_export_timeout: Duration::from_secs(60),
+            scheduled_delay: Duration::from_secs(60 * 60 * 24),
+            ..Default::default()
+        };
+        let exporter = BlockingExporter {
+            delay_for: Duration::from_secs(60),
+            delay_fn: tokio::time::sleep,
+        };
+        let processor = tokio_runtime.block_on(async {
+            BatchSpanProcessor::new(Box::new(exporter), config, runtime::Tokio)
+        });
+        processor.on_end(new_test_export_span_data());
+
+        let start = Instant::now();
+        let err = processor
+            .force_flush_with_timeout(Duration::from_millis(100))
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().contains("did not flush within"));
+        tokio_runtime.shutdown_background();
+    }
+
+    #[test]
+    fn test_batch_span_processor_shutdown_with_timeout() {
+        let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let config = BatchConfig {
+            max_export_timeout: Duration::from_secs(60),
+            scheduled_delay: Duration::from_secs(60 * 60 * 24),
+            ..Default::default()
+        };
+        let exporter = BlockingExporter {
+            delay_for: Duration::from_secs(60),
+            delay_fn: tokio::time::sleep,
+        };
+        let processor = tokio_runtime.block_on(async {
+            BatchSpanProcessor::new(Box::new(exporter), config, runtime::Tokio)
+        });
+        processor.on_end(new_test_export_span_data());
+
+        let start = Instant::now();
+        let err = processor
+            .shutdown_with_timeout(Duration::from_millis(100))
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().contains("did not shut down within"));
+        tokio_runtime.shutdown_background();
+    }
+
+    // Exporter whose first exports are the slowest, recording the order in
+    // which batches complete.
+    #[derive(Debug)]
+    struct OrderRecordingExporter {
+        exported: Arc<Mutex<Vec<String>>>,
+        delays: Vec<Duration>,
+    }
+
+    impl SpanExporter for OrderRecordingExporter {
+        fn export(
+            &mut self,
+            batch: Vec<SpanData>,
+        ) -> futures_util::future::BoxFuture<'static, ExportResult> {
+            let delay = self.delays.pop().unwrap_or_default();
+            let exported = self.exported.clone();
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                exported
+                    .lock()
+                    .unwrap()
+                    .extend(batch.into_iter().map(|span| span.name.into_owned()));
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_strict_fifo() {
+        let exported = Arc::new(Mutex::new(Vec::new()));
+        let exporter = OrderRecordingExporter {
+            exported: exported.clone(),
+            // popped from the back, so the first batch is the slowest
+            delays: vec![
+                Duration::from_millis(0),
+                Duration::from_millis(50),
+                Duration::from_millis(100),
+            ],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_export_batch_size(1)
+            .with_max_concurrent_exports(4)
+            .with_strict_fifo(true)
+            .build();
+        let processor =
+            BatchSpanProcessor::new(Box::new(exporter), config, runtime::TokioCurrentThread);
+        for name in ["first", "second", "third"] {
+            let mut span = new_test_export_span_data();
+            span.name = name.into();
+            processor.on_end(span);
+        }
+        assert!(processor.force_flush().is_ok());
+        assert_eq!(*exported.lock().unwrap(), vec!["first", "second", "third"]);
+        assert!(processor.shutdown().is_ok());
+    }
+
+    #[test]
+    fn test_estimated_size() {
+        let mut span = new_test_export_span_data();
+        let base = estimated_size(&span);
+
+        span.attributes.push(KeyValue::new("key", "value"));
+        assert_eq!(estimated_size(&span), base + 3 + 5);
+        span.attributes.push(KeyValue::new("count", 1));
+        assert_eq!(estimated_size(&span), base + 3 + 5 + 5 + 8);
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_max_export_batch_bytes() {
+        let exported = Arc::new(Mutex::new(Vec::new()));
+        let exporter = OrderRecordingExporter {
+            exported: exported.clone(),
+            delays: Vec::new(),
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_export_batch_size(512)
+            .with_max_export_batch_bytes(10_000)
+            .build();
+        let processor =
+            BatchSpanProcessor::new(Box::new(exporter), config, runtime::TokioCurrentThread);
+        let large_span = |name: &'static str| {
+            let mut span = new_test_export_span_data();
+            span.name = name.into();
+            span.attributes
+                .push(KeyValue::new("payload", "x".repeat(4_000)));
+            span
+        };
+
+        processor.on_end(large_span("first"));
+        processor.on_end(large_span("second"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(exported.lock().unwrap().is_empty());
+
+        // over the byte budget, far below the batch size
+        processor.on_end(large_span("third"));
+        processor.on_end(large_span("fourth"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*exported.lock().unwrap(), vec!["first", "second", "third"]);
+
+        // the byte count starts over with the next batch
+        assert!(processor.force_flush().is_ok());
+        assert_eq!(
+            *exported.lock().unwrap(),
+            vec!["first", "second", "third", "fourth"]
+        );
+        assert!(processor.shutdown().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_flush_on_error() {
+        let exported = Arc::new(Mutex::new(Vec::new()));
+        let exporter = OrderRecordingExporter {
+            exported: exported.clone(),
+            delays: Vec::new(),
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_flush_on_error(true)
+            .build();
+        let processor =
+            BatchSpanProcessor::new(Box::new(exporter), config, runtime::TokioCurrentThread);
+
+        let mut span = new_test_export_span_data();
+        span.name = "ok".into();
+        processor.on_end(span);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(exported.lock().unwrap().is_empty());
+
+        let mut span = new_test_export_span_data();
+        span.name = "error".into();
+        span.status = Status::error("failed");
+        processor.on_end(span);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*exported.lock().unwrap(), vec!["ok", "error"]);
+        assert!(processor.shutdown().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_stats() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .build();
+        let processor = BatchSpanProcessor::new(
+            Box::new(exporter.clone()),
+            config,
+            runtime::TokioCurrentThread,
+        );
+        for _ in 0..3 {
+            processor.on_end(new_test_export_span_data());
+        }
+        assert!(processor.force_flush().is_ok());
+
+        let stats = processor.stats().unwrap();
+        assert_eq!(stats.queue_depth, 0);
+        assert_eq!(stats.exported_spans, 3);
+        assert_eq!(stats.exports, 1);
+        assert_eq!(stats.export_failures, 0);
+        assert!(stats.last_export_latency.is_some());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 3);
+
+        assert!(processor.shutdown().is_ok());
+        // give the worker task time to exit and close the channel
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        processor.on_end(new_test_export_span_data());
+        assert_eq!(processor.stats().unwrap().dropped_spans.shutdown, 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_on_export() {
+        let exports = Arc::new(Mutex::new(Vec::new()));
+        let on_export = exports.clone();
+        let exporter = OrderRecordingExporter {
+            exported: Arc::new(Mutex::new(Vec::new())),
+            delays: vec![Duration::from_millis(10); 3],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_export_batch_size(2)
+            .build();
+        let processor = BatchSpanProcessor::builder(exporter, runtime::TokioCurrentThread)
+            .with_batch_config(config)
+            .with_on_export(move |metrics| on_export.lock().unwrap().push(metrics))
+            .build();
+
+        for _ in 0..5 {
+            processor.on_end(new_test_export_span_data());
+        }
+        assert!(processor.force_flush().is_ok());
+
+        let exports = exports.lock().unwrap().clone();
+        let batch_sizes: Vec<_> = exports.iter().map(|metrics| metrics.batch_size).collect();
+        assert_eq!(batch_sizes, vec![2, 2, 1]);
+        assert!(exports.iter().all(|metrics| metrics.success));
+
+        let stats = processor.stats().unwrap();
+        assert_eq!(stats.exports, 3);
+        assert_eq!(stats.exported_spans, 5);
+        assert!(stats.total_export_latency >= Duration::from_millis(30));
+        assert_eq!(
+            stats.total_export_latency,
+            exports
+                .iter()
+                .map(|metrics| metrics.duration)
+                .sum::<Duration>()
+        );
+        assert!(processor.shutdown().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_dropped_spans_count() {
+        let dropped = Arc::new(Mutex::new(0));
+        let on_dropped = dropped.clone();
+        let exporter = OrderRecordingExporter {
+            exported: Arc::new(Mutex::new(Vec::new())),
+            // keeps the worker busy with the first span
+            delays: vec![Duration::from_millis(500)],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_queue_size(2)
+            .with_max_export_batch_size(1)
+            .build();
+        let processor = BatchSpanProcessor::builder(exporter, runtime::TokioCurrentThread)
+            .with_batch_config(config)
+            .with_on_dropped(move |count| *on_dropped.lock().unwrap() += count)
+            .build();
+
+        processor.on_end(new_test_export_span_data());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(processor.dropped_spans_count(), 0);
+
+        // the queue holds 2 spans while the first one is being exported
+        for _ in 0..5 {
+            processor.on_end(new_test_export_span_data());
+        }
+        assert_eq!(processor.dropped_spans_count(), 3);
+        assert_eq!(*dropped.lock().unwrap(), 3);
+    }
+
+    // Names of the spans exported when `spans` end while the queue of 2 spans
+    // is full, with `policy`.
+    async fn saturate_queue(policy: OverflowPolicy, spans: &[&'static str]) -> (Vec<String>, u64) {
+        let exported = Arc::new(Mutex::new(Vec::new()));
+        let exporter = OrderRecordingExporter {
+            exported: exported.clone(),
+            // keeps the worker busy with the first span
+            delays: vec![Duration::from_millis(500)],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_queue_size(2)
+            .with_max_export_batch_size(1)
+            .with_overflow_policy(policy)
+            .build();
+        let processor = BatchSpanProcessor::builder(exporter, runtime::TokioCurrentThread)
+            .with_batch_config(config)
+            .build();
+        let span = |name: &'static str| SpanData {
+            name: name.into(),
+            ..new_test_export_span_data()
+        };
+
+        processor.on_end(span("exporting"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        for name in spans {
+            processor.on_end(span(name));
+        }
+        let dropped = processor.dropped_spans_count();
+        // once the worker is done with the first span and its messages
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert!(processor.force_flush().is_ok());
+        assert_eq!(processor.stats().unwrap().queue_depth, 0);
+
+        let exported = exported.lock().unwrap().clone();
+        (exported, dropped)
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_overflow_drop_newest() {
+        let (exported, dropped) =
+            saturate_queue(OverflowPolicy::DropNewest, &["1", "2", "3", "4", "5"]).await;
+
+        assert_eq!(exported, vec!["exporting", "1", "2"]);
+        assert_eq!(dropped, 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_overflow_drop_oldest() {
+        let (exported, dropped) =
+            saturate_queue(OverflowPolicy::DropOldest, &["1", "2", "3", "4", "5"]).await;
+
+        assert_eq!(exported, vec!["exporting", "4", "5"]);
+        assert_eq!(dropped, 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_drops_reach_provider() {
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let on_processor_drop = dropped.clone();
+        let exporter = OrderRecordingExporter {
+            exported: Arc::new(Mutex::new(Vec::new())),
+            // keeps the worker busy with the first span
+            delays: vec![Duration::from_millis(500)],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_queue_size(2)
+            .with_max_export_batch_size(1)
+            .build();
+        let processor = BatchSpanProcessor::builder(exporter, runtime::TokioCurrentThread)
+            .with_batch_config(config)
+            .build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(processor)
+            .with_on_processor_drop(move |name, status| {
+                on_processor_drop
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), status))
+            })
+            .build();
+        let tracer = provider.tracer("test");
+
+        tracer.start("exporting").end();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(dropped.lock().unwrap().is_empty());
+
+        // the queue holds 2 spans while the first one is being exported
+        for _ in 0..5 {
+            tracer.start("queued").end();
+        }
+        let dropped = dropped.lock().unwrap();
+        assert_eq!(dropped.len(), 3);
+        assert!(dropped.iter().all(|(name, status)| {
+            name.contains("BatchSpanProcessor") && *status == OnEndStatus::QueueFull
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_batch_span_processor_shutdown_retried_when_queue_full() {
+        let exporter = OrderRecordingExporter {
+            exported: Arc::new(Mutex::new(Vec::new())),
+            // keeps the worker busy with the first span
+            delays: vec![Duration::from_millis(500)],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_queue_size(2)
+            .with_max_export_batch_size(1)
+            .build();
+        let processor = BatchSpanProcessor::builder(exporter, runtime::TokioCurrentThread)
+            .with_batch_config(config)
+            .build();
+
+        processor.on_end(new_test_export_span_data());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        processor.on_end(new_test_export_span_data());
+        processor.on_end(new_test_export_span_data());
+
+        // the shutdown message doesn't fit in the queue
+        assert!(processor.shutdown().is_err());
+        assert!(!processor.status().unwrap().is_shutdown);
+
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        assert!(processor.shutdown().is_ok());
+        assert!(processor.status().unwrap().is_shutdown);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_status() {
+        let exporter = OrderRecordingExporter {
+            exported: Arc::new(Mutex::new(Vec::new())),
+            // keeps the worker busy with the first span
+            delays: vec![Duration::from_millis(500)],
+        };
+        let config = BatchConfigBuilder::default()
+            .with_scheduled_delay(Duration::from_secs(60 * 60 * 24))
+            .with_max_export_batch_size(1)
+            .build();
+        let batch = BatchSpanProcessor::builder(exporter, runtime::TokioCurrentThread)
+            .with_batch_config(config)
+            .build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(batch)
+            .with_simple_exporter(InMemorySpanExporterBuilder::new().build())
+            .build();
+        let tracer = provider.tracer("test");
+
+        tracer.start("first").end();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        for _ in 0..3 {
+            tracer.start("queued").end();
+        }
+
+        let status = provider.pipeline_status();
+        assert_eq!(status.len(), 2);
+        assert!(status[0].name.contains("BatchSpanProcessor"));
+        assert!(!status[0].is_shutdown);
+        assert_eq!(status[0].queue_depth, Some(3));
+        assert_eq!(status[0].dropped_spans, Some(DroppedSpanCounts::default()));
+        assert!(status[1].name.contains("SimpleSpanProcessor"));
+        assert!(!status[1].is_shutdown);
+        assert_eq!(status[1].queue_depth, None);
+
+        assert!(provider.shutdown().is_ok());
+        let status = provider.pipeline_status();
+        assert!(status.iter().all(|status| status.is_shutdown));
+        assert_eq!(status[0].queue_depth, Some(0));
+    }
+
+    // Exporter recording the service name of the resource each span was
+    // exported with.
+    #[derive(Debug, Default)]
+    struct ResourceRecordingExporter {
+        resource: Resource,
+        exported: Arc<Mutex<Vec<Option<Value>>>>,
+    }
+
+    impl SpanExporter for ResourceRecordingExporter {
+        fn export(
+            &mut self,
+            batch: Vec<SpanData>,
+        ) -> futures_util::future::BoxFuture<'static, ExportResult> {
+            let service_name = self.resource.get(Key::from_static_str(SERVICE_NAME));
+            self.exported
+                .lock()
+                .unwrap()
+                .extend(batch.iter().map(|_| service_name.clone()));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn set_resource(&mut self, resource: &Resource) {
+            self.resource = resource.clone();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_spans_started_during_construction_carry_resource() {
+        const PROVIDERS: usize = 10;
+        const THREADS: usize = 4;
+        const SPANS: usize = 50;
+
+        let exported = Arc::new(Mutex::new(Vec::new()));
+        let handle = tokio::runtime::Handle::current();
+        let builders: Vec<_> = (0..PROVIDERS)
+            .map(|_| {
+                let exported = exported.clone();
+                let handle = handle.clone();
+                std::thread::spawn(move || {
+                    let _guard = handle.enter();
+                    let exporter = ResourceRecordingExporter {
+                        exported,
+                        ..Default::default()
+                    };
+                    let provider = TracerProvider::builder()
+                        .with_batch_exporter(exporter, runtime::Tokio)
+                        .with_resource(Resource::new(vec![KeyValue::new(
+                            SERVICE_NAME,
+                            "configured",
+                        )]))
+                        .build();
+                    // start spans from other threads as soon as the provider exists
+                    let threads: Vec<_> = (0..THREADS)
+                        .map(|_| {
+                            let tracer = provider.tracer("test");
+                            std::thread::spawn(move || {
+                                for _ in 0..SPANS {
+                                    tracer.in_span("span", |_cx| {});
+                                }
+                            })
+                        })
+                        .collect();
+                    for thread in threads {
+                        thread.join().unwrap();
+                    }
+                    assert!(provider
+                        .force_flush()
+                        .iter()
+                        .all(|(_, result)| result.is_ok()));
+                })
+            })
+            .collect();
+        for thread in builders {
+            thread.join().unwrap();
+        }
+
+        let exported = exported.lock().unwrap();
+        assert_eq!(exported.len(), PROVIDERS * THREADS * SPANS);
+        assert!(exported
+            .iter()
+            .all(|service_name| service_name == &Some(Value::from("configured"))));
+    }
 }
@@ -0,0 +1,361 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{
+    OnEndStatus, ProcessorStats, ProcessorStatus, ProviderTelemetry, Span, SpanProcessor,
+};
+use futures_util::future::{self, BoxFuture};
+use opentelemetry::trace::{Span as _, TraceError, TraceId, TraceResult};
+use opentelemetry::Context;
+use std::time::{Duration, Instant};
+
+/// A [`SpanProcessor`] that splits traces into cohorts and forwards each
+/// trace to the processor of exactly one cohort.
+///
+/// Each cohort is described by the fraction of traces it should receive and
+/// the processor its spans are forwarded to, which makes it possible to send
+/// e.g. 10% of traces to one backend and another 10% to a second backend to
+/// compare sampling strategies on comparable data.
+///
+/// # Determinism
+///
+/// The cohort of a span is derived only from its trace id, using the same
+/// bits of the trace id as [`Sampler::TraceIdRatioBased`]. As a result:
+///
+/// * all spans of a trace are routed to the same cohort, even when the trace
+///   spans several services that configure the same cohorts;
+/// * the assignment does not depend on process, platform or start order, so
+///   replaying the same trace ids always produces the same split.
+///
+/// Cohorts take consecutive, non-overlapping slices of the trace id space in
+/// the order they are given. Fractions are clamped to `[0, 1]`, NaN fractions
+/// count as `0`, and once their sum reaches `1.0` later cohorts never receive
+/// any trace. Traces that fall outside every cohort are not forwarded at all.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{CohortSplittingProcessor, SpanProcessor, TracerProvider};
+///
+/// fn init_tracing(
+///     cohort_a: Box<dyn SpanProcessor>,
+///     cohort_b: Box<dyn SpanProcessor>,
+/// ) -> TracerProvider {
+///     // 10% of traces go to cohort A, another 10% to cohort B, the rest is dropped
+///     TracerProvider::builder()
+///         .with_span_processor(CohortSplittingProcessor::new(vec![
+///             (0.1, cohort_a),
+///             (0.1, cohort_b),
+///         ]))
+///         .build()
+/// }
+/// ```
+///
+/// [`Sampler::TraceIdRatioBased`]: crate::trace::Sampler::TraceIdRatioBased
+#[derive(Debug)]
+pub struct CohortSplittingProcessor {
+    // upper bound of each cohort's slice of the trace id space
+    cohorts: Vec<(u64, Box<dyn SpanProcessor>)>,
+}
+
+impl CohortSplittingProcessor {
+    /// Create a new processor routing traces to `cohorts`.
+    ///
+    /// Each entry is the fraction of traces the cohort should receive and the
+    /// processor those traces are forwarded to.
+    pub fn new(cohorts: Vec<(f64, Box<dyn SpanProcessor>)>) -> Self {
+        let mut upper_bound = 0.0;
+        let cohorts = cohorts
+            .into_iter()
+            .map(|(fraction, processor)| {
+                // `clamp` keeps NaN, which would make the bound 1.0
+                let fraction = if fraction.is_nan() {
+                    0.0
+                } else {
+                    fraction.clamp(0.0, 1.0)
+                };
+                upper_bound = (upper_bound + fraction).min(1.0);
+                ((upper_bound * (1u64 << 63) as f64) as u64, processor)
+            })
+            .collect();
+
+        CohortSplittingProcessor { cohorts }
+    }
+
+    fn cohort(&self, trace_id: TraceId) -> Option<&dyn SpanProcessor> {
+        let bytes = trace_id.to_bytes();
+        let (_, low) = bytes.split_at(8);
+        let rnd_from_trace_id = u64::from_be_bytes(low.try_into().unwrap()) >> 1;
+
+        self.cohorts
+            .iter()
+            .find(|(upper_bound, _)| rnd_from_trace_id < *upper_bound)
+            .map(|(_, processor)| processor.as_ref())
+    }
+
+    fn for_each(&self, f: impl Fn(&dyn SpanProcessor) -> TraceResult<()>) -> TraceResult<()> {
+        let errs: Vec<_> = self
+            .cohorts
+            .iter()
+            .filter_map(|(_, processor)| f(processor.as_ref()).err())
+            .collect();
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(TraceError::Other(format!("{errs:?}").into()))
+        }
+    }
+}
+
+impl SpanProcessor for CohortSplittingProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        let trace_id = span.span_context().trace_id();
+        if let Some(processor) = self.cohort(trace_id) {
+            processor.on_start(span, cx);
+        }
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        match self.cohort(span.span_context.trace_id()) {
+            Some(processor) => processor.try_on_end(span),
+            None => OnEndStatus::Accepted,
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.for_each(|processor| processor.force_flush())
+    }
+
+    /// Flushes the processors one after the other, each with what is left of
+    /// `timeout`.
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        let deadline = Instant::now() + timeout;
+        self.for_each(|processor| {
+            processor.force_flush_with_timeout(deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.for_each(|processor| processor.shutdown())
+    }
+
+    /// Shuts the processors down one after the other, each with what is left
+    /// of `timeout`.
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        let deadline = Instant::now() + timeout;
+        self.for_each(|processor| {
+            processor.shutdown_with_timeout(deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        Box::pin(async move {
+            let errs: Vec<_> =
+                future::join_all(self.cohorts.iter().map(|(_, processor)| processor.warmup()))
+                    .await
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .collect();
+
+            if errs.is_empty() {
+                Ok(())
+            } else {
+                Err(TraceError::Other(format!("{errs:?}").into()))
+            }
+        })
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        for (_, processor) in &mut self.cohorts {
+            processor.set_resource(resource);
+        }
+    }
+
+    /// The sum of the stats of the processors reporting stats, if any.
+    fn stats(&self) -> Option<ProcessorStats> {
+        let stats: Vec<_> = self.cohorts.iter().map(|(_, p)| p.stats()).collect();
+        if stats.iter().all(Option::is_none) {
+            None
+        } else {
+            Some(ProviderTelemetry::new(stats).total)
+        }
+    }
+
+    fn reset_stats(&self) {
+        for (_, processor) in &self.cohorts {
+            processor.reset_stats();
+        }
+    }
+
+    /// The combined status of the processors reporting one, shut down if any
+    /// of them is.
+    fn status(&self) -> Option<ProcessorStatus> {
+        ProcessorStatus::combined(
+            self.name(),
+            self.cohorts.iter().map(|(_, processor)| processor.status()),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::CohortSplittingProcessor;
+    use crate::export::trace::SpanData;
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use crate::trace::{
+        DroppedSpanCounts, ProcessorStatus, SimpleSpanProcessor, Span, SpanProcessor,
+    };
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceResult, TraceState};
+    use opentelemetry::Context;
+
+    /// Reports a fixed status.
+    #[derive(Debug)]
+    struct StatusProcessor(ProcessorStatus);
+
+    impl SpanProcessor for StatusProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn status(&self) -> Option<ProcessorStatus> {
+            Some(self.0.clone())
+        }
+    }
+
+    fn span_with_trace_id(trace_id: u128) -> crate::export::trace::SpanData {
+        let mut span = new_test_export_span_data();
+        span.span_context = SpanContext::new(
+            TraceId::from(trace_id),
+            SpanId::from(1),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        span
+    }
+
+    #[test]
+    fn routes_traces_to_a_single_stable_cohort() {
+        let exporter_a = InMemorySpanExporterBuilder::new().build();
+        let exporter_b = InMemorySpanExporterBuilder::new().build();
+        let processor = CohortSplittingProcessor::new(vec![
+            (
+                0.5,
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter_a.clone()))),
+            ),
+            (
+                0.25,
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter_b.clone()))),
+            ),
+        ]);
+
+        // the cohort is picked from the upper 63 bits of the low half of the trace id
+        let cohort_a = 1u128 << 64;
+        let cohort_b = (1u128 << 64) | (0xA000_0000_0000_0000u128);
+        let no_cohort = (1u128 << 64) | (0xE000_0000_0000_0000u128);
+
+        for trace_id in [cohort_a, cohort_b, no_cohort, cohort_a] {
+            processor.on_end(span_with_trace_id(trace_id));
+        }
+
+        let finished_a = exporter_a.get_finished_spans().unwrap();
+        assert_eq!(finished_a.len(), 2);
+        assert!(finished_a
+            .iter()
+            .all(|span| span.span_context.trace_id() == TraceId::from(cohort_a)));
+
+        let finished_b = exporter_b.get_finished_spans().unwrap();
+        assert_eq!(finished_b.len(), 1);
+        assert_eq!(
+            finished_b[0].span_context.trace_id(),
+            TraceId::from(cohort_b)
+        );
+    }
+
+    #[test]
+    fn fractions_are_clamped() {
+        let exporter_a = InMemorySpanExporterBuilder::new().build();
+        let exporter_b = InMemorySpanExporterBuilder::new().build();
+        let processor = CohortSplittingProcessor::new(vec![
+            (
+                -1.0,
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter_a.clone()))),
+            ),
+            (
+                2.0,
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter_b.clone()))),
+            ),
+        ]);
+
+        processor.on_end(span_with_trace_id(u128::MAX));
+        processor.on_end(span_with_trace_id(1));
+
+        assert!(exporter_a.get_finished_spans().unwrap().is_empty());
+        assert_eq!(exporter_b.get_finished_spans().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn nan_fractions_count_as_zero() {
+        let exporter_a = InMemorySpanExporterBuilder::new().build();
+        let exporter_b = InMemorySpanExporterBuilder::new().build();
+        let processor = CohortSplittingProcessor::new(vec![
+            (
+                f64::NAN,
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter_a.clone()))),
+            ),
+            (
+                0.5,
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter_b.clone()))),
+            ),
+        ]);
+
+        processor.on_end(span_with_trace_id(1));
+        processor.on_end(span_with_trace_id(u128::MAX));
+
+        assert!(exporter_a.get_finished_spans().unwrap().is_empty());
+        assert_eq!(exporter_b.get_finished_spans().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn combines_the_status_of_cohorts() {
+        let status = |is_shutdown, queue_depth, queue_full| ProcessorStatus {
+            queue_depth: Some(queue_depth),
+            dropped_spans: Some(DroppedSpanCounts {
+                queue_full,
+                shutdown: 0,
+            }),
+            ..ProcessorStatus::new("inner", is_shutdown)
+        };
+        let processor = CohortSplittingProcessor::new(vec![
+            (0.5, Box::new(StatusProcessor(status(false, 2, 1)))),
+            (0.5, Box::new(StatusProcessor(status(true, 3, 4)))),
+        ]);
+
+        let combined = processor.status().unwrap();
+        assert_eq!(combined.name, processor.name());
+        assert!(combined.is_shutdown);
+        assert_eq!(combined.queue_depth, Some(5));
+        assert_eq!(combined.dropped_spans.unwrap().queue_full, 5);
+
+        let without_status = CohortSplittingProcessor::new(vec![(
+            1.0,
+            Box::new(SimpleSpanProcessor::new(Box::new(
+                InMemorySpanExporterBuilder::new().build(),
+            ))),
+        )]);
+        assert!(without_status.status().is_none());
+        assert!(without_status.stats().is_none());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::{Span as _, TraceResult};
+use opentelemetry::{Context, KeyValue};
+use std::fmt;
+use std::time::Duration;
+
+type ContextAttributes = dyn Fn(&Context) -> Vec<KeyValue> + Send + Sync;
+
+/// A [`SpanProcessor`] adding attributes to every span when it starts, before
+/// forwarding it to the processor it wraps.
+///
+/// Attributes are either fixed, e.g. the `deployment.environment`, or
+/// computed from the parent [`Context`] of the span, e.g. to add a tenant id
+/// stored in it. Fixed attributes are added first. Every other method is
+/// forwarded unchanged.
+///
+/// # Span limits
+///
+/// Attributes are added through [`Span::set_attribute`], so they count
+/// towards [`SpanLimits::max_attributes_per_span`] like any other attribute.
+/// They're added after the attributes provided when the span is started and
+/// before the ones set while it's recording. Attributes over the limit are
+/// dropped and counted in the span's dropped attributes count.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::KeyValue;
+/// use opentelemetry_sdk::trace::{AttributeEnrichingSpanProcessor, SpanProcessor, TracerProvider};
+///
+/// fn init_tracing(processor: Box<dyn SpanProcessor>) -> TracerProvider {
+///     TracerProvider::builder()
+///         .with_span_processor(AttributeEnrichingSpanProcessor::new(
+///             processor,
+///             vec![KeyValue::new("deployment.environment", "production")],
+///         ))
+///         .build()
+/// }
+/// ```
+///
+/// [`Span::set_attribute`]: opentelemetry::trace::Span::set_attribute
+/// [`SpanLimits::max_attributes_per_span`]: crate::trace::SpanLimits::max_attributes_per_span
+pub struct AttributeEnrichingSpanProcessor {
+    inner: Box<dyn SpanProcessor>,
+    attributes: Vec<KeyValue>,
+    context_attributes: Option<Box<ContextAttributes>>,
+}
+
+impl fmt::Debug for AttributeEnrichingSpanProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttributeEnrichingSpanProcessor")
+            .field("inner", &self.inner)
+            .field("attributes", &self.attributes)
+            .finish()
+    }
+}
+
+impl AttributeEnrichingSpanProcessor {
+    /// Create a processor adding `attributes` to every span before
+    /// forwarding it to `inner`.
+    pub fn new(inner: Box<dyn SpanProcessor>, attributes: Vec<KeyValue>) -> Self {
+        AttributeEnrichingSpanProcessor {
+            inner,
+            attributes,
+            context_attributes: None,
+        }
+    }
+
+    /// Also add the attributes returned by `f` for the parent context of
+    /// each span, after the fixed attributes.
+    pub fn with_context_attributes<F>(self, f: F) -> Self
+    where
+        F: Fn(&Context) -> Vec<KeyValue> + Send + Sync + 'static,
+    {
+        AttributeEnrichingSpanProcessor {
+            context_attributes: Some(Box::new(f)),
+            ..self
+        }
+    }
+}
+
+impl SpanProcessor for AttributeEnrichingSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        if span.is_recording() {
+            for attribute in &self.attributes {
+                span.set_attribute(attribute.clone());
+            }
+            if let Some(context_attributes) = &self.context_attributes {
+                for attribute in context_attributes(cx) {
+                    span.set_attribute(attribute);
+                }
+            }
+        }
+        self.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.inner.on_end(span)
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        self.inner.try_on_end(span)
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.force_flush_with_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.inner.shutdown()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::AttributeEnrichingSpanProcessor;
+    use crate::testing::trace::InMemorySpanExporterBuilder;
+    use crate::trace::{config, SimpleSpanProcessor, TracerProvider};
+    use opentelemetry::trace::{Tracer, TracerProvider as _};
+    use opentelemetry::{Context, KeyValue};
+
+    #[derive(Debug)]
+    struct TenantId(&'static str);
+
+    #[test]
+    fn attributes_reach_exported_span() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(
+                AttributeEnrichingSpanProcessor::new(
+                    Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+                    vec![KeyValue::new("deployment.environment", "test")],
+                )
+                .with_context_attributes(|cx| {
+                    cx.get::<TenantId>()
+                        .map(|tenant| vec![KeyValue::new("tenant.id", tenant.0)])
+                        .unwrap_or_default()
+                }),
+            )
+            .build();
+        let tracer = provider.tracer("test");
+
+        let cx = Context::new().with_value(TenantId("acme"));
+        drop(tracer.start_with_context("span", &cx));
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(
+            finished[0].attributes,
+            vec![
+                KeyValue::new("deployment.environment", "test"),
+                KeyValue::new("tenant.id", "acme"),
+            ]
+        );
+    }
+
+    #[test]
+    fn attributes_count_towards_limit() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(AttributeEnrichingSpanProcessor::new(
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+                vec![KeyValue::new("a", 1), KeyValue::new("b", 2)],
+            ))
+            .with_config(config().with_max_attributes_per_span(1))
+            .build();
+
+        drop(provider.tracer("test").start("span"));
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished[0].attributes, vec![KeyValue::new("a", 1)]);
+        assert_eq!(finished[0].dropped_attributes_count, 1);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::TraceResult;
+use opentelemetry::Context;
+use std::time::Duration;
+
+/// A [`SpanProcessor`] sampling down the events and links of ended spans
+/// before forwarding them to the processor it wraps, keeping the payload of
+/// spans with many events in check.
+///
+/// Events are kept evenly spread over the span, always including the first
+/// and last ones which are usually the most useful. Links are truncated,
+/// keeping the first ones. Removed events and links are added to the
+/// `dropped_count` of [`SpanData::events`] and [`SpanData::links`]
+/// respectively. Every other method is forwarded unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{EventSamplingSpanProcessor, SpanProcessor, TracerProvider};
+///
+/// fn init_tracing(processor: Box<dyn SpanProcessor>) -> TracerProvider {
+///     TracerProvider::builder()
+///         .with_span_processor(
+///             EventSamplingSpanProcessor::new(processor)
+///                 .with_event_ratio(0.1)
+///                 .with_max_events(32)
+///                 .with_max_links(8),
+///         )
+///         .build()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EventSamplingSpanProcessor {
+    inner: Box<dyn SpanProcessor>,
+    event_ratio: f64,
+    max_events: Option<usize>,
+    max_links: Option<usize>,
+}
+
+impl EventSamplingSpanProcessor {
+    /// Create a processor forwarding spans to `inner`, with all of their
+    /// events and links until limits are set.
+    pub fn new(inner: Box<dyn SpanProcessor>) -> Self {
+        EventSamplingSpanProcessor {
+            inner,
+            event_ratio: 1.0,
+            max_events: None,
+            max_links: None,
+        }
+    }
+
+    /// Keep this fraction of the events of each span, rounded up. The first
+    /// and last events are kept whatever the ratio.
+    ///
+    /// The ratio is clamped between `0.0` and `1.0`, NaN keeps every event.
+    pub fn with_event_ratio(self, ratio: f64) -> Self {
+        EventSamplingSpanProcessor {
+            event_ratio: if ratio.is_nan() {
+                1.0
+            } else {
+                ratio.clamp(0.0, 1.0)
+            },
+            ..self
+        }
+    }
+
+    /// Keep at most `max_events` events of each span.
+    pub fn with_max_events(self, max_events: usize) -> Self {
+        EventSamplingSpanProcessor {
+            max_events: Some(max_events),
+            ..self
+        }
+    }
+
+    /// Keep at most the first `max_links` links of each span.
+    pub fn with_max_links(self, max_links: usize) -> Self {
+        EventSamplingSpanProcessor {
+            max_links: Some(max_links),
+            ..self
+        }
+    }
+
+    fn sample_events(&self, span: &mut SpanData) {
+        let len = span.events.events.len();
+        let by_ratio = ((len as f64 * self.event_ratio).ceil() as usize).max(len.min(2));
+        let keep = self.max_events.map_or(by_ratio, |max| by_ratio.min(max));
+        if keep >= len {
+            return;
+        }
+
+        let events = std::mem::take(&mut span.events.events);
+        span.events.events = match keep {
+            0 => Vec::new(),
+            1 => events.into_iter().take(1).collect(),
+            _ => {
+                // indices spread evenly from the first to the last event
+                let mut kept = (0..keep).map(|i| i * (len - 1) / (keep - 1)).peekable();
+                events
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(index, event)| kept.next_if_eq(&index).map(|_| event))
+                    .collect()
+            }
+        };
+        span.events.dropped_count = span
+            .events
+            .dropped_count
+            .saturating_add((len - span.events.events.len()) as u32);
+    }
+
+    fn sample_links(&self, span: &mut SpanData) {
+        let len = span.links.links.len();
+        match self.max_links {
+            Some(max_links) if max_links < len => {
+                span.links.links.truncate(max_links);
+                span.links.dropped_count = span
+                    .links
+                    .dropped_count
+                    .saturating_add((len - max_links) as u32);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl SpanProcessor for EventSamplingSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, mut span: SpanData) -> OnEndStatus {
+        self.sample_events(&mut span);
+        self.sample_links(&mut span);
+        self.inner.try_on_end(span)
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.force_flush_with_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.inner.shutdown()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::EventSamplingSpanProcessor;
+    use crate::export::trace::SpanData;
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use crate::trace::{SimpleSpanProcessor, SpanProcessor};
+    use opentelemetry::trace::{Event, Link, SpanContext};
+
+    fn span_with(events: usize, links: usize) -> SpanData {
+        let mut span = new_test_export_span_data();
+        span.events.events = (0..events)
+            .map(|i| Event::with_name(format!("event {i}")))
+            .collect();
+        span.links.links = (0..links)
+            .map(|_| Link::with_context(SpanContext::empty_context()))
+            .collect();
+        span
+    }
+
+    fn export<F>(configure: F, span: SpanData) -> SpanData
+    where
+        F: FnOnce(EventSamplingSpanProcessor) -> EventSamplingSpanProcessor,
+    {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = configure(EventSamplingSpanProcessor::new(Box::new(
+            SimpleSpanProcessor::new(Box::new(exporter.clone())),
+        )));
+        processor.on_end(span);
+        exporter.get_finished_spans().unwrap().remove(0)
+    }
+
+    fn event_names(span: &SpanData) -> Vec<String> {
+        span.events
+            .iter()
+            .map(|event| event.name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn max_events_keeps_first_and_last() {
+        let span = export(|p| p.with_max_events(3), span_with(10, 0));
+
+        assert_eq!(event_names(&span), vec!["event 0", "event 4", "event 9"]);
+        assert_eq!(span.events.dropped_count, 7);
+    }
+
+    #[test]
+    fn event_ratio_keeps_first_and_last() {
+        let span = export(|p| p.with_event_ratio(0.1), span_with(10, 0));
+
+        assert_eq!(event_names(&span), vec!["event 0", "event 9"]);
+        assert_eq!(span.events.dropped_count, 8);
+    }
+
+    #[test]
+    fn max_links_truncates_links() {
+        let span = export(|p| p.with_max_links(2), span_with(2, 5));
+
+        assert_eq!(span.links.len(), 2);
+        assert_eq!(span.links.dropped_count, 3);
+        assert_eq!(event_names(&span), vec!["event 0", "event 1"]);
+        assert_eq!(span.events.dropped_count, 0);
+    }
+}
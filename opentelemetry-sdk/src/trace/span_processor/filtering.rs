@@ -0,0 +1,170 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::TraceResult;
+use opentelemetry::Context;
+use std::fmt;
+use std::time::Duration;
+
+/// A [`SpanProcessor`] that only forwards ended spans matching a predicate to
+/// the processor it wraps.
+///
+/// The predicate is called once for each span in [`on_end`], after the span
+/// has ended so that all of its attributes and its status are available.
+/// Spans for which it returns `false` are dropped and never reach the wrapped
+/// processor, and in turn its exporter. Every other method, including
+/// [`on_start`], is forwarded unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{FilteringSpanProcessor, SpanProcessor, TracerProvider};
+///
+/// fn init_tracing(processor: Box<dyn SpanProcessor>) -> TracerProvider {
+///     // don't export health check requests
+///     TracerProvider::builder()
+///         .with_span_processor(FilteringSpanProcessor::new(processor, |span| {
+///             !span.name.starts_with("GET /health")
+///         }))
+///         .build()
+/// }
+/// ```
+///
+/// [`on_end`]: SpanProcessor::on_end
+/// [`on_start`]: SpanProcessor::on_start
+pub struct FilteringSpanProcessor {
+    inner: Box<dyn SpanProcessor>,
+    predicate: Box<dyn Fn(&SpanData) -> bool + Send + Sync>,
+}
+
+impl fmt::Debug for FilteringSpanProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteringSpanProcessor")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl FilteringSpanProcessor {
+    /// Create a processor forwarding to `inner` the spans for which
+    /// `predicate` returns `true`.
+    pub fn new<F>(inner: Box<dyn SpanProcessor>, predicate: F) -> Self
+    where
+        F: Fn(&SpanData) -> bool + Send + Sync + 'static,
+    {
+        FilteringSpanProcessor {
+            inner,
+            predicate: Box::new(predicate),
+        }
+    }
+}
+
+impl SpanProcessor for FilteringSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        if (self.predicate)(&span) {
+            self.inner.try_on_end(span)
+        } else {
+            OnEndStatus::Accepted
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.force_flush_with_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.inner.shutdown()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::FilteringSpanProcessor;
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use crate::trace::{SimpleSpanProcessor, SpanProcessor, TracerProvider};
+    use opentelemetry::trace::{Tracer, TracerProvider as _};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn drops_health_checks() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = FilteringSpanProcessor::new(
+            Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+            |span| !span.name.starts_with("GET /health"),
+        );
+
+        for name in ["GET /health", "GET /users", "GET /healthz"] {
+            let mut span = new_test_export_span_data();
+            span.name = name.into();
+            processor.on_end(span);
+        }
+        assert!(processor.force_flush().is_ok());
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "GET /users");
+    }
+
+    #[test]
+    fn predicate_is_only_called_on_end() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let predicate_calls = calls.clone();
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(FilteringSpanProcessor::new(
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+                move |_| {
+                    predicate_calls.fetch_add(1, Ordering::SeqCst);
+                    true
+                },
+            ))
+            .build();
+
+        let span = provider.tracer("test").start("span");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        drop(span);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+    }
+}
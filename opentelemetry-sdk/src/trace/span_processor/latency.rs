@@ -0,0 +1,252 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::{Span as _, SpanId, TraceResult};
+use opentelemetry::Context;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Default number of started spans a [`LatencySpanProcessor`] keeps track of.
+const DEFAULT_MAX_PENDING_SPANS: usize = 2048;
+
+/// A [`SpanProcessor`] only forwarding to the processor it wraps the spans
+/// which took at least a given time, approximating latency based retention
+/// without a collector.
+///
+/// Sampling happens when a span starts, before its duration is known, so
+/// spans have to be sampled by the provider's sampler first. This processor
+/// then keeps track of every span it sees starting and, when it ends, drops
+/// it if its duration is below the threshold.
+///
+/// # Pending spans
+///
+/// At most [`with_max_pending_spans`] started spans are tracked at once,
+/// `2048` by default. Spans starting while that many are pending are not
+/// tracked and are dropped when they end, whatever their duration, as are
+/// spans ending after [`shutdown`], which forgets the spans still pending.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{LatencySpanProcessor, SpanProcessor, TracerProvider};
+/// use std::time::Duration;
+///
+/// fn init_tracing(processor: Box<dyn SpanProcessor>) -> TracerProvider {
+///     // only export requests slower than 500ms
+///     TracerProvider::builder()
+///         .with_span_processor(LatencySpanProcessor::new(
+///             processor,
+///             Duration::from_millis(500),
+///         ))
+///         .build()
+/// }
+/// ```
+///
+/// [`with_max_pending_spans`]: LatencySpanProcessor::with_max_pending_spans
+/// [`shutdown`]: SpanProcessor::shutdown
+pub struct LatencySpanProcessor {
+    inner: Box<dyn SpanProcessor>,
+    threshold: Duration,
+    max_pending_spans: usize,
+    pending: Mutex<HashSet<SpanId>>,
+}
+
+impl fmt::Debug for LatencySpanProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencySpanProcessor")
+            .field("inner", &self.inner)
+            .field("threshold", &self.threshold)
+            .field("max_pending_spans", &self.max_pending_spans)
+            .finish()
+    }
+}
+
+impl LatencySpanProcessor {
+    /// Create a processor forwarding to `inner` the spans lasting at least
+    /// `threshold`.
+    pub fn new(inner: Box<dyn SpanProcessor>, threshold: Duration) -> Self {
+        LatencySpanProcessor {
+            inner,
+            threshold,
+            max_pending_spans: DEFAULT_MAX_PENDING_SPANS,
+            pending: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Set the maximum number of started spans tracked at once.
+    pub fn with_max_pending_spans(self, max_pending_spans: usize) -> Self {
+        LatencySpanProcessor {
+            max_pending_spans,
+            ..self
+        }
+    }
+
+    /// Returns the number of spans which started but haven't ended yet.
+    pub fn pending_spans(&self) -> usize {
+        self.pending.lock().map_or(0, |pending| pending.len())
+    }
+}
+
+impl SpanProcessor for LatencySpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        if let Ok(mut pending) = self.pending.lock() {
+            if pending.len() < self.max_pending_spans {
+                pending.insert(span.span_context().span_id());
+            }
+        }
+        self.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        let was_pending = self.pending.lock().map_or(false, |mut pending| {
+            pending.remove(&span.span_context.span_id())
+        });
+        if !was_pending {
+            return OnEndStatus::Accepted;
+        }
+
+        let duration = span
+            .end_time
+            .duration_since(span.start_time)
+            .unwrap_or_default();
+        if duration >= self.threshold {
+            self.inner.try_on_end(span)
+        } else {
+            OnEndStatus::Accepted
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.force_flush_with_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.clear();
+        }
+        self.inner.shutdown()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.clear();
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::LatencySpanProcessor;
+    use crate::testing::trace::{InMemorySpanExporter, InMemorySpanExporterBuilder};
+    use crate::trace::{SimpleSpanProcessor, SpanProcessor, TracerProvider};
+    use opentelemetry::trace::{Span, Tracer, TracerProvider as _};
+    use std::time::{Duration, SystemTime};
+
+    fn latency_processor(exporter: &InMemorySpanExporter) -> LatencySpanProcessor {
+        LatencySpanProcessor::new(
+            Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+            Duration::from_millis(500),
+        )
+    }
+
+    #[test]
+    fn only_exports_slow_spans() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(latency_processor(&exporter))
+            .build();
+        let tracer = provider.tracer("test");
+
+        let start = SystemTime::now();
+        for (name, duration) in [("fast", 10), ("slow", 1500)] {
+            let mut span = tracer
+                .span_builder(name)
+                .with_start_time(start)
+                .start(&tracer);
+            span.end_with_timestamp(start + Duration::from_millis(duration));
+        }
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "slow");
+    }
+
+    #[test]
+    fn bounds_pending_spans() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(latency_processor(&exporter).with_max_pending_spans(1))
+            .build();
+        let tracer = provider.tracer("test");
+
+        let start = SystemTime::now();
+        let mut tracked = tracer
+            .span_builder("tracked")
+            .with_start_time(start)
+            .start(&tracer);
+        let mut untracked = tracer
+            .span_builder("untracked")
+            .with_start_time(start)
+            .start(&tracer);
+        untracked.end_with_timestamp(start + Duration::from_secs(1));
+        tracked.end_with_timestamp(start + Duration::from_secs(1));
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "tracked");
+    }
+
+    #[test]
+    fn shutdown_drops_pending_spans() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = latency_processor(&exporter);
+        let provider = TracerProvider::builder().build();
+        let tracer = provider.tracer("test");
+
+        let start = SystemTime::now();
+        let mut span = tracer
+            .span_builder("never ended")
+            .with_start_time(start)
+            .start(&tracer);
+        processor.on_start(&mut span, &opentelemetry::Context::new());
+        assert_eq!(processor.pending_spans(), 1);
+
+        assert!(processor.shutdown().is_ok());
+        assert_eq!(processor.pending_spans(), 0);
+    }
+}
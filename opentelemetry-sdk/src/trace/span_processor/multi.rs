@@ -0,0 +1,273 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{
+    OnEndStatus, ProcessorStats, ProcessorStatus, ProviderTelemetry, Span, SpanProcessor,
+};
+use futures_util::future::{self, BoxFuture};
+use opentelemetry::trace::{TraceError, TraceResult};
+use opentelemetry::Context;
+use std::time::{Duration, Instant};
+
+/// A [`SpanProcessor`] forwarding every span to several processors, e.g. to
+/// export the same spans to two backends.
+///
+/// Processors are called in the order they are given. Each of them gets its
+/// own copy of every ended span, so the span is cloned once per processor but
+/// the last one. The combined processor can itself be wrapped, e.g. by a
+/// [`FilteringSpanProcessor`] to apply the same filter to every backend.
+///
+/// [`force_flush`] and [`shutdown`] are called on every processor, even when
+/// some of them fail, and fail if any processor failed.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{MultiSpanProcessor, SpanProcessor, TracerProvider};
+///
+/// fn init_tracing(
+///     primary: Box<dyn SpanProcessor>,
+///     secondary: Box<dyn SpanProcessor>,
+/// ) -> TracerProvider {
+///     TracerProvider::builder()
+///         .with_span_processor(MultiSpanProcessor::new(vec![primary, secondary]))
+///         .build()
+/// }
+/// ```
+///
+/// [`FilteringSpanProcessor`]: crate::trace::FilteringSpanProcessor
+/// [`force_flush`]: SpanProcessor::force_flush
+/// [`shutdown`]: SpanProcessor::shutdown
+#[derive(Debug)]
+pub struct MultiSpanProcessor {
+    processors: Vec<Box<dyn SpanProcessor>>,
+}
+
+impl MultiSpanProcessor {
+    /// Create a processor forwarding every span to each of `processors`.
+    pub fn new(processors: Vec<Box<dyn SpanProcessor>>) -> Self {
+        MultiSpanProcessor { processors }
+    }
+
+    fn for_each(&self, f: impl Fn(&dyn SpanProcessor) -> TraceResult<()>) -> TraceResult<()> {
+        let errs: Vec<_> = self
+            .processors
+            .iter()
+            .filter_map(|processor| f(processor.as_ref()).err())
+            .collect();
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(TraceError::Other(format!("{errs:?}").into()))
+        }
+    }
+}
+
+impl SpanProcessor for MultiSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        for processor in &self.processors {
+            processor.on_start(span, cx);
+        }
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    /// Returns the status of the first processor that dropped the span, if
+    /// any.
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        let mut status = OnEndStatus::Accepted;
+        let mut record = |processor_status| {
+            if status == OnEndStatus::Accepted {
+                status = processor_status;
+            }
+        };
+        if let Some((last, rest)) = self.processors.split_last() {
+            for processor in rest {
+                record(processor.try_on_end(span.clone()));
+            }
+            record(last.try_on_end(span));
+        }
+        status
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.for_each(|processor| processor.force_flush())
+    }
+
+    /// Flushes the processors one after the other, each with what is left of
+    /// `timeout`.
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        let deadline = Instant::now() + timeout;
+        self.for_each(|processor| {
+            processor.force_flush_with_timeout(deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.for_each(|processor| processor.shutdown())
+    }
+
+    /// Shuts the processors down one after the other, each with what is left
+    /// of `timeout`.
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        let deadline = Instant::now() + timeout;
+        self.for_each(|processor| {
+            processor.shutdown_with_timeout(deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        Box::pin(async move {
+            let errs: Vec<_> =
+                future::join_all(self.processors.iter().map(|processor| processor.warmup()))
+                    .await
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .collect();
+
+            if errs.is_empty() {
+                Ok(())
+            } else {
+                Err(TraceError::Other(format!("{errs:?}").into()))
+            }
+        })
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        for processor in &mut self.processors {
+            processor.set_resource(resource);
+        }
+    }
+
+    /// The sum of the stats of the processors reporting stats, if any.
+    fn stats(&self) -> Option<ProcessorStats> {
+        let stats: Vec<_> = self.processors.iter().map(|p| p.stats()).collect();
+        if stats.iter().all(Option::is_none) {
+            None
+        } else {
+            Some(ProviderTelemetry::new(stats).total)
+        }
+    }
+
+    fn reset_stats(&self) {
+        for processor in &self.processors {
+            processor.reset_stats();
+        }
+    }
+
+    /// The combined status of the processors reporting one, shut down if any
+    /// of them is.
+    fn status(&self) -> Option<ProcessorStatus> {
+        ProcessorStatus::combined(
+            self.name(),
+            self.processors.iter().map(|processor| processor.status()),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::MultiSpanProcessor;
+    use crate::export::trace::SpanData;
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use crate::trace::{
+        DroppedSpanCounts, ProcessorStatus, SimpleSpanProcessor, Span, SpanProcessor,
+    };
+    use opentelemetry::trace::{TraceError, TraceResult};
+    use opentelemetry::Context;
+
+    #[derive(Debug)]
+    struct FailingShutdownProcessor;
+
+    impl SpanProcessor for FailingShutdownProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Err(TraceError::from("shutdown failed"))
+        }
+    }
+
+    /// Reports a fixed status.
+    #[derive(Debug)]
+    struct StatusProcessor(ProcessorStatus);
+
+    impl SpanProcessor for StatusProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn status(&self) -> Option<ProcessorStatus> {
+            Some(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn on_end_reaches_every_processor() {
+        let first = InMemorySpanExporterBuilder::new().build();
+        let second = InMemorySpanExporterBuilder::new().build();
+        let processor = MultiSpanProcessor::new(vec![
+            Box::new(SimpleSpanProcessor::new(Box::new(first.clone()))),
+            Box::new(SimpleSpanProcessor::new(Box::new(second.clone()))),
+        ]);
+
+        processor.on_end(new_test_export_span_data());
+
+        assert_eq!(first.get_finished_spans().unwrap().len(), 1);
+        assert_eq!(second.get_finished_spans().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn shutdown_error_surfaces() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = MultiSpanProcessor::new(vec![
+            Box::new(FailingShutdownProcessor),
+            Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+        ]);
+
+        let err = processor.shutdown().unwrap_err();
+        assert!(err.to_string().contains("shutdown failed"), "{err}");
+        assert!(processor.force_flush().is_ok());
+    }
+
+    #[test]
+    fn combines_the_status_of_processors() {
+        let status = |is_shutdown, queue_depth, queue_full| ProcessorStatus {
+            queue_depth: Some(queue_depth),
+            dropped_spans: Some(DroppedSpanCounts {
+                queue_full,
+                shutdown: 0,
+            }),
+            ..ProcessorStatus::new("inner", is_shutdown)
+        };
+        let processor = MultiSpanProcessor::new(vec![
+            Box::new(StatusProcessor(status(false, 2, 1))),
+            Box::new(FailingShutdownProcessor),
+            Box::new(StatusProcessor(status(true, 3, 4))),
+        ]);
+
+        let combined = processor.status().unwrap();
+        assert_eq!(combined.name, processor.name());
+        assert!(combined.is_shutdown);
+        assert_eq!(combined.queue_depth, Some(5));
+        assert_eq!(combined.dropped_spans.unwrap().queue_full, 5);
+
+        let without_status = MultiSpanProcessor::new(vec![Box::new(FailingShutdownProcessor)]);
+        assert!(without_status.status().is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::TraceResult;
+use opentelemetry::Context;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+type RewriteFn = Arc<dyn Fn(&str) -> Cow<'_, str> + Send + Sync>;
+
+/// Function rewriting the name of ended spans.
+#[derive(Clone)]
+struct Rewrite(RewriteFn);
+
+impl fmt::Debug for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Rewrite")
+    }
+}
+
+/// A [`SpanProcessor`] rewriting the name of ended spans before forwarding
+/// them to the processor it wraps, e.g. to normalize `GET /users/12345` to
+/// `GET /users/{id}` and keep personal data and high cardinality names out
+/// of the backend without changing the instrumentation.
+///
+/// Only the ended copy of the span handed to the wrapped processor is
+/// renamed, the span itself and the processors not wrapped keep the
+/// original name. Names the function returns unchanged are kept as they are
+/// without allocating. Every other method is forwarded unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{SpanNameRewritingProcessor, SpanProcessor, TracerProvider};
+/// use std::borrow::Cow;
+///
+/// fn init_tracing(processor: Box<dyn SpanProcessor>) -> TracerProvider {
+///     TracerProvider::builder()
+///         .with_span_processor(SpanNameRewritingProcessor::new(processor, |name| {
+///             match name.strip_prefix("GET /users/") {
+///                 Some(_) => Cow::Borrowed("GET /users/{id}"),
+///                 None => Cow::Borrowed(name),
+///             }
+///         }))
+///         .build()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SpanNameRewritingProcessor {
+    inner: Box<dyn SpanProcessor>,
+    rewrite: Rewrite,
+}
+
+impl SpanNameRewritingProcessor {
+    /// Create a processor forwarding spans to `inner`, renamed by `rewrite`.
+    pub fn new<F>(inner: Box<dyn SpanProcessor>, rewrite: F) -> Self
+    where
+        F: Fn(&str) -> Cow<'_, str> + Send + Sync + 'static,
+    {
+        SpanNameRewritingProcessor {
+            inner,
+            rewrite: Rewrite(Arc::new(rewrite)),
+        }
+    }
+}
+
+impl SpanProcessor for SpanNameRewritingProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, mut span: SpanData) -> OnEndStatus {
+        let rewritten = match (self.rewrite.0)(&span.name) {
+            Cow::Borrowed(name) if name == span.name.as_ref() => None,
+            name => Some(name.into_owned()),
+        };
+        if let Some(name) = rewritten {
+            span.name = Cow::Owned(name);
+        }
+        self.inner.try_on_end(span)
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.force_flush_with_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.inner.shutdown()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource)
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::SpanNameRewritingProcessor;
+    use crate::testing::trace::InMemorySpanExporterBuilder;
+    use crate::trace::{SimpleSpanProcessor, TracerProvider};
+    use opentelemetry::trace::{Span, Tracer, TracerProvider as _};
+    use std::borrow::Cow;
+
+    #[test]
+    fn rewrites_exported_names() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let provider = TracerProvider::builder()
+            .with_span_processor(SpanNameRewritingProcessor::new(
+                Box::new(SimpleSpanProcessor::new(Box::new(exporter.clone()))),
+                |name| match name.strip_prefix("GET /users/") {
+                    Some(_) => Cow::Borrowed("GET /users/{id}"),
+                    None => Cow::Borrowed(name),
+                },
+            ))
+            .build();
+        let tracer = provider.tracer("test");
+
+        let mut span = tracer.start("GET /users/12345");
+        span.end();
+        tracer.start("GET /health").end();
+
+        let names: Vec<_> = exporter
+            .get_finished_spans()
+            .unwrap()
+            .into_iter()
+            .map(|span| span.name)
+            .collect();
+        assert_eq!(names, vec!["GET /users/{id}", "GET /health"]);
+    }
+}
@@ -0,0 +1,305 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Self-telemetry reported by a [`SpanProcessor`].
+///
+/// See [`SpanProcessor::stats`] and [`TracerProvider::telemetry_snapshot`].
+///
+/// [`SpanProcessor`]: crate::trace::SpanProcessor
+/// [`SpanProcessor::stats`]: crate::trace::SpanProcessor::stats
+/// [`TracerProvider::telemetry_snapshot`]: crate::trace::TracerProvider::telemetry_snapshot
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProcessorStats {
+    /// Number of spans accepted by the processor and not yet handed to the
+    /// exporter.
+    pub queue_depth: usize,
+    /// Number of spans handed to the exporter in batches that were exported
+    /// successfully.
+    pub exported_spans: u64,
+    /// Number of spans dropped by the processor, by reason.
+    pub dropped_spans: DroppedSpanCounts,
+    /// Number of calls to the exporter.
+    pub exports: u64,
+    /// Number of calls to the exporter that failed or timed out.
+    pub export_failures: u64,
+    /// Duration of the most recent call to the exporter, if any.
+    pub last_export_latency: Option<Duration>,
+    /// Total duration of the calls to the exporter.
+    pub total_export_latency: Duration,
+}
+
+/// A call to the exporter of a [`BatchSpanProcessor`], passed to the callback
+/// registered with [`BatchSpanProcessorBuilder::with_on_export`].
+///
+/// [`BatchSpanProcessor`]: crate::trace::BatchSpanProcessor
+/// [`BatchSpanProcessorBuilder::with_on_export`]: crate::trace::BatchSpanProcessorBuilder::with_on_export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExportMetrics {
+    /// Number of spans in the exported batch.
+    pub batch_size: usize,
+    /// Duration of the call to the exporter, up to the export timeout.
+    pub duration: Duration,
+    /// Whether the batch was exported successfully, `false` if the export
+    /// failed or timed out.
+    pub success: bool,
+}
+
+/// Number of spans dropped by a span processor, by reason.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DroppedSpanCounts {
+    /// Spans dropped because the processor queue was full.
+    pub queue_full: u64,
+    /// Spans dropped because the processor had been shut down.
+    pub shutdown: u64,
+}
+
+/// Health of a span processor, cheap enough to compute from a readiness
+/// endpoint.
+///
+/// See [`SpanProcessor::status`] and [`TracerProvider::pipeline_status`].
+///
+/// [`SpanProcessor::status`]: crate::trace::SpanProcessor::status
+/// [`TracerProvider::pipeline_status`]: crate::trace::TracerProvider::pipeline_status
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProcessorStatus {
+    /// The [name](crate::trace::SpanProcessor::name) of the processor.
+    pub name: String,
+    /// Whether the processor has been shut down.
+    pub is_shutdown: bool,
+    /// Number of spans waiting to be exported, for processors queueing spans.
+    pub queue_depth: Option<usize>,
+    /// Number of spans dropped so far, for processors that may drop spans.
+    pub dropped_spans: Option<DroppedSpanCounts>,
+}
+
+impl ProcessorStatus {
+    /// A status with only the processor name and shutdown state.
+    pub fn new(name: impl Into<String>, is_shutdown: bool) -> Self {
+        ProcessorStatus {
+            name: name.into(),
+            is_shutdown,
+            queue_depth: None,
+            dropped_spans: None,
+        }
+    }
+
+    /// The status of a processor named `name` forwarding spans to processors
+    /// with `statuses`, `None` if none of them reports a status.
+    ///
+    /// It is shut down if any of them is, and the queue depths and dropped
+    /// spans of those reporting them are summed.
+    pub(crate) fn combined(
+        name: impl Into<String>,
+        statuses: impl IntoIterator<Item = Option<ProcessorStatus>>,
+    ) -> Option<Self> {
+        statuses
+            .into_iter()
+            .flatten()
+            .reduce(|total, status| ProcessorStatus {
+                is_shutdown: total.is_shutdown || status.is_shutdown,
+                queue_depth: sum(total.queue_depth, status.queue_depth, |a, b| a + b),
+                dropped_spans: sum(total.dropped_spans, status.dropped_spans, |a, b| {
+                    DroppedSpanCounts {
+                        queue_full: a.queue_full + b.queue_full,
+                        shutdown: a.shutdown + b.shutdown,
+                    }
+                }),
+                ..total
+            })
+            .map(|status| ProcessorStatus {
+                name: name.into(),
+                ..status
+            })
+    }
+}
+
+/// `a + b`, where a missing value counts as nothing.
+fn sum<T>(a: Option<T>, b: Option<T>, add: impl FnOnce(T, T) -> T) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(add(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Self-telemetry of every span processor of a [`TracerProvider`].
+///
+/// See [`TracerProvider::telemetry_snapshot`].
+///
+/// [`TracerProvider`]: crate::trace::TracerProvider
+/// [`TracerProvider::telemetry_snapshot`]: crate::trace::TracerProvider::telemetry_snapshot
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProviderTelemetry {
+    /// Stats of each span processor, in the order the processors were
+    /// registered. `None` for processors that do not report stats.
+    pub processors: Vec<Option<ProcessorStats>>,
+    /// Sum of the stats of every processor reporting stats. The last export
+    /// latency is the largest of the processors' latest export latencies.
+    pub total: ProcessorStats,
+}
+
+impl ProviderTelemetry {
+    pub(crate) fn new(processors: Vec<Option<ProcessorStats>>) -> Self {
+        let total = processors
+            .iter()
+            .flatten()
+            .fold(ProcessorStats::default(), |total, stats| ProcessorStats {
+                queue_depth: total.queue_depth + stats.queue_depth,
+                exported_spans: total.exported_spans + stats.exported_spans,
+                dropped_spans: DroppedSpanCounts {
+                    queue_full: total.dropped_spans.queue_full + stats.dropped_spans.queue_full,
+                    shutdown: total.dropped_spans.shutdown + stats.dropped_spans.shutdown,
+                },
+                exports: total.exports + stats.exports,
+                export_failures: total.export_failures + stats.export_failures,
+                last_export_latency: total.last_export_latency.max(stats.last_export_latency),
+                total_export_latency: total.total_export_latency + stats.total_export_latency,
+            });
+
+        ProviderTelemetry { processors, total }
+    }
+}
+
+/// Counters shared between a batch processor and its worker task.
+#[derive(Debug, Default)]
+pub(crate) struct BatchStats {
+    queue_depth: AtomicUsize,
+    exported_spans: AtomicU64,
+    dropped_queue_full: AtomicU64,
+    dropped_shutdown: AtomicU64,
+    exports: AtomicU64,
+    export_failures: AtomicU64,
+    // nanoseconds + 1, so that 0 means no export happened yet
+    last_export_latency: AtomicU64,
+    // nanoseconds
+    total_export_latency: AtomicU64,
+    is_shutdown: AtomicBool,
+}
+
+impl BatchStats {
+    pub(crate) fn record_enqueued(&self) {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped_queue_full(&self) {
+        self.dropped_queue_full.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped_shutdown(&self) {
+        self.dropped_shutdown.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records `count` spans leaving the queue to be exported.
+    pub(crate) fn record_dequeued(&self, count: usize) {
+        self.queue_depth.fetch_sub(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_export(&self, count: usize, success: bool, latency: Duration) {
+        self.exports.fetch_add(1, Ordering::Relaxed);
+        if success {
+            self.exported_spans
+                .fetch_add(count as u64, Ordering::Relaxed);
+        } else {
+            self.export_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX - 1);
+        self.last_export_latency.store(nanos + 1, Ordering::Relaxed);
+        self.total_export_latency
+            .fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// Returns whether the processor was already shut down.
+    pub(crate) fn record_shutdown(&self) -> bool {
+        self.is_shutdown.swap(true, Ordering::Relaxed)
+    }
+
+    /// Undoes [`record_shutdown`](Self::record_shutdown) when the shutdown
+    /// couldn't be started.
+    pub(crate) fn cancel_shutdown(&self) {
+        self.is_shutdown.store(false, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::Relaxed)
+    }
+
+    /// Resets the counters, keeping the queue depth and shutdown state.
+    pub(crate) fn reset(&self) {
+        for counter in [
+            &self.exported_spans,
+            &self.dropped_queue_full,
+            &self.dropped_shutdown,
+            &self.exports,
+            &self.export_failures,
+            &self.last_export_latency,
+            &self.total_export_latency,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ProcessorStats {
+        let last_export_latency = match self.last_export_latency.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos - 1)),
+        };
+        ProcessorStats {
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+            exported_spans: self.exported_spans.load(Ordering::Relaxed),
+            dropped_spans: DroppedSpanCounts {
+                queue_full: self.dropped_queue_full.load(Ordering::Relaxed),
+                shutdown: self.dropped_shutdown.load(Ordering::Relaxed),
+            },
+            exports: self.exports.load(Ordering::Relaxed),
+            export_failures: self.export_failures.load(Ordering::Relaxed),
+            last_export_latency,
+            total_export_latency: Duration::from_nanos(
+                self.total_export_latency.load(Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
+/// Read-only handle to the queue depth of a [`BatchSpanProcessor`], returned
+/// by [`BatchSpanProcessor::queue_depth_gauge`].
+///
+/// The gauge stays valid, and keeps reporting the last depth, after the
+/// processor is dropped.
+///
+/// [`BatchSpanProcessor`]: crate::trace::BatchSpanProcessor
+/// [`BatchSpanProcessor::queue_depth_gauge`]: crate::trace::BatchSpanProcessor::queue_depth_gauge
+#[derive(Clone, Debug)]
+pub struct QueueDepthGauge {
+    stats: Arc<BatchStats>,
+    capacity: usize,
+}
+
+impl QueueDepthGauge {
+    pub(crate) fn new(stats: Arc<BatchStats>, capacity: usize) -> Self {
+        QueueDepthGauge { stats, capacity }
+    }
+
+    /// Number of spans queued and not yet handed to the exporter.
+    pub fn depth(&self) -> usize {
+        self.stats.queue_depth.load(Ordering::Relaxed)
+    }
+
+    /// Maximum number of spans the queue holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Fraction of the queue in use, from `0.0` when empty to `1.0` when
+    /// full.
+    pub fn fill_fraction(&self) -> f64 {
+        if self.capacity == 0 {
+            return 1.0;
+        }
+        (self.depth() as f64 / self.capacity as f64).min(1.0)
+    }
+}
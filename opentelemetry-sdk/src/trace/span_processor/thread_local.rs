@@ -0,0 +1,415 @@
+use crate::export::trace::SpanData;
+use crate::resource::Resource;
+use crate::trace::{OnEndStatus, ProcessorStats, ProcessorStatus, Span, SpanProcessor};
+use futures_util::future::BoxFuture;
+use opentelemetry::trace::TraceResult;
+use opentelemetry::Context;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// Default number of spans a thread buffers before forwarding them.
+const DEFAULT_CHUNK_SIZE: usize = 64;
+
+/// Default interval between two flushes of the buffers of every thread.
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+static NEXT_PROCESSOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Buffers of the current thread, by processor id. Dropping them when the
+    /// thread exits hands the spans they still hold over to other threads.
+    /// The buffers of dropped processors are removed when the thread buffers
+    /// spans for a new processor.
+    static BUFFERS: RefCell<HashMap<usize, LocalBuffer>> = RefCell::new(HashMap::new());
+}
+
+type Buffer = Arc<Mutex<Vec<SpanData>>>;
+
+/// A [`SpanProcessor`] buffering ended spans in the thread they end on and
+/// forwarding them to the processor it wraps in chunks, so that threads
+/// ending many spans concurrently don't all contend on the wrapped
+/// processor, e.g. on the channel of a [`BatchSpanProcessor`].
+///
+/// Each thread forwards its buffer once it holds [`with_chunk_size`] spans,
+/// `64` by default. The buffers of every thread are forwarded every
+/// [`with_flush_interval`], 5 seconds by default, from a background thread,
+/// and on [`force_flush`] and [`shutdown`], so spans ended on idle threads
+/// are not held back. When a thread exits, the spans left in its buffer are handed over
+/// and forwarded along with the next chunk of another thread, or on the next
+/// flush. Spans ended after `shutdown` are forwarded right away.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry_sdk::trace::{SpanProcessor, ThreadLocalBufferingProcessor, TracerProvider};
+///
+/// fn init_tracing(processor: Box<dyn SpanProcessor>) -> TracerProvider {
+///     TracerProvider::builder()
+///         .with_span_processor(ThreadLocalBufferingProcessor::new(processor).with_chunk_size(128))
+///         .build()
+/// }
+/// ```
+///
+/// [`BatchSpanProcessor`]: crate::trace::BatchSpanProcessor
+/// [`with_chunk_size`]: ThreadLocalBufferingProcessor::with_chunk_size
+/// [`with_flush_interval`]: ThreadLocalBufferingProcessor::with_flush_interval
+/// [`force_flush`]: SpanProcessor::force_flush
+/// [`shutdown`]: SpanProcessor::shutdown
+pub struct ThreadLocalBufferingProcessor {
+    id: usize,
+    chunk_size: usize,
+    flush_interval: Duration,
+    is_shutdown: AtomicBool,
+    shared: Arc<Shared>,
+    /// Stops the thread flushing the buffers when dropped, set once a thread
+    /// buffers spans
+    flusher: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+/// State shared with the buffers of each thread.
+struct Shared {
+    inner: Box<dyn SpanProcessor>,
+    buffers: Mutex<Vec<Weak<Mutex<Vec<SpanData>>>>>,
+    /// Spans left by threads which exited
+    orphans: Mutex<Vec<SpanData>>,
+}
+
+impl Shared {
+    fn forward(&self, spans: Vec<SpanData>) {
+        for span in spans {
+            self.inner.on_end(span);
+        }
+    }
+
+    fn forward_orphans(&self) {
+        let spans = std::mem::take(&mut *self.orphans.lock().unwrap_or_else(|e| e.into_inner()));
+        self.forward(spans);
+    }
+
+    /// Forward the spans buffered by every thread.
+    fn flush_buffers(&self) {
+        let buffers = self
+            .buffers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        for buffer in buffers {
+            let spans = std::mem::take(&mut *buffer.lock().unwrap_or_else(|e| e.into_inner()));
+            self.forward(spans);
+        }
+        self.forward_orphans();
+    }
+}
+
+/// Buffer of a thread for one processor.
+struct LocalBuffer {
+    spans: Buffer,
+    shared: Weak<Shared>,
+}
+
+impl Drop for LocalBuffer {
+    fn drop(&mut self) {
+        // the thread is exiting, the spans are forwarded by other threads
+        // rather than from a thread local destructor
+        if let Some(shared) = self.shared.upgrade() {
+            let mut spans =
+                std::mem::take(&mut *self.spans.lock().unwrap_or_else(|e| e.into_inner()));
+            shared
+                .orphans
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .append(&mut spans);
+        }
+    }
+}
+
+impl fmt::Debug for ThreadLocalBufferingProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadLocalBufferingProcessor")
+            .field("inner", &self.shared.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("flush_interval", &self.flush_interval)
+            .finish()
+    }
+}
+
+impl ThreadLocalBufferingProcessor {
+    /// Create a processor buffering spans per thread before forwarding them
+    /// to `inner`.
+    pub fn new(inner: Box<dyn SpanProcessor>) -> Self {
+        ThreadLocalBufferingProcessor {
+            id: NEXT_PROCESSOR_ID.fetch_add(1, Ordering::Relaxed),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            is_shutdown: AtomicBool::new(false),
+            shared: Arc::new(Shared {
+                inner,
+                buffers: Mutex::new(Vec::new()),
+                orphans: Mutex::new(Vec::new()),
+            }),
+            flusher: Mutex::new(None),
+        }
+    }
+
+    /// Set the number of spans each thread buffers before forwarding them.
+    ///
+    /// A chunk size of `0` or `1` forwards spans as soon as they end.
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        ThreadLocalBufferingProcessor { chunk_size, ..self }
+    }
+
+    /// Set the interval between two flushes of the buffers of every thread,
+    /// forwarding the spans of threads which don't end enough spans to fill
+    /// a chunk.
+    pub fn with_flush_interval(self, flush_interval: Duration) -> Self {
+        ThreadLocalBufferingProcessor {
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Create the buffer of the current thread.
+    fn register(&self) -> LocalBuffer {
+        let spans = Buffer::default();
+        let mut buffers = self
+            .shared
+            .buffers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        // forget the buffers of threads which exited
+        buffers.retain(|buffer| buffer.strong_count() > 0);
+        buffers.push(Arc::downgrade(&spans));
+        self.start_flusher();
+        LocalBuffer {
+            spans,
+            shared: Arc::downgrade(&self.shared),
+        }
+    }
+
+    /// Start the thread flushing the buffers every `flush_interval`, unless
+    /// it's running. It's started with the first buffer rather than in `new`
+    /// so that the state isn't shared before the provider sets the resource.
+    fn start_flusher(&self) {
+        let mut flusher = self.flusher.lock().unwrap_or_else(|e| e.into_inner());
+        if flusher.is_some() || self.is_shutdown.load(Ordering::Relaxed) {
+            return;
+        }
+        let (stop, stopped) = mpsc::channel::<()>();
+        let shared = Arc::downgrade(&self.shared);
+        let flush_interval = self.flush_interval;
+        thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(flush_interval) {
+                match shared.upgrade() {
+                    Some(shared) => shared.flush_buffers(),
+                    None => break,
+                }
+            }
+        });
+        *flusher = Some(stop);
+    }
+
+    fn stop_flusher(&self) {
+        self.flusher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+    }
+}
+
+impl SpanProcessor for ThreadLocalBufferingProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.shared.inner.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.try_on_end(span);
+    }
+
+    fn try_on_end(&self, span: SpanData) -> OnEndStatus {
+        if self.is_shutdown.load(Ordering::Relaxed) {
+            return self.shared.inner.try_on_end(span);
+        }
+
+        let mut span = Some(span);
+        let full = BUFFERS.try_with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            if !buffers.contains_key(&self.id) {
+                // forget the buffers of dropped processors
+                buffers.retain(|_, local| local.shared.strong_count() > 0);
+            }
+            let local = buffers.entry(self.id).or_insert_with(|| self.register());
+            let mut spans = local.spans.lock().unwrap_or_else(|e| e.into_inner());
+            spans.extend(span.take());
+            if spans.len() >= self.chunk_size {
+                Some(std::mem::take(&mut *spans))
+            } else {
+                None
+            }
+        });
+        match (span, full) {
+            // the buffers of this thread are already dropped, the thread is
+            // exiting
+            (Some(span), _) => self
+                .shared
+                .orphans
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(span),
+            (None, Ok(Some(spans))) => {
+                self.shared.forward(spans);
+                self.shared.forward_orphans();
+            }
+            (None, _) => {}
+        }
+        OnEndStatus::Accepted
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.shared.flush_buffers();
+        self.shared.inner.force_flush()
+    }
+
+    fn force_flush_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.shared.flush_buffers();
+        self.shared.inner.force_flush_with_timeout(timeout)
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        self.stop_flusher();
+        self.shared.flush_buffers();
+        self.shared.inner.shutdown()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> TraceResult<()> {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        self.stop_flusher();
+        self.shared.flush_buffers();
+        self.shared.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn warmup(&self) -> BoxFuture<'_, TraceResult<()>> {
+        self.shared.inner.warmup()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        // the state is only shared once a thread buffered spans, which
+        // happens after the provider sets the resource
+        if let Some(shared) = Arc::get_mut(&mut self.shared) {
+            shared.inner.set_resource(resource)
+        }
+    }
+
+    fn stats(&self) -> Option<ProcessorStats> {
+        self.shared.inner.stats()
+    }
+
+    fn reset_stats(&self) {
+        self.shared.inner.reset_stats()
+    }
+
+    fn status(&self) -> Option<ProcessorStatus> {
+        self.shared.inner.status().map(|status| ProcessorStatus {
+            name: self.name().to_string(),
+            ..status
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::{ThreadLocalBufferingProcessor, BUFFERS};
+    use crate::testing::trace::{new_test_export_span_data, InMemorySpanExporterBuilder};
+    use crate::trace::{SimpleSpanProcessor, SpanProcessor};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn forwards_buffers_of_exited_threads() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = Arc::new(
+            ThreadLocalBufferingProcessor::new(Box::new(SimpleSpanProcessor::new(Box::new(
+                exporter.clone(),
+            ))))
+            .with_chunk_size(16),
+        );
+
+        let handles = (0..8)
+            .map(|_| {
+                let processor = processor.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        processor.on_end(new_test_export_span_data());
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        // 100 isn't a multiple of the chunk size, the last spans of each
+        // thread were handed over when it exited
+        assert!(exporter.get_finished_spans().unwrap().len() < 800);
+
+        assert!(processor.force_flush().is_ok());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 800);
+    }
+
+    #[test]
+    fn force_flush_forwards_buffers_of_live_threads() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = ThreadLocalBufferingProcessor::new(Box::new(SimpleSpanProcessor::new(
+            Box::new(exporter.clone()),
+        )));
+
+        for _ in 0..10 {
+            processor.on_end(new_test_export_span_data());
+        }
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+
+        assert!(processor.force_flush().is_ok());
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn flushes_buffers_of_idle_threads_every_interval() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let processor = ThreadLocalBufferingProcessor::new(Box::new(SimpleSpanProcessor::new(
+            Box::new(exporter.clone()),
+        )))
+        .with_flush_interval(Duration::from_millis(10));
+
+        processor.on_end(new_test_export_span_data());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while exporter.get_finished_spans().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "buffer was not flushed");
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(processor.shutdown().is_ok());
+    }
+
+    #[test]
+    fn forgets_buffers_of_dropped_processors() {
+        thread::spawn(|| {
+            for _ in 0..3 {
+                let processor = ThreadLocalBufferingProcessor::new(Box::new(
+                    SimpleSpanProcessor::new(Box::new(InMemorySpanExporterBuilder::new().build())),
+                ));
+                processor.on_end(new_test_export_span_data());
+            }
+            assert_eq!(BUFFERS.with(|buffers| buffers.borrow().len()), 1);
+        })
+        .join()
+        .unwrap();
+    }
+}
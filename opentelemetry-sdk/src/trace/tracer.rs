@@ -16,9 +16,13 @@ use crate::{
     InstrumentationLibrary,
 };
 use opentelemetry::{
-    trace::{SamplingDecision, SpanBuilder, SpanContext, SpanKind, TraceContextExt, TraceFlags},
-    Context, KeyValue,
+    trace::{
+        SamplingDecision, Span as _, SpanBuilder, SpanContext, SpanKind, TraceContextExt,
+        TraceFlags,
+    },
+    Context, Key, KeyValue,
 };
+use std::collections::HashSet;
 use std::fmt;
 use std::sync::Arc;
 
@@ -27,6 +31,53 @@ use std::sync::Arc;
 pub struct Tracer {
     instrumentation_lib: Arc<InstrumentationLibrary>,
     provider: TracerProvider,
+    sampler: Option<Arc<dyn ShouldSample>>,
+    default_span_attributes: Arc<DefaultSpanAttributes>,
+}
+
+/// Attributes added to every span a tracer starts, see
+/// [`Tracer::with_default_span_attributes`], also used for the resource
+/// attributes copied onto spans.
+#[derive(Debug, Default)]
+pub(crate) struct DefaultSpanAttributes {
+    // the first attribute of each key
+    attributes: Vec<KeyValue>,
+    // keys of `attributes`, to find the ones spans set themselves without
+    // going through every attribute
+    keys: HashSet<Key>,
+}
+
+impl DefaultSpanAttributes {
+    pub(crate) fn new(attributes: Vec<KeyValue>) -> Self {
+        let mut keys = HashSet::with_capacity(attributes.len());
+        let attributes = attributes
+            .into_iter()
+            .filter(|kv| keys.insert(kv.key.clone()))
+            .collect();
+        DefaultSpanAttributes { attributes, keys }
+    }
+
+    pub(crate) fn attributes(&self) -> &[KeyValue] {
+        &self.attributes
+    }
+
+    /// Adds the attributes to `attributes`, but those of the keys it has.
+    pub(crate) fn add_to(&self, attributes: &mut Vec<KeyValue>) {
+        if self.attributes.is_empty() {
+            return;
+        }
+        let overridden: HashSet<Key> = attributes
+            .iter()
+            .filter(|kv| self.keys.contains(&kv.key))
+            .map(|kv| kv.key.clone())
+            .collect();
+        attributes.extend(
+            self.attributes
+                .iter()
+                .filter(|kv| !overridden.contains(&kv.key))
+                .cloned(),
+        );
+    }
 }
 
 impl fmt::Debug for Tracer {
@@ -42,16 +93,62 @@ impl fmt::Debug for Tracer {
 
 impl Tracer {
     /// Create a new tracer (used internally by `TracerProvider`s).
+    ///
+    /// `sampler` overrides the sampler of the provider config for this tracer.
+    /// `default_span_attributes` are added to every span the tracer starts,
+    /// after the attributes of the span builder and of the sampler, so they
+    /// are the first to be dropped when a span has too many attributes.
     pub(crate) fn new(
         instrumentation_lib: Arc<InstrumentationLibrary>,
         provider: TracerProvider,
+        sampler: Option<Arc<dyn ShouldSample>>,
+        default_span_attributes: Arc<DefaultSpanAttributes>,
     ) -> Self {
         Tracer {
             instrumentation_lib,
             provider,
+            sampler,
+            default_span_attributes,
         }
     }
 
+    /// Returns a tracer of the same instrumentation library adding
+    /// `attributes` to every span it starts, e.g. the component or the tenant
+    /// the tracer is used for.
+    ///
+    /// Attributes set on the span builder take precedence over the default
+    /// ones with the same key. The default attributes are subject to the
+    /// [`SpanLimits`] like the others, and added after the attributes of the
+    /// span builder and of the sampler, so they are the first to be dropped
+    /// when a span has too many attributes.
+    ///
+    /// Tracers are cached by library and default attributes, so other tracers
+    /// of the provider, including the ones with the same name, are not
+    /// affected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::trace::TracerProvider as _;
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_sdk::trace::TracerProvider;
+    ///
+    /// let provider = TracerProvider::builder().allow_no_processors().build();
+    /// let tracer = provider
+    ///     .tracer("checkout")
+    ///     .with_default_span_attributes(vec![KeyValue::new("component", "cart")]);
+    /// ```
+    pub fn with_default_span_attributes<T>(&self, attributes: T) -> Self
+    where
+        T: IntoIterator<Item = KeyValue>,
+    {
+        self.provider.tracer_for(
+            self.instrumentation_lib.clone(),
+            attributes.into_iter().collect(),
+            self.provider.is_shutdown(),
+        )
+    }
+
     /// TracerProvider associated with this tracer.
     pub(crate) fn provider(&self) -> &TracerProvider {
         &self.provider
@@ -74,11 +171,20 @@ impl Tracer {
         for extra_attr in attrs {
             attribute_options.push(extra_attr);
         }
+        self.default_span_attributes.add_to(&mut attribute_options);
+        let provider = self.provider();
+        provider
+            .resource_attributes_on_spans()
+            .add_to(&mut attribute_options);
         let span_attributes_limit = span_limits.max_attributes_per_span as usize;
         let dropped_attributes_count = attribute_options
             .len()
             .saturating_sub(span_attributes_limit);
         attribute_options.truncate(span_attributes_limit);
+        let marker = &provider.config().attribute_value_truncation_marker;
+        span_limits.truncate_attribute_values(&mut attribute_options, marker);
+        let truncation = provider.truncation_counter();
+        truncation.record_attributes(dropped_attributes_count);
         let dropped_attributes_count = dropped_attributes_count as u32;
 
         // Links are available as Option<Vec<Link>> in the builder
@@ -94,11 +200,14 @@ impl Tracer {
         let span_links: SpanLinks = if let Some(mut links) = builder.links.take() {
             let dropped_count = links.len().saturating_sub(spans_links_limit);
             links.truncate(spans_links_limit);
+            truncation.record_links(dropped_count);
             let link_attributes_limit = span_limits.max_attributes_per_link as usize;
             for link in links.iter_mut() {
                 let dropped_attributes_count =
                     link.attributes.len().saturating_sub(link_attributes_limit);
                 link.attributes.truncate(link_attributes_limit);
+                span_limits.truncate_attribute_values(&mut link.attributes, marker);
+                truncation.record_link_attributes(dropped_attributes_count);
                 link.dropped_attributes_count = dropped_attributes_count as u32;
             }
             SpanLinks {
@@ -118,12 +227,13 @@ impl Tracer {
             ..
         } = builder;
 
-        let start_time = start_time.unwrap_or_else(opentelemetry::time::now);
+        let start_time = start_time.unwrap_or_else(|| provider.config().clock.now());
         let end_time = end_time.unwrap_or(start_time);
         let spans_events_limit = span_limits.max_events_per_span as usize;
         let span_events: SpanEvents = if let Some(mut events) = events {
             let dropped_count = events.len().saturating_sub(spans_events_limit);
             events.truncate(spans_events_limit);
+            truncation.record_events(dropped_count);
             let event_attributes_limit = span_limits.max_attributes_per_event as usize;
             for event in events.iter_mut() {
                 let dropped_attributes_count = event
@@ -131,6 +241,8 @@ impl Tracer {
                     .len()
                     .saturating_sub(event_attributes_limit);
                 event.attributes.truncate(event_attributes_limit);
+                span_limits.truncate_attribute_values(&mut event.attributes, marker);
+                truncation.record_event_attributes(dropped_attributes_count);
                 event.dropped_attributes_count = dropped_attributes_count as u32;
             }
             SpanEvents {
@@ -172,7 +284,10 @@ impl Tracer {
     // Note: this is necessary for tracing-opentelemetry's `PreSampledTracer`.
     #[doc(hidden)]
     pub fn should_sample(&self) -> &dyn ShouldSample {
-        &*self.provider.config().sampler
+        match &self.sampler {
+            Some(sampler) => &**sampler,
+            None => &*self.provider.config().sampler,
+        }
     }
 }
 
@@ -191,6 +306,7 @@ impl opentelemetry::trace::Tracer for Tracer {
         let provider = self.provider();
         // no point start a span if the tracer provider has already being shutdown
         if provider.is_shutdown() {
+            provider.record_post_shutdown_span();
             return Span::new(
                 SpanContext::empty_context(),
                 None,
@@ -228,7 +344,7 @@ impl opentelemetry::trace::Tracer for Tracer {
         let samplings_result = if let Some(sr) = builder.sampling_result.take() {
             sr
         } else {
-            config.sampler.should_sample(
+            self.should_sample().should_sample(
                 Some(parent_cx),
                 trace_id,
                 &builder.name,
@@ -239,7 +355,9 @@ impl opentelemetry::trace::Tracer for Tracer {
         };
 
         let trace_flags = parent_cx.span().span_context().trace_flags();
-        let trace_state = samplings_result.trace_state;
+        let trace_state = config
+            .trace_state_merge_policy
+            .merge(psc.trace_state(), samplings_result.trace_state);
         let span_limits = config.span_limits;
         // Build optional inner context, `None` if not recording.
         let mut span = match samplings_result.decision {
@@ -276,16 +394,28 @@ impl opentelemetry::trace::Tracer for Tracer {
                 )
             }
             SamplingDecision::Drop => {
+                if let Some(on_sampling_drop) = provider.on_sampling_drop() {
+                    on_sampling_drop
+                        .call(&builder.name, builder.attributes.as_deref().unwrap_or(&[]));
+                }
                 let span_context =
                     SpanContext::new(trace_id, span_id, TraceFlags::default(), false, trace_state);
                 Span::new(span_context, None, self.clone(), span_limits)
             }
         };
 
-        // Call `on_start` for all processors
-        for processor in provider.span_processors() {
+        // Call `on_start` for all processors, in order and with the same span
+        // so each of them sees the changes made by the previous ones
+        let processors = provider.span_processors();
+        for processor in processors.iter() {
             processor.on_start(&mut span, parent_cx)
         }
+        if span.is_suppressed() {
+            span.discard();
+        } else if span.is_recording() {
+            provider.span_counter().record_start();
+            span.set_processors(processors);
+        }
 
         span
     }
@@ -294,16 +424,22 @@ impl opentelemetry::trace::Tracer for Tracer {
 #[cfg(all(test, feature = "testing", feature = "trace"))]
 mod tests {
     use crate::{
-        testing::trace::TestSpan,
-        trace::{Config, Sampler, ShouldSample},
+        export::trace::SpanData,
+        testing::trace::{InMemorySpanExporterBuilder, TestSpan},
+        trace::{
+            Clock, Config, RecordOnlySampler, Sampler, ShouldSample, SpanProcessor,
+            TraceStateMergePolicy,
+        },
     };
     use opentelemetry::{
         trace::{
             Link, SamplingDecision, SamplingResult, Span, SpanContext, SpanId, SpanKind,
-            TraceContextExt, TraceFlags, TraceId, TraceState, Tracer, TracerProvider,
+            TraceContextExt, TraceFlags, TraceId, TraceResult, TraceState, Tracer, TracerProvider,
         },
         Context, KeyValue,
     };
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
 
     #[derive(Clone, Debug)]
     struct TestSampler {}
@@ -358,6 +494,379 @@ mod tests {
         assert_eq!(expected.get("foo"), Some("notbar"))
     }
 
+    // Sampler adding its own entries to an empty trace state.
+    #[derive(Clone, Debug)]
+    struct TraceStateSampler(Vec<(&'static str, &'static str)>);
+
+    impl ShouldSample for TraceStateSampler {
+        fn should_sample(
+            &self,
+            _parent_context: Option<&Context>,
+            _trace_id: TraceId,
+            _name: &str,
+            _span_kind: &SpanKind,
+            _attributes: &[KeyValue],
+            _links: &[Link],
+        ) -> SamplingResult {
+            SamplingResult {
+                decision: SamplingDecision::RecordAndSample,
+                attributes: Vec::new(),
+                trace_state: TraceState::from_key_value(self.0.clone()).unwrap(),
+            }
+        }
+    }
+
+    fn merged_trace_state(
+        policy: TraceStateMergePolicy,
+        parent: Vec<(String, String)>,
+        sampled: Vec<(&'static str, &'static str)>,
+    ) -> String {
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_config(
+                Config::default()
+                    .with_sampler(TraceStateSampler(sampled))
+                    .with_trace_state_merge_policy(policy),
+            )
+            .build();
+        let tracer = tracer_provider.tracer("test");
+        let parent_context = Context::new().with_span(TestSpan(SpanContext::new(
+            TraceId::from_u128(128),
+            SpanId::from_u64(64),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::from_key_value(parent).unwrap(),
+        )));
+
+        let span = tracer.start_with_context("span", &parent_context);
+        span.span_context().trace_state().header()
+    }
+
+    #[test]
+    fn trace_state_merge_policies() {
+        let parent = || {
+            vec![
+                ("parent".to_string(), "1".to_string()),
+                ("shared".to_string(), "parent".to_string()),
+            ]
+        };
+        let sampled = || vec![("vendor", "th:8"), ("shared", "sampler")];
+
+        assert_eq!(
+            merged_trace_state(TraceStateMergePolicy::Replace, parent(), sampled()),
+            "vendor=th:8,shared=sampler"
+        );
+        assert_eq!(
+            merged_trace_state(TraceStateMergePolicy::Override, parent(), sampled()),
+            "vendor=th:8,shared=sampler,parent=1"
+        );
+        assert_eq!(
+            merged_trace_state(TraceStateMergePolicy::Append, parent(), sampled()),
+            "parent=1,shared=parent,vendor=th:8"
+        );
+    }
+
+    #[test]
+    fn trace_state_is_capped_at_32_entries() {
+        let parent: Vec<_> = (0..32)
+            .map(|i| (format!("parent{i}"), i.to_string()))
+            .collect();
+
+        let merged = merged_trace_state(
+            TraceStateMergePolicy::Override,
+            parent.clone(),
+            vec![("vendor", "th:8")],
+        );
+        let entries: Vec<_> = merged.split(',').collect();
+        assert_eq!(entries.len(), 32);
+        assert_eq!(entries[0], "vendor=th:8");
+        assert_eq!(entries[31], "parent30=30");
+
+        let merged = merged_trace_state(
+            TraceStateMergePolicy::Append,
+            parent,
+            vec![("vendor", "th:8")],
+        );
+        let entries: Vec<_> = merged.split(',').collect();
+        assert_eq!(entries.len(), 32);
+        assert_eq!(entries[31], "parent31=31");
+    }
+
+    #[test]
+    fn record_only_spans_are_not_exported() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_config(Config::default().with_sampler(RecordOnlySampler))
+            .with_simple_exporter(exporter.clone())
+            .with_batch_exporter(exporter.clone(), crate::runtime::TokioCurrentThread)
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let mut span = tracer.start("local only");
+        assert!(span.is_recording());
+        assert!(!span.span_context().is_sampled());
+        span.set_attribute(KeyValue::new("debug", true));
+        assert_eq!(
+            span.exported_data().unwrap().attributes,
+            vec![KeyValue::new("debug", true)]
+        );
+        span.end();
+
+        assert!(tracer_provider.force_flush().is_ok());
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+    }
+
+    #[derive(Clone, Debug)]
+    struct RuleSampler;
+
+    impl ShouldSample for RuleSampler {
+        fn should_sample(
+            &self,
+            _parent_context: Option<&Context>,
+            _trace_id: TraceId,
+            _name: &str,
+            _span_kind: &SpanKind,
+            _attributes: &[KeyValue],
+            _links: &[Link],
+        ) -> SamplingResult {
+            SamplingResult {
+                decision: SamplingDecision::RecordAndSample,
+                attributes: vec![KeyValue::new("sampling.rule", "slow-requests")],
+                trace_state: TraceState::default(),
+            }
+        }
+    }
+
+    #[test]
+    fn sampler_attributes_are_added_to_span() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_config(Config::default().with_sampler(RuleSampler))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        tracer
+            .span_builder("sampled")
+            .with_attributes(vec![KeyValue::new("http.method", "GET")])
+            .start(&tracer)
+            .end();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(
+            finished[0].attributes,
+            vec![
+                KeyValue::new("http.method", "GET"),
+                KeyValue::new("sampling.rule", "slow-requests"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sampler_attributes_are_subject_to_span_limits() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_config(
+                Config::default()
+                    .with_sampler(RuleSampler)
+                    .with_max_attributes_per_span(1),
+            )
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        tracer
+            .span_builder("sampled")
+            .with_attributes(vec![KeyValue::new("http.method", "GET")])
+            .start(&tracer)
+            .end();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(
+            finished[0].attributes,
+            vec![KeyValue::new("http.method", "GET")]
+        );
+        assert_eq!(finished[0].dropped_attributes_count, 1);
+    }
+
+    #[test]
+    fn default_span_attributes_are_added_to_spans_of_the_tracer() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_config(Config::default().with_max_attributes_per_span(3))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = tracer_provider
+            .tracer("checkout")
+            .with_default_span_attributes(vec![
+                KeyValue::new("component", "cart"),
+                KeyValue::new("tenant", "default"),
+                KeyValue::new("region", "eu"),
+            ]);
+        // same name, but neither the same tracer nor the same cache entry
+        let other = tracer_provider.tracer("checkout");
+
+        tracer
+            .span_builder("defaults")
+            .with_attributes(vec![
+                KeyValue::new("http.method", "GET"),
+                KeyValue::new("tenant", "acme"),
+            ])
+            .start(&tracer)
+            .end();
+        other.start("no defaults").end();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(
+            finished[0].attributes,
+            vec![
+                KeyValue::new("http.method", "GET"),
+                KeyValue::new("tenant", "acme"),
+                KeyValue::new("component", "cart"),
+            ]
+        );
+        assert_eq!(finished[0].dropped_attributes_count, 1);
+        assert!(finished[1].attributes.is_empty());
+    }
+
+    #[derive(Clone, Debug)]
+    struct FixedClock(SystemTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+    }
+
+    #[test]
+    fn timestamps_are_read_from_clock() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_config(Config::default().with_clock(FixedClock(now)))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let mut span = tracer.start("replayed");
+        span.add_event("event", Vec::new());
+        span.end();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished[0].start_time, now);
+        assert_eq!(finished[0].end_time, now);
+        assert_eq!(finished[0].events[0].timestamp, now);
+    }
+
+    // Processor adding an attribute to every span it sees starting.
+    #[derive(Debug)]
+    struct EnrichingProcessor;
+
+    impl SpanProcessor for EnrichingProcessor {
+        fn on_start(&self, span: &mut crate::trace::Span, _cx: &Context) {
+            span.set_attribute(KeyValue::new("enriched", true));
+        }
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    // Processor recording the attributes of the spans it sees starting.
+    #[derive(Debug, Default)]
+    struct StartAttributesProcessor(Arc<Mutex<Vec<Vec<KeyValue>>>>);
+
+    impl SpanProcessor for StartAttributesProcessor {
+        fn on_start(&self, span: &mut crate::trace::Span, _cx: &Context) {
+            if let Some(data) = span.exported_data() {
+                self.0.lock().unwrap().push(data.attributes);
+            }
+        }
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn on_start_sees_changes_of_earlier_processors() {
+        let before = StartAttributesProcessor::default();
+        let seen_before = before.0.clone();
+        let after = StartAttributesProcessor::default();
+        let seen_after = after.0.clone();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_span_processor(before)
+            .with_span_processor(EnrichingProcessor)
+            .with_span_processor(after)
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        tracer.start("enriched").end();
+
+        assert_eq!(*seen_before.lock().unwrap(), vec![Vec::<KeyValue>::new()]);
+        assert_eq!(
+            *seen_after.lock().unwrap(),
+            vec![vec![KeyValue::new("enriched", true)]]
+        );
+    }
+
+    // Processor suppressing the spans named "noisy".
+    #[derive(Debug)]
+    struct SuppressingProcessor;
+
+    impl SpanProcessor for SuppressingProcessor {
+        fn on_start(&self, span: &mut crate::trace::Span, _cx: &Context) {
+            if span
+                .exported_data()
+                .map_or(false, |data| data.name == "noisy")
+            {
+                span.suppress();
+            }
+        }
+
+        fn on_end(&self, _span: SpanData) {}
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn processors_can_suppress_spans() {
+        let exporter = InMemorySpanExporterBuilder::new().build();
+        let tracer_provider = crate::trace::TracerProvider::builder()
+            .with_span_processor(SuppressingProcessor)
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = tracer_provider.tracer("test");
+
+        let noisy = tracer.start("noisy");
+        assert!(!noisy.is_recording());
+        assert!(!noisy.span_context().is_sampled());
+        drop(noisy);
+        tracer.start("kept").end();
+
+        let finished = exporter.get_finished_spans().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "kept");
+        assert_eq!(tracer_provider.span_metrics().active, 0);
+    }
+
     #[test]
     fn drop_parent_based_children() {
         let sampler = Sampler::ParentBased(Box::new(Sampler::AlwaysOn));
@@ -37,14 +37,17 @@ fn is_zero(v: &u64) -> bool {
 
 impl From<&opentelemetry_sdk::Resource> for Resource {
     fn from(value: &opentelemetry_sdk::Resource) -> Self {
+        let mut attributes: Vec<_> = value
+            .iter()
+            .map(|(key, value)| KeyValue {
+                key: key.clone().into(),
+                value: value.clone().into(),
+            })
+            .collect();
+        // the resource is backed by a hash map, sort for a stable output
+        attributes.sort_by(|a, b| a.key.cmp(&b.key));
         Resource {
-            attributes: value
-                .iter()
-                .map(|(key, value)| KeyValue {
-                    key: key.clone().into(),
-                    value: value.clone().into(),
-                })
-                .collect(),
+            attributes,
             dropped_attributes_count: 0,
         }
     }
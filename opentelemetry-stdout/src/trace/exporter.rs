@@ -66,6 +66,7 @@ impl opentelemetry_sdk::export::trace::SpanExporter for SpanExporter {
 pub struct SpanExporterBuilder {
     writer: Option<Box<dyn Write + Send + Sync>>,
     encoder: Option<Encoder>,
+    pretty_print: bool,
 }
 
 impl fmt::Debug for SpanExporterBuilder {
@@ -111,17 +112,141 @@ impl SpanExporterBuilder {
         self
     }
 
+    /// Write spans as indented multi-line JSON instead of compact single-line
+    /// JSON, which is easier to read while developing locally.
+    ///
+    /// Has no effect if an encoder is set with
+    /// [`SpanExporterBuilder::with_encoder`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_stdout::SpanExporterBuilder;
+    ///
+    /// let exporter = SpanExporterBuilder::default().with_pretty_print(true).build();
+    /// ```
+    pub fn with_pretty_print(mut self, pretty_print: bool) -> Self {
+        self.pretty_print = pretty_print;
+        self
+    }
+
     /// Create a span exporter with the current configuration
     pub fn build(self) -> SpanExporter {
+        let pretty_print = self.pretty_print;
         SpanExporter {
             writer: Some(self.writer.unwrap_or_else(|| Box::new(stdout()))),
             resource: Resource::empty(),
             encoder: self.encoder.unwrap_or_else(|| {
-                Box::new(|writer, spans| {
-                    serde_json::to_writer(writer, &spans)
-                        .map_err(|err| TraceError::Other(Box::new(err)))
+                Box::new(move |writer, spans| {
+                    if pretty_print {
+                        serde_json::to_writer_pretty(writer, &spans)
+                    } else {
+                        serde_json::to_writer(writer, &spans)
+                    }
+                    .map_err(|err| TraceError::Other(Box::new(err)))
                 })
             }),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SpanExporterBuilder;
+    use opentelemetry::trace::{
+        SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::{InstrumentationLibrary, KeyValue};
+    use opentelemetry_sdk::export::trace::{SpanData, SpanExporter as _};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use opentelemetry_sdk::Resource;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use std::time::SystemTime;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn span_data() -> SpanData {
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_hex("4BF92F3577B34DA6A3CE929D0E0E4736").unwrap(),
+                SpanId::from_hex("00F067AA0BA902B7").unwrap(),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::INVALID,
+            span_kind: SpanKind::Internal,
+            name: "known span".into(),
+            start_time: SystemTime::UNIX_EPOCH,
+            end_time: SystemTime::UNIX_EPOCH,
+            attributes: vec![KeyValue::new("b", "second"), KeyValue::new("a", "first")],
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_lib: InstrumentationLibrary::builder("test").build(),
+        }
+    }
+
+    fn export(pretty_print: bool) -> String {
+        let buffer = SharedBuffer::default();
+        let mut exporter = SpanExporterBuilder::default()
+            .with_writer(buffer.clone())
+            .with_pretty_print(pretty_print)
+            .build();
+        exporter.set_resource(&Resource::new([
+            KeyValue::new("service.name", "test"),
+            KeyValue::new("host.name", "localhost"),
+        ]));
+        futures_util::future::FutureExt::now_or_never(exporter.export(vec![span_data()]))
+            .unwrap()
+            .unwrap();
+        buffer.contents()
+    }
+
+    #[test]
+    fn test_compact_output() {
+        let output = export(false);
+        assert_eq!(output.lines().count(), 1);
+        assert!(output.contains(r#""traceId":"4bf92f3577b34da6a3ce929d0e0e4736""#));
+        assert!(output.contains(r#""spanId":"00f067aa0ba902b7""#));
+        assert!(output.contains(r#""name":"known span""#));
+        // span attributes keep their order, resource attributes are sorted
+        let (b, a) = (
+            output.find(r#""b""#).unwrap(),
+            output.find(r#""a""#).unwrap(),
+        );
+        assert!(b < a);
+        let host = output.find(r#""host.name""#).unwrap();
+        let service = output.find(r#""service.name""#).unwrap();
+        assert!(host < service);
+    }
+
+    #[test]
+    fn test_pretty_output() {
+        let output = export(true);
+        assert!(output.lines().count() > 1);
+        assert!(output.contains(r#""traceId": "4bf92f3577b34da6a3ce929d0e0e4736""#));
+        assert!(output.contains(r#""spanId": "00f067aa0ba902b7""#));
+        assert!(output.contains(r#""key": "a""#));
+        assert!(output.contains(r#""stringValue": "first""#));
+    }
+}